@@ -0,0 +1,76 @@
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+
+use search::problems::maze_2d::Maze2DAction;
+use search::problems::maze_2d::Maze2DCell;
+use search::problems::maze_2d::Maze2DCost;
+use search::problems::maze_2d::Maze2DSpace;
+use search::problems::maze_2d::Maze2DState;
+use search::search::SearchTree;
+use search::search::SearchTreeIndex;
+use search::search::SearchTreeNode;
+use search::space::Space;
+
+type Tree = SearchTree<Maze2DState, Maze2DAction, Maze2DCost>;
+
+/// Builds a single branch `(0,0) → (1,0) → ... → (depth,0)`.
+fn deep_tree(depth: usize) -> (Maze2DSpace, Tree, SearchTreeIndex) {
+    let space = Maze2DSpace::new_from_map(vec![vec![Maze2DCell::Empty; depth + 1]]);
+    let mut tree = Tree::new();
+
+    let s = Maze2DState::new_from_usize(0, 0).unwrap();
+    let mut leaf = tree.push(SearchTreeNode::new(0, s, None, 0));
+    for x in 1..=depth {
+        let s = Maze2DState::new_from_usize(x, 0).unwrap();
+        let g = tree[leaf].g() + space.cost(&s, &Maze2DAction::Right);
+        leaf = tree.push(SearchTreeNode::new(
+            0,
+            s,
+            Some((leaf, Maze2DAction::Right)),
+            g,
+        ));
+    }
+
+    (space, tree, leaf)
+}
+
+/// Reconstructs the actions reserving their exact capacity up front.
+fn path_preallocated(
+    space: &Maze2DSpace,
+    tree: &Tree,
+    leaf: SearchTreeIndex,
+) -> (Vec<Maze2DAction>, Maze2DCost) {
+    let mut actions = Vec::with_capacity(tree[leaf].depth() as usize);
+    let mut cost = 0;
+    for (_i, node) in tree.ancestors(leaf) {
+        if let Some((parent_index, a)) = node.parent() {
+            actions.push(a);
+            cost += space.cost(tree[parent_index].state(), &a);
+        }
+    }
+    actions.reverse();
+    (actions, cost)
+}
+
+fn path_reconstruction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Path Reconstruction");
+
+    for depth in [100, 10_000, 1_000_000] {
+        let (space, tree, leaf) = deep_tree(depth);
+
+        group.bench_with_input(
+            BenchmarkId::new("append+reverse", depth),
+            &leaf,
+            |b, leaf| b.iter(|| tree.path(&space, *leaf)),
+        );
+        group.bench_with_input(BenchmarkId::new("preallocated", depth), &leaf, |b, leaf| {
+            b.iter(|| path_preallocated(&space, &tree, *leaf))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, path_reconstruction);
+criterion_main!(benches);
@@ -26,15 +26,17 @@ const NUM_SOLUTIONS: usize = 2;
 /// 1s instance takes 1m40s.
 const MAX_INSTANCE_TIME: Duration = Duration::from_secs(1);
 
+type Maze2DAStar = AStarSearch<
+    Maze2DHeuristicDiagonalDistance,
+    Maze2DProblem,
+    Maze2DSpace,
+    Maze2DState,
+    Maze2DAction,
+    Maze2DCost,
+>;
+
 fn astar(problem: Maze2DProblem) -> u64 {
-    let search = AStarSearch::<
-        Maze2DHeuristicDiagonalDistance,
-        Maze2DProblem,
-        Maze2DSpace,
-        Maze2DState,
-        Maze2DAction,
-        Maze2DCost,
-    >::new(problem);
+    let search = Maze2DAStar::new(problem);
 
     let mut solutions = 0u64;
     for _path in search.take(NUM_SOLUTIONS) {
@@ -43,6 +45,19 @@ fn astar(problem: Maze2DProblem) -> u64 {
     solutions
 }
 
+/// Like `astar`, but re-using the allocations of a previous search.
+fn astar_reset(search: &mut Maze2DAStar, problem: Maze2DProblem) -> u64 {
+    search.reset(problem);
+
+    let mut solutions = 0u64;
+    for _i in 0..NUM_SOLUTIONS {
+        if search.find_next_goal().is_some() {
+            solutions += 1;
+        }
+    }
+    solutions
+}
+
 fn compare_search(c: &mut Criterion) {
     let mut group = c.benchmark_group("Maze2D Search");
 
@@ -63,14 +78,7 @@ fn compare_search(c: &mut Criterion) {
             let num_goals = 3;
 
             if let Some(problem) = base_problem.randomize(&mut rng, num_starts, num_goals) {
-                let mut astar_search = AStarSearch::<
-                    Maze2DHeuristicDiagonalDistance,
-                    Maze2DProblem,
-                    Maze2DSpace,
-                    Maze2DState,
-                    Maze2DAction,
-                    Maze2DCost,
-                >::new(problem.clone());
+                let mut astar_search = Maze2DAStar::new(problem.clone());
 
                 let mut astar_solutions = 0;
                 let mut astar_stopwatch = Stopwatch::new_started();
@@ -98,6 +106,11 @@ fn compare_search(c: &mut Criterion) {
                 group.bench_with_input(BenchmarkId::new("A*", &instance_name), &problem, |b, p| {
                     b.iter(|| astar(p.clone()))
                 });
+                group.bench_with_input(
+                    BenchmarkId::new("A* (reset)", &instance_name),
+                    &problem,
+                    |b, p| b.iter(|| astar_reset(&mut astar_search, p.clone())),
+                );
             }
         }
     }
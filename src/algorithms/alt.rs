@@ -0,0 +1,331 @@
+//! ALT heuristics (A*, Landmarks and the Triangle inequality).
+//!
+//! The distances from a few landmarks bound the distance between any two
+//! states, as `d(L, b) <= d(L, a) + d(a, b)`. On large spaces with obstacles
+//! this is far more informed than geometric heuristics, at the cost of storing
+//! a distance map per landmark.
+//!
+//! Heuristics can't hold state, so `AltSpace` carries the landmarks along with
+//! the space for `AltHeuristic` to use them.
+
+use derive_more::Display;
+use rustc_hash::FxHashMap;
+
+use crate::algorithms::dijkstra::parallel_distance_maps;
+use crate::space::Action;
+use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
+use crate::space::ReversibleSpace;
+use crate::space::Space;
+use crate::space::State;
+
+/// Distances from every landmark to the states they reach, and from the
+/// states reaching them.
+///
+/// Costs may be asymmetric, so both directions are needed to bound distances
+/// from either side of a landmark.
+#[derive(Debug, Clone)]
+pub struct Landmarks<St, C>
+where
+    St: State,
+    C: OrdCost,
+{
+    /// `d(L, s)` for each landmark `L`.
+    from: Vec<FxHashMap<St, C>>,
+    /// `d(s, L)` for each landmark `L`.
+    to: Vec<FxHashMap<St, C>>,
+}
+
+impl<St, C> Landmarks<St, C>
+where
+    St: State,
+    C: OrdCost,
+{
+    /// Computes the distance maps of the landmarks in parallel.
+    #[must_use]
+    pub fn precompute<Sp, A>(space: &Sp, landmarks: &[St]) -> Self
+    where
+        Sp: ReversibleSpace<St, A, C> + Sync,
+        St: Send + Sync,
+        A: Action + Send + Sync,
+        C: Send,
+    {
+        Self {
+            from: parallel_distance_maps(space, landmarks),
+            to: parallel_distance_maps(&Reversed(space), landmarks),
+        }
+    }
+
+    /// A lower bound of the cost from `a` to `b`, the best among landmarks.
+    ///
+    /// It's admissible and consistent, as `d(L, b) <= d(L, a) + d(a, b)` and
+    /// `d(a, L) <= d(a, b) + d(b, L)`. States a landmark tells apart as
+    /// disconnected are infinitely far away.
+    #[must_use]
+    pub fn h(&self, a: &St, b: &St) -> C {
+        let mut h = C::zero();
+        for (from, to) in self.from.iter().zip(&self.to) {
+            let forward = match (from.get(a), from.get(b)) {
+                (Some(&la), Some(&lb)) if lb > la => lb - la,
+                // `a` can't reach `b`, or the landmark would too.
+                (Some(_), None) => return C::max_value(),
+                _ => C::zero(),
+            };
+            let backward = match (to.get(a), to.get(b)) {
+                (Some(&al), Some(&bl)) if al > bl => al - bl,
+                // `a` can't reach `b`, or it would reach the landmark too.
+                (None, Some(_)) => return C::max_value(),
+                _ => C::zero(),
+            };
+            h = h.max(forward).max(backward);
+        }
+        h
+    }
+}
+
+/// An action of `Reversed`, going back to the state it was taken from.
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq)]
+#[display("{action}⁻¹")]
+struct Undo<St, A> {
+    from: St,
+    action: A,
+}
+
+impl<St: State, A: Action> Action for Undo<St, A> {}
+
+/// A space with its transitions reversed, to get the distances towards a
+/// state.
+#[derive(Clone, Debug)]
+struct Reversed<'a, Sp>(&'a Sp);
+
+impl<Sp, St, A, C> Space<St, Undo<St, A>, C> for Reversed<'_, Sp>
+where
+    Sp: ReversibleSpace<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    fn apply(&self, s: &St, undo: &Undo<St, A>) -> Option<St> {
+        (self.0.apply(&undo.from, &undo.action) == Some(*s)).then_some(undo.from)
+    }
+
+    fn cost(&self, _s: &St, undo: &Undo<St, A>) -> C {
+        self.0.cost(&undo.from, &undo.action)
+    }
+
+    fn neighbours(&self, s: &St) -> Vec<(St, Undo<St, A>)> {
+        self.0
+            .reverse_neighbours(s)
+            .into_iter()
+            .map(|(from, action)| (from, Undo { from, action }))
+            .collect()
+    }
+
+    fn valid(&self, s: &St) -> bool {
+        self.0.valid(s)
+    }
+}
+
+/// A space along with landmarks on it, for `AltHeuristic` to use.
+#[derive(Clone, Debug)]
+pub struct AltSpace<Sp, St, C>
+where
+    St: State,
+    C: OrdCost,
+{
+    space: Sp,
+    landmarks: Landmarks<St, C>,
+}
+
+impl<Sp, St, C> AltSpace<Sp, St, C>
+where
+    St: State,
+    C: OrdCost,
+{
+    #[must_use]
+    pub fn new(space: Sp, landmarks: Landmarks<St, C>) -> Self {
+        Self { space, landmarks }
+    }
+
+    #[must_use]
+    pub fn space(&self) -> &Sp {
+        &self.space
+    }
+
+    #[must_use]
+    pub fn landmarks(&self) -> &Landmarks<St, C> {
+        &self.landmarks
+    }
+}
+
+impl<Sp, St, A, C> Space<St, A, C> for AltSpace<Sp, St, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn apply(&self, s: &St, a: &A) -> Option<St> {
+        self.space.apply(s, a)
+    }
+
+    #[inline(always)]
+    fn cost(&self, s: &St, a: &A) -> C {
+        self.space.cost(s, a)
+    }
+
+    #[inline(always)]
+    fn neighbours(&self, s: &St) -> Vec<(St, A)> {
+        self.space.neighbours(s)
+    }
+
+    #[inline(always)]
+    fn for_each_neighbour(&self, s: &St, f: impl FnMut(St, A)) {
+        self.space.for_each_neighbour(s, f)
+    }
+
+    #[inline(always)]
+    fn valid(&self, s: &St) -> bool {
+        self.space.valid(s)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> Option<usize> {
+        self.space.size()
+    }
+}
+
+/// The landmark bound of an `AltSpace`, see `Landmarks::h`.
+#[derive(Debug)]
+pub struct AltHeuristic;
+
+impl<Sp, St, A, C> ObjectiveHeuristic<AltSpace<Sp, St, C>, St, A, C> for AltHeuristic
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// No bound without the landmarks.
+    #[inline(always)]
+    fn h(_a: &St, _b: &St) -> C {
+        C::zero()
+    }
+
+    #[inline(always)]
+    fn h_in(space: &AltSpace<Sp, St, C>, a: &St, b: &St) -> C {
+        space.landmarks.h(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::algorithms::astar::AStarSearch;
+    use crate::algorithms::dijkstra::DijkstraSearch;
+    use crate::problem::BaseProblem;
+    use crate::problem::FloodProblem;
+    use crate::problem::ObjectiveProblem;
+    use crate::problems::maze_2d::Connectivity;
+    use crate::problems::maze_2d::Maze2DCost;
+    use crate::problems::maze_2d::Maze2DHeuristicManhattanDistance;
+    use crate::problems::maze_2d::Maze2DProblem;
+    use crate::problems::maze_2d::Maze2DState;
+    use crate::problems::maze_2d::Maze2DTerrain;
+
+    #[test]
+    fn dominates_manhattan() {
+        let problem = Maze2DProblem::try_from(indoc! {"
+            ..........#.........
+            ..........#.........
+            ....S.....#.........
+            ..........#......G..
+            ..........#.........
+            ..........#.........
+            ....................
+        "})
+        .unwrap()
+        .with_connectivity(Connectivity::Four);
+        let space = problem.space();
+        let goal = problem.goals()[0];
+        let corners = [(0, 0), (19, 0), (0, 6), (19, 6)]
+            .map(|(x, y)| Maze2DState::new_from_usize(x, y).unwrap());
+        let landmarks = Landmarks::precompute(space, &corners);
+
+        // Admissible and consistent.
+        let exact = DijkstraSearch::new(FloodProblem::new(space.clone(), goal)).into_distance_map();
+        for (s, d) in &exact {
+            assert!(landmarks.h(s, &goal) <= *d);
+            assert_eq!(landmarks.h(s, s), 0);
+            for (t, a) in space.neighbours(s) {
+                assert!(landmarks.h(s, &goal) <= space.cost(s, &a) + landmarks.h(&t, &goal));
+            }
+        }
+
+        let alt_problem =
+            FloodProblem::new(AltSpace::new(space.clone(), landmarks), problem.starts()[0])
+                .with_goal(goal);
+        let mut alt = AStarSearch::<AltHeuristic, _, _, _, _, _>::new(alt_problem);
+        let mut manhattan =
+            AStarSearch::<Maze2DHeuristicManhattanDistance, _, _, _, _, _>::new(problem);
+        let alt_path = alt.find_next_goal().unwrap();
+        let manhattan_path = manhattan.find_next_goal().unwrap();
+
+        assert_eq!(alt_path.cost(), manhattan_path.cost());
+        assert!(alt.expanded_count() < manhattan.expanded_count());
+    }
+
+    #[test]
+    fn disconnected() {
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#..
+            ..#.G
+        "})
+        .unwrap();
+        let space = problem.space();
+        let (start, goal) = (problem.starts()[0], problem.goals()[0]);
+
+        let landmarks = Landmarks::precompute(space, &[start]);
+        assert_eq!(landmarks.h(&start, &goal), Maze2DCost::MAX);
+
+        let beyond = Maze2DState::new_from_usize(3, 0).unwrap();
+        let landmarks = Landmarks::precompute(space, &[goal]);
+        assert_eq!(landmarks.h(&beyond, &start), Maze2DCost::MAX);
+        assert!(landmarks.h(&beyond, &goal) > 0);
+    }
+
+    #[test]
+    fn asymmetric_costs() {
+        let mut problem = Maze2DProblem::try_from(indoc! {"
+            S....
+            .#.#.
+            ....G
+        "})
+        .unwrap();
+        // Stepping into mud costs more than stepping out of it.
+        let mud = Maze2DTerrain::new(5).unwrap();
+        for (x, y) in [(1, 0), (2, 0), (2, 1), (4, 2)] {
+            assert!(problem.set_terrain(&Maze2DState::new_from_usize(x, y).unwrap(), mud));
+        }
+        let space = problem.space();
+        let corners = [(0, 0), (4, 2)].map(|(x, y)| Maze2DState::new_from_usize(x, y).unwrap());
+        let landmarks = Landmarks::precompute(space, &corners);
+
+        let distances = |s: Maze2DState| {
+            DijkstraSearch::new(FloodProblem::new(space.clone(), s)).into_distance_map()
+        };
+        let (start, goal) = (problem.starts()[0], problem.goals()[0]);
+        assert_ne!(distances(start)[&goal], distances(goal)[&start]);
+
+        // Admissible between any pair of states.
+        for (a, _d) in distances(start) {
+            for (b, d) in distances(a) {
+                assert!(landmarks.h(&a, &b) <= d, "h({a}, {b}) > {d}");
+            }
+        }
+    }
+}
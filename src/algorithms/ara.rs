@@ -0,0 +1,343 @@
+//! Anytime Repairing A* (ARA*).
+//!
+//! Runs Weighted A* to quickly find a solution, and then keeps lowering the
+//! weight to improve it. Instead of searching from scratch, each iteration
+//! re-uses the previous search, only re-expanding the nodes whose g-value
+//! improved after they were expanded (the inconsistent ones).
+
+use std::cmp::max;
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+
+use num_traits::One;
+use num_traits::SaturatingMul;
+use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
+
+use crate::problem::ObjectiveProblem;
+use crate::search::SearchTree;
+use crate::search::SearchTreeIndex;
+use crate::search::SearchTreeNode;
+use crate::space::Action;
+use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::Space;
+use crate::space::State;
+
+/// A Search Node's bookkeeping, referenced by its position in
+/// `AraStarSearch::nodes`.
+#[derive(Copy, Clone, Debug)]
+struct AraNode<C: OrdCost> {
+    node_index: SearchTreeIndex,
+    h: C,
+    /// The key in the open list, if it's open.
+    key: Option<C>,
+    /// The last iteration that expanded the node.
+    closed_in: usize,
+    /// Whether it's in the inconsistent list.
+    incons: bool,
+}
+
+/// ARA* for Objective Problems.
+///
+/// Each `improve()` call returns a path costing at most `weight` times the
+/// optimal cost, with the weight lowered by `step` on every call until it
+/// reaches 1 and the path is optimal.
+#[derive(Debug)]
+pub struct AraStarSearch<OH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    search_tree: SearchTree<St, A, C>,
+    nodes: Vec<AraNode<C>>,
+    node_map: FxHashMap<St, usize>,
+
+    /// The open nodes as `(g + weight * h, node)`.
+    open: BTreeSet<(C, usize)>,
+    /// Closed nodes reached through a better path during an iteration. They
+    /// are re-opened on the next one.
+    incons: Vec<usize>,
+
+    /// The current heuristic inflation factor.
+    weight: C,
+    /// How much the weight is lowered between iterations.
+    step: C,
+    /// The number of `improve()` calls that searched.
+    iteration: usize,
+    /// Whether the optimal path was already returned, or can't exist.
+    done: bool,
+
+    goals: FxHashSet<St>,
+
+    /// The number of expanded nodes, counting again re-expansions.
+    expanded: usize,
+
+    problem: OP,
+
+    _phantom_heuristic: PhantomData<OH>,
+    _phantom_space: PhantomData<Sp>,
+}
+
+impl<OH, OP, Sp, St, A, C> AraStarSearch<OH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost + SaturatingMul + One,
+{
+    /// Initializes the Search with an initial weight, lowered by `step` on
+    /// every improvement.
+    ///
+    /// Weights below 1 are raised to 1, and a zero `step` drops the weight
+    /// straight to 1 after the first path.
+    #[must_use]
+    pub fn new(op: OP, weight: C, step: C) -> Self {
+        let mut search = Self {
+            search_tree: SearchTree::new(),
+            nodes: Vec::new(),
+            node_map: FxHashMap::default(),
+            open: BTreeSet::new(),
+            incons: Vec::new(),
+            weight: max(weight, C::one()),
+            step,
+            iteration: 0,
+            done: false,
+            goals: op.goals().iter().cloned().collect(),
+            expanded: 0,
+            problem: op,
+            _phantom_heuristic: PhantomData,
+            _phantom_space: PhantomData,
+        };
+
+        let starts = search.problem.starts().to_vec();
+        for s in starts {
+            search.push_new(&s, None, C::zero());
+        }
+        search
+    }
+
+    /// Finds a path at most `weight` times worse than the optimal one.
+    ///
+    /// Paths may cost less than the g-value of their goal, as nodes improved
+    /// after being expanded don't update their sub-trees. Costs don't increase
+    /// between calls. Returns `None` once the optimal path was returned, or
+    /// when no goal can be reached.
+    #[must_use]
+    pub fn improve(&mut self) -> Option<Path<St, A, C>> {
+        if self.done {
+            return None;
+        }
+        if self.iteration > 0 {
+            self.lower_weight();
+        }
+        self.iteration += 1;
+
+        self.improve_path();
+        if self.weight == C::one() {
+            self.done = true;
+        }
+        match self.best_goal() {
+            Some((node_index, _g)) => Some(
+                self.search_tree
+                    .stale_path(self.problem.space(), node_index),
+            ),
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+
+    /// The current heuristic inflation factor.
+    #[inline(always)]
+    #[must_use]
+    pub fn weight(&self) -> C {
+        self.weight
+    }
+
+    /// The number of nodes expanded so far, counting again re-expansions.
+    #[inline(always)]
+    #[must_use]
+    pub fn expanded_count(&self) -> usize {
+        self.expanded
+    }
+
+    /// Lowers the weight and re-opens the inconsistent nodes, re-keying the
+    /// open list for the new weight.
+    fn lower_weight(&mut self) {
+        self.weight = if !self.step.is_zero() && self.weight > C::one() + self.step {
+            self.weight - self.step
+        } else {
+            C::one()
+        };
+
+        let mut open: Vec<usize> = std::mem::take(&mut self.open)
+            .into_iter()
+            .map(|(_key, node)| node)
+            .collect();
+        for node in self.incons.drain(..) {
+            self.nodes[node].incons = false;
+            open.push(node);
+        }
+        for node in open {
+            let g = self.search_tree[self.nodes[node].node_index].g;
+            self.insert_open(node, g);
+        }
+    }
+
+    /// Expands nodes until no open node could lead to a better goal under
+    /// the current weight.
+    fn improve_path(&mut self) {
+        while let Some(&(key, node)) = self.open.first() {
+            if self.best_goal().is_some_and(|(_node_index, g)| g <= key) {
+                break;
+            }
+            self.open.pop_first();
+            self.nodes[node].key = None;
+            self.nodes[node].closed_in = self.iteration;
+            self.expanded += 1;
+
+            let node_index = self.nodes[node].node_index;
+            let state = *self.search_tree[node_index].state();
+            let g = self.search_tree[node_index].g;
+            let depth = self.search_tree[node_index].depth;
+
+            for (s, a) in self.problem.space().neighbours(&state) {
+                let new_g = g + self.problem.space().cost(&state, &a);
+                let parent = (node_index, a);
+
+                let Some(&neigh) = self.node_map.get(&s) else {
+                    self.push_new(&s, Some(parent), new_g);
+                    continue;
+                };
+                let neigh_index = self.nodes[neigh].node_index;
+                if new_g >= self.search_tree[neigh_index].g {
+                    continue;
+                }
+
+                self.search_tree[neigh_index].reach(parent, new_g, depth + 1);
+                if self.nodes[neigh].closed_in == self.iteration {
+                    if !self.nodes[neigh].incons {
+                        self.nodes[neigh].incons = true;
+                        self.incons.push(neigh);
+                    }
+                } else {
+                    self.insert_open(neigh, new_g);
+                }
+            }
+        }
+    }
+
+    /// The reached goal with the lowest g-value.
+    fn best_goal(&self) -> Option<(SearchTreeIndex, C)> {
+        self.goals
+            .iter()
+            .filter_map(|goal| {
+                let node_index = self.nodes[*self.node_map.get(goal)?].node_index;
+                Some((node_index, self.search_tree[node_index].g))
+            })
+            .min_by_key(|(_node_index, g)| *g)
+    }
+
+    fn push_new(&mut self, s: &St, parent: Option<(SearchTreeIndex, A)>, g: C) {
+        let mut h = C::max_value();
+        for goal in &self.goals {
+            h = h.min(OH::h_in(self.problem.space(), s, goal));
+        }
+
+        let node = self.nodes.len();
+        let node_index = self
+            .search_tree
+            .push(SearchTreeNode::new(node, *s, parent, g));
+        self.nodes.push(AraNode {
+            node_index,
+            h,
+            key: None,
+            closed_in: 0,
+            incons: false,
+        });
+        self.node_map.insert(*s, node);
+        self.insert_open(node, g);
+    }
+
+    /// Opens a node, or updates its key if it was open.
+    fn insert_open(&mut self, node: usize, g: C) {
+        if let Some(key) = self.nodes[node].key {
+            self.open.remove(&(key, node));
+        }
+        let key = g.saturating_add(&self.nodes[node].h.saturating_mul(&self.weight));
+        self.nodes[node].key = Some(key);
+        self.open.insert((key, node));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::astar::AStarSearch;
+    use crate::problem::BaseProblem;
+    use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+    use crate::problems::maze_2d::Maze2DProblem;
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn two_paths() {
+        use std::path::PathBuf;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let optimal =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone())
+                .find_next_goal()
+                .unwrap()
+                .cost();
+
+        let mut search = AraStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(
+            problem.clone(),
+            5,
+            1,
+        );
+        let mut costs = vec![];
+        while let Some(path) = search.improve() {
+            assert!(problem.space().valid_path(&path));
+            assert!(path.cost() <= search.weight() * optimal);
+            costs.push(path.cost());
+        }
+
+        assert_eq!(costs.len(), 5);
+        assert!(costs.is_sorted_by(|a, b| a >= b));
+        assert_eq!(costs.last(), Some(&optimal));
+        assert_eq!(search.weight(), 1);
+    }
+
+    #[test]
+    fn unreachable() {
+        let problem = Maze2DProblem::try_from("S#G\n").unwrap();
+        let mut search =
+            AraStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem, 3, 1);
+        assert_eq!(search.improve(), None);
+        assert_eq!(search.improve(), None);
+    }
+
+    #[test]
+    fn zero_step() {
+        let problem = Maze2DProblem::try_from("S...G\n").unwrap();
+        let mut search =
+            AraStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem, 3, 0);
+        assert!(search.improve().is_some());
+        assert_eq!(search.weight(), 3);
+        assert!(search.improve().is_some());
+        assert_eq!(search.weight(), 1);
+        assert_eq!(search.improve(), None);
+    }
+}
@@ -1,93 +1,199 @@
 //! Implementation of the A* path-finding algorithm.
 
 use core::intrinsics::unlikely;
-use std::cmp::min;
+use std::cmp::max;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::time::Instant;
 
+use num_traits::One;
+use num_traits::SaturatingMul;
 use rustc_hash::FxHashMap;
 use rustc_hash::FxHashSet;
 
-use crate::derank::derank;
+use crate::data_structures::intrusive_heap::HeapIndexTracker;
+use crate::data_structures::intrusive_heap::IntrusiveHeap;
 use crate::problem::ObjectiveProblem;
+use crate::search::DEADLINE_CHECK_INTERVAL;
+use crate::search::DEFAULT_NODE_MAP_CAPACITY;
+use crate::search::DEFAULT_OPEN_CAPACITY;
+use crate::search::SearchOutcome;
 use crate::search::SearchTree;
 use crate::search::SearchTreeIndex;
 use crate::search::SearchTreeNode;
 use crate::space::Action;
-use crate::space::Cost;
 use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
 use crate::space::Path;
 use crate::space::Space;
 use crate::space::State;
 
+/// The ranking of open nodes, built from their `g` and `h` values.
+///
+/// Lower ranks are expanded first.
+pub trait Rank<C: OrdCost>: Copy + Debug + Ord {
+    #[must_use]
+    fn new(g: C, h: C) -> Self;
+    /// Improves `g` without recomputing `h`.
+    ///
+    /// Necessary with inconsistent or inadmissible heuristics.
+    fn improve_g(&mut self, new_g: C);
+    /// Worsens `h`.
+    ///
+    /// Necessary when dropping objectives (after finding them).
+    /// Returns whether the ranking worsened.
+    fn worsen_h(&mut self, new_h: C) -> bool;
+}
+
+/// How A* breaks ties between nodes with the same f-value.
+///
+/// The policy is part of the rank type (see `AStarRank`), so all the ranks in
+/// a search break ties the same way.
+pub trait TieBreak: Copy + Debug + Eq {
+    /// Orders the h-values of two nodes with the same f-value.
+    fn cmp_h<C: OrdCost>(a: &C, b: &C) -> std::cmp::Ordering;
+}
+
+/// Prefer nodes closer to the goals, which tends to dive towards them.
+///
+/// With equal f-values a lower h means a higher g, so this also prefers nodes
+/// further from the starts.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LowH;
+impl TieBreak for LowH {
+    #[inline(always)]
+    fn cmp_h<C: OrdCost>(a: &C, b: &C) -> std::cmp::Ordering {
+        a.cmp(b)
+    }
+}
+
+/// Prefer nodes closer to the starts, which tends to explore more of the
+/// f-value plateau before reaching the goal.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LowG;
+impl TieBreak for LowG {
+    #[inline(always)]
+    fn cmp_h<C: OrdCost>(a: &C, b: &C) -> std::cmp::Ordering {
+        b.cmp(a)
+    }
+}
+
 /// The ranking tuple for A*
 ///
-/// We prefer better f-values, and tie break for lower h.
+/// We prefer better f-values, and tie break for lower h by default (see
+/// `TieBreak`).
 ///
 /// Intuition around higher g-value might be slightly easier, but keeping the
 /// raw h value helps to avoid recomputing it later.
 ///
+/// NOTE: On Weighted A* `h` is the already inflated `weight * h`, so
+/// `improve_g` and `worsen_h` keep working as long as they are given inflated
+/// values too.
+///
 // ```
 // use search::algorithms::astar::AStarRank;
-// use search::space::Cost;
+// use search::algorithms::astar::Rank;
+// use search::space::OrdCost;
 //
 // let l0 = LittleCost::new(0);
 // let l1 = LittleCost::new(1);
 // let l2 = LittleCost::new(2);
-// assert!(AStarRank::new(l2, l0) < AStarRank::new(l2, l1));
+// assert!(AStarRank::<_>::new(l2, l0) < AStarRank::<_>::new(l2, l1));
 // ```
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct AStarRank<C: Cost> {
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AStarRank<C: OrdCost, T: TieBreak = LowH> {
     f: C,
     h: C,
+    _phantom_tie_break: PhantomData<T>,
 }
-impl<C> AStarRank<C>
+impl<C, T> AStarRank<C, T>
 where
-    C: Cost,
+    C: OrdCost,
+    T: TieBreak,
 {
-    pub fn new(g: C, h: C) -> Self {
+    /// Rebuilds a rank from its `f` and `h`, like the ones read from another
+    /// rank.
+    ///
+    /// The tie-break policy is part of the type, so the rebuilt rank orders
+    /// like the original one.
+    pub fn from_parts(f: C, h: C) -> Self {
         Self {
-            f: g.saturating_add(&h),
+            f,
             h,
+            _phantom_tie_break: PhantomData,
         }
     }
-    /// Improves `g` in `Rank{f, h}` without recomputing `h`.
+    /// The estimated cost of a path through the node, `f = g + h`.
+    #[inline(always)]
+    #[must_use]
+    pub fn f(&self) -> C {
+        self.f
+    }
+    /// The estimated cost left to a goal.
+    #[inline(always)]
+    #[must_use]
+    pub fn h(&self) -> C {
+        self.h
+    }
+    /// Computes `f = g + h`.
     ///
-    /// Necessary with inconsistent or inadmissible heuristics.
-    pub fn improve_g(&mut self, new_g: C) {
+    /// Saturating at `C::max_value()` is meant for infinite `g` or `h`, so
+    /// finite values reaching it are flagged as an overflow.
+    #[inline(always)]
+    fn add(g: C, h: C) -> C {
+        let f = g.saturating_add(&h);
+        debug_assert!(
+            f.valid() || !g.valid() || !h.valid(),
+            "f = g + h overflowed ({g} + {h})"
+        );
+        f
+    }
+}
+
+impl<C, T> Rank<C> for AStarRank<C, T>
+where
+    C: OrdCost,
+    T: TieBreak,
+{
+    #[inline(always)]
+    fn new(g: C, h: C) -> Self {
+        Self::from_parts(Self::add(g, h), h)
+    }
+    /// Improves `g` in `Rank{f, h}` without recomputing `h`.
+    #[inline(always)]
+    fn improve_g(&mut self, new_g: C) {
         debug_assert!(self.f > new_g.saturating_add(&self.h)); // We don't have g, but f. `(self.g > new_g) /+ h`
-        self.f = new_g.saturating_add(&self.h);
+        self.f = Self::add(new_g, self.h);
     }
     /// Worsens `h` in `Rank{f, h}`.
-    ///
-    /// Necessary when dropping objectives (after finding them).
-    /// Returns whether the ranking worsened.
-    pub fn worsen_h(&mut self, new_h: C) -> bool {
+    #[inline(always)]
+    fn worsen_h(&mut self, new_h: C) -> bool {
         if new_h > self.h {
             let g = self.f - self.h;
             self.h = new_h;
-            self.f = g.saturating_add(&new_h);
+            self.f = Self::add(g, new_h);
             return true;
         }
         false
     }
 }
 
-const HEAP_ARITY: usize = 8usize;
-#[inline(always)]
-#[must_use]
-fn up(i: usize) -> usize {
-    crate::heap_primitives::index_parent::<HEAP_ARITY>(i)
-}
-#[inline(always)]
-#[must_use]
-fn down_left(i: usize) -> usize {
-    crate::heap_primitives::index_first_children::<HEAP_ARITY>(i)
+impl<C: OrdCost, T: TieBreak> PartialOrd for AStarRank<C, T> {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
-#[inline(always)]
-#[must_use]
-fn down_right(i: usize) -> usize {
-    crate::heap_primitives::index_last_children::<HEAP_ARITY>(i)
+impl<C: OrdCost, T: TieBreak> Ord for AStarRank<C, T> {
+    #[inline(always)]
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.f
+            .cmp(&other.f)
+            .then_with(|| T::cmp_h(&self.h, &other.h))
+    }
 }
 
 /// A heap node for A*
@@ -98,18 +204,15 @@ fn down_right(i: usize) -> usize {
 // TODO: Make public only with the "inspect" feature
 #[derive(Debug)]
 #[cfg_attr(feature = "inspect", derive(Clone))]
-pub struct AStarHeapNode<C>
-where
-    C: Cost,
-{
+pub struct AStarHeapNode<R> {
     /// The rank of this node that defines how good it is.
-    pub rank: AStarRank<C>,
+    pub rank: R,
     /// The index of this node in the Node Arena. Ignored when ranking.
     pub node_index: SearchTreeIndex,
 }
 
 /// `PartialEq`` is forwarded to `self.rank`
-impl<C: Cost> PartialEq for AStarHeapNode<C> {
+impl<R: Ord> PartialEq for AStarHeapNode<R> {
     #[inline(always)]
     fn eq(&self, other: &Self) -> bool {
         self.rank.eq(&other.rank)
@@ -117,57 +220,126 @@ impl<C: Cost> PartialEq for AStarHeapNode<C> {
 }
 /// `Eq` just says our `PartialEq` is also reflexive (`∀a. a==a`).
 /// `https://doc.rust-lang.org/std/cmp/trait.Eq.html`
-impl<C: Cost> Eq for AStarHeapNode<C> {}
+impl<R: Ord> Eq for AStarHeapNode<R> {}
 
 /// `PartialOrd` is forwarded to `Ord::cmp`
-impl<C: Cost> PartialOrd for AStarHeapNode<C> {
+impl<R: Ord> PartialOrd for AStarHeapNode<R> {
     #[inline(always)]
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.rank.cmp(&other.rank))
+        Some(self.cmp(other))
     }
 }
 /// `Ord` is forwarded to `self.rank`
-impl<C: Cost> Ord for AStarHeapNode<C> {
+impl<R: Ord> Ord for AStarHeapNode<R> {
     #[inline(always)]
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.rank.cmp(&other.rank)
     }
 }
 
-/// A* search implementation for Objective Problems.
+/// Keeps `SearchTreeNode::heap_index` in sync with the open list.
+impl<St, A, C, R> HeapIndexTracker<AStarHeapNode<R>> for SearchTree<St, A, C>
+where
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn set_heap_index(&mut self, node: &AStarHeapNode<R>, heap_index: usize) {
+        self[node.node_index].heap_index = heap_index;
+    }
+}
+
+/// A Search Tree node within an `AStarSnapshot`.
+#[cfg(feature = "serde")]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct AStarSnapshotNode<St, A, C> {
+    state: St,
+    /// The parent's position in `AStarSnapshot::nodes`.
+    parent: Option<(usize, A)>,
+    g: C,
+    h: C,
+    depth: u32,
+    closed: bool,
+}
+
+/// A serializable checkpoint of an `AStarSearch`, see `AStarSearch::snapshot`.
 ///
-/// This initializes the search and offers an Iterator that goes around
-/// different solutions.
+/// Search Tree nodes point to each other in memory, so the snapshot refers to
+/// them by their position in `nodes` instead.
+#[cfg(feature = "serde")]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AStarSnapshot<St, A, C: OrdCost> {
+    nodes: Vec<AStarSnapshotNode<St, A, C>>,
+    /// The open list in heap order, as positions in `nodes`.
+    ///
+    /// Ranks are rebuilt from the nodes' `g` and `h`.
+    open: Vec<usize>,
+    remaining_goals: Vec<St>,
+    expanded: usize,
+    generated: usize,
+
+    /// The Weighted A* weight, if any.
+    weight: Option<C>,
+    allow_reopening: bool,
+    max_path_len: Option<usize>,
+}
+
+/// The `SearchTreeNode::heap_index` of open nodes dropped from the open list
+/// as they can't reach any remaining goal.
 ///
-/// Having a list of objectives allows to drop the ones already reached when
-/// reaching multiple goals.
+/// They are never expanded, so they aren't closed either.
+const DROPPED: usize = usize::MAX;
+
+/// A callback getting the state, g and h of every expanded node.
+type ExpandFn<St, C> = dyn FnMut(&St, C, C);
+struct ExpandHook<St, C>(Box<ExpandFn<St, C>>);
+
+impl<St, C> Debug for ExpandHook<St, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ExpandHook")
+    }
+}
+
+/// The heuristic of a search built on `AStarNodes`.
+pub(crate) trait NodeHeuristic<Sp, St, C> {
+    /// The heuristic of a newly generated node.
+    fn h(&mut self, space: &Sp, s: &St, node_index: SearchTreeIndex) -> C;
+
+    /// The heuristic without Weighted A* inflation, to check its consistency.
+    #[cfg(feature = "verify")]
+    fn raw_h(&self, space: &Sp, s: &St) -> C;
+}
+
+/// The nodes of an A* search, along with its counters and limits.
+///
+/// The A* searches only differ on their goals and heuristics, so they share
+/// how nodes are generated, ranked and expanded.
 #[derive(Debug)]
-pub struct AStarSearch<OH, OP, Sp, St, A, C>
+pub(crate) struct AStarNodes<St, A, C, R>
 where
-    OH: ObjectiveHeuristic<Sp, St, A, C>,
-    OP: ObjectiveProblem<Sp, St, A, C>,
-    Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
+    R: Rank<C>,
 {
     /// All the Search Nodes. Naturally forms a Search Forest as each node may
     /// have a parent Node.
     ///
     /// Could be backed by an Arena since this collection only grows and does
     /// not need contiguous memory.
-    search_tree: SearchTree<St, A, C>,
+    pub(crate) search_tree: SearchTree<St, A, C>,
 
-    /// An intrusive heap of `(AStarRank, SearchTreeIndex)` that keeps the
+    /// An intrusive heap of `(Rank, SearchTreeIndex)` that keeps the
     /// referenced node updated (`SearchTreeNode::heap_index`).
     /// This allows re-ranking a `SearchTreeNode` in the heap without a linear
-    /// search for its `(AStarRank, SearchTreeIndex)` entry.
+    /// search for its `(Rank, SearchTreeIndex)` entry.
     ///
     /// ```pseudocode
     /// for (i, hn) in self.open.enumerate():
     ///   assert_eq(self.search_tree[hn.node_index].heap_index, i)
     /// ```
-    open: Vec<AStarHeapNode<C>>,
+    pub(crate) open: IntrusiveHeap<AStarHeapNode<R>>,
 
     /// Amalgamation of,
     /// - The `HashMap<St, &mut SearchTreeNode>`, but using `SearchTreeIndex`
@@ -176,224 +348,309 @@ where
     ///   - To recall whether we had already explored a state.
     ///
     /// It's the same size as the Search Tree.
-    node_map: FxHashMap<St, SearchTreeIndex>,
-
-    /// A list of remaining goals.
-    /// Used to compute objective heuristics.
-    remaining_goals_list: Vec<St>,
-    /// A set of remaining goals. Used for goal checks and to cut the search
-    /// earlier.
-    /// NOTE: With short sets the list should be fine.
-    remaining_goals_set: FxHashSet<St>,
-
-    problem: OP,
-
-    _phantom_heuristic: PhantomData<OH>,
-    _phantom_space: PhantomData<Sp>,
-    _phantom_action: PhantomData<A>,
+    pub(crate) node_map: FxHashMap<St, SearchTreeIndex>,
+
+    /// The number of expanded nodes.
+    pub(crate) expanded: usize,
+    /// The number of generated nodes, counting again the ones reached
+    /// through a better path.
+    pub(crate) generated: usize,
+    /// The number of expansions done by the current `find_next_goal` call.
+    call_expansions: usize,
+
+    /// When `find_next_goal` calls should give up.
+    pub(crate) deadline: Option<Instant>,
+    /// Whether the last `find_next_goal` call stopped due to `deadline`.
+    pub(crate) timed_out: bool,
+
+    /// The maximum number of expansions a single `find_next_goal` call can do.
+    pub(crate) max_expansions: Option<usize>,
+    /// Whether the last `find_next_goal` call stopped due to `max_expansions`.
+    pub(crate) budget_exhausted: bool,
+
+    /// Whether closed nodes reached through a better path are re-opened.
+    pub(crate) allow_reopening: bool,
+
+    /// Called on every expansion.
+    on_expand: Option<ExpandHook<St, C>>,
+
+    /// The neighbours of the node being expanded, kept to re-use their
+    /// allocation across expansions.
+    neighbours: Vec<(St, A)>,
 }
 
-/// A* search implementation for Objective Problems.
-impl<OH, OP, Sp, St, A, C> AStarSearch<OH, OP, Sp, St, A, C>
+impl<St, A, C, R> AStarNodes<St, A, C, R>
 where
-    OH: ObjectiveHeuristic<Sp, St, A, C>,
-    OP: ObjectiveProblem<Sp, St, A, C>,
-    Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
+    R: Rank<C>,
 {
-    /// Initializes the Search
     #[must_use]
-    pub fn new(op: OP) -> Self {
-        let starts = op.starts().to_vec();
-        let goals = op.goals().to_vec();
-
-        let mut search = Self {
-            search_tree: SearchTree::<St, A, C>::new(),
-            open: Vec::with_capacity(2048),
-            node_map: FxHashMap::default(),
-            remaining_goals_list: goals.clone(),
-            remaining_goals_set: FxHashSet::from_iter(goals.iter().cloned()),
+    pub(crate) fn with_capacity(open_capacity: usize, map_capacity: usize) -> Self {
+        Self {
+            search_tree: SearchTree::<St, A, C>::with_capacity(map_capacity),
+            open: IntrusiveHeap::with_capacity(open_capacity),
+            node_map: FxHashMap::with_capacity_and_hasher(map_capacity, Default::default()),
 
-            problem: op,
+            expanded: 0,
+            generated: 0,
+            call_expansions: 0,
 
-            _phantom_heuristic: PhantomData,
-            _phantom_space: PhantomData,
-            _phantom_action: PhantomData,
-        };
+            deadline: None,
+            timed_out: false,
 
-        for s in starts {
-            let g: C = C::zero();
-            let h: C = search.h(&s);
-            let parent: Option<(SearchTreeIndex, A)> = None;
-            search.push_new(&s, parent, g, h);
+            max_expansions: None,
+            budget_exhausted: false,
+
+            allow_reopening: false,
+
+            on_expand: None,
+
+            neighbours: Vec::new(),
         }
+    }
 
-        search
+    /// Drops all the nodes and counters, keeping the allocated capacity and
+    /// the settings.
+    pub(crate) fn clear(&mut self) {
+        self.search_tree.clear();
+        self.open.clear();
+        self.node_map.clear();
+        self.expanded = 0;
+        self.generated = 0;
+        self.budget_exhausted = false;
+        self.timed_out = false;
     }
 
-    /// Runs the search until the first goal is found.
+    /// Starts a `find_next_goal` call, forgetting why the last one stopped.
+    #[inline(always)]
+    pub(crate) fn start_call(&mut self) {
+        self.budget_exhausted = false;
+        self.timed_out = false;
+        self.call_expansions = 0;
+    }
+
+    /// Pops the next node to expand, unless the open list is empty or the
+    /// current `find_next_goal` call ran out of expansions or time.
     ///
-    /// It removes the goal from the remaining goals to help focus the search on
-    /// the remaining goals.
+    /// Interrupted calls leave the search untouched so the next one resumes
+    /// it.
+    #[inline(always)]
     #[must_use]
-    pub fn find_next_goal(&mut self) -> Option<Path<St, A, C>> {
-        #[cfg(feature = "coz_profile")]
-        coz::scope!("FindNextGoal");
-
-        if self.remaining_goals_set.is_empty() {
+    pub(crate) fn next_node(&mut self) -> Option<SearchTreeIndex> {
+        if self.open.is_empty() {
+            return None;
+        }
+        if unlikely(
+            self.max_expansions
+                .is_some_and(|max| self.call_expansions >= max),
+        ) {
+            self.budget_exhausted = true;
+            return None;
+        }
+        if unlikely(self.past_deadline()) {
+            self.timed_out = true;
             return None;
         }
+        self.call_expansions += 1;
+        self.pop()
+    }
 
-        // Check remaining un-explored nodes
-        // NOTE: We could avoid a `Heap::pop()` by peeking and doing the goal-check.
-        // TODO: See if `pop_node()` would be the same or faster that `pop()`
-        while let Some(node_index) = self.pop() {
-            #[cfg(feature = "coz_profile")]
-            coz::scope!("NodeExpansion");
+    #[inline(always)]
+    #[must_use]
+    fn past_deadline(&self) -> bool {
+        match self.deadline {
+            Some(deadline) => {
+                self.call_expansions.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+                    && Instant::now() >= deadline
+            }
+            None => false,
+        }
+    }
 
-            let state = *self.search_tree[node_index].state();
-            let g: C = self.search_tree[node_index].g;
-            debug_assert!(!self.is_closed(&state));
+    pub(crate) fn set_expand_hook(&mut self, hook: impl FnMut(&St, C, C) + 'static) {
+        self.on_expand = Some(ExpandHook(Box::new(hook)));
+    }
 
-            // NOTE: We can do a goal-check and return here if we only need one
-            // path or can yield a result
+    /// Closes a node and generates its neighbours.
+    ///
+    /// Returns the expanded state.
+    pub(crate) fn expand<Sp, H>(
+        &mut self,
+        space: &Sp,
+        node_index: SearchTreeIndex,
+        heuristic: &mut H,
+    ) -> St
+    where
+        Sp: Space<St, A, C>,
+        H: NodeHeuristic<Sp, St, C>,
+    {
+        #[cfg(feature = "coz_profile")]
+        coz::scope!("NodeExpansion");
+
+        let state = *self.search_tree[node_index].state();
+        let g: C = self.search_tree[node_index].g;
+        let depth = self.search_tree[node_index].depth;
+        debug_assert!(!self.is_closed(&state));
+
+        // Mark as closed
+        self.mark_closed(&state);
+        self.expanded += 1;
+        if let Some(mut hook) = self.on_expand.take() {
+            let h = self.search_tree[node_index].h;
+            (hook.0)(&state, g, h);
+            self.on_expand = Some(hook);
+        }
 
-            // Mark as closed
-            self.mark_closed(&state);
+        // Expand `State`
+        let mut neighbours = std::mem::take(&mut self.neighbours);
+        neighbours.clear();
+        space.for_each_neighbour(&state, |s, a| neighbours.push((s, a)));
+        for &(s, a) in &neighbours {
+            #[cfg(feature = "coz_profile")]
+            coz::scope!("ReachNode");
+            #[cfg(feature = "verify")]
+            if !self.allow_reopening {
+                Self::verify_consistent(space, heuristic, &state, &a, &s);
+            }
 
-            // Expand `State`
-            for (s, a) in self.problem.space().neighbours(&state) {
-                #[cfg(feature = "coz_profile")]
-                coz::scope!("ReachNode");
-
-                // Have we seen this `State`?
-                match self.node_map.get(&s) {
-                    Some(neigh_index) => {
-                        #[cfg(feature = "coz_profile")]
-                        coz::scope!("ReachExistingNode");
-                        if neigh_index.is_closed() {
-                            // Yes, and we expanded the State already.
-                            // NOTE: Could be a goal we had already found through a
-                            // sub-optimal path. Currently we only search for
-                            // an optimal path to a new goal.
-                            continue;
+            // Have we seen this `State`?
+            match self.node_map.get(&s) {
+                Some(neigh_index) => {
+                    #[cfg(feature = "coz_profile")]
+                    coz::scope!("ReachExistingNode");
+                    if neigh_index.is_closed() {
+                        // Yes, and we expanded the State already.
+                        // NOTE: Could be a goal we had already found through a
+                        // sub-optimal path. Currently we only search for
+                        // an optimal path to a new goal.
+                        if unlikely(self.allow_reopening) {
+                            let neigh_index = *neigh_index;
+                            let c: C = space.cost(&state, &a);
+                            let new_g = g.saturating_add(&c);
+                            if new_g < self.search_tree[neigh_index].g {
+                                self.reopen(&s, (node_index, a), new_g, depth + 1);
+                            }
                         }
+                        continue;
+                    }
 
-                        // Yes, but it's still unexplored. Update the existing
-                        // `Node` if needed.
-                        let neigh = &mut self.search_tree[*neigh_index];
-                        let neigh_heap_index = neigh.heap_index;
-                        let c: C = self.problem.space().cost(&s, &a);
-                        let new_g = g + c;
-                        if new_g < neigh.g {
-                            // Found better path to existing node
-                            neigh.reach((node_index, a), new_g);
-                            self.open[neigh_heap_index].rank.improve_g(new_g);
-                            self._unsafe_sift_up(neigh_heap_index);
-                        }
+                    // Yes, but it's still unexplored. Update the existing
+                    // `Node` if needed.
+                    let neigh = &mut self.search_tree[*neigh_index];
+                    let neigh_heap_index = neigh.heap_index;
+                    if neigh_heap_index == DROPPED {
+                        // It can't reach any remaining goal anyway.
+                        continue;
                     }
-                    None => {
-                        #[cfg(feature = "coz_profile")]
-                        coz::scope!("ReachNewNode");
-                        // No, let's create a new Node for it.
-                        let c: C = self.problem.space().cost(&s, &a);
-                        let neigh_g = g + c;
-                        let neigh_h = self.h(&s);
-
-                        self.push_new(&s, Some((node_index, a)), neigh_g, neigh_h);
+                    let c: C = space.cost(&state, &a);
+                    let new_g = g.saturating_add(&c);
+                    if new_g < neigh.g {
+                        // Found better path to existing node
+                        neigh.reach((node_index, a), new_g, depth + 1);
+                        self.open[neigh_heap_index].rank.improve_g(new_g);
+                        self.open
+                            .decrease_key(neigh_heap_index, &mut self.search_tree);
+                        self.generated += 1;
                     }
                 }
-            }
-
-            // NOTE: This should be done before expanding if we could yield or
-            // only want the path to the first goal.
-            if unlikely(self.is_goal(&state)) {
-                #[cfg(feature = "coz_profile")]
-                coz::progress!("GoalFound");
-                self.remove_goal(&state);
-                return Some(self.search_tree.path(self.problem.space(), node_index));
+                None => {
+                    #[cfg(feature = "coz_profile")]
+                    coz::scope!("ReachNewNode");
+                    // No, let's create a new Node for it.
+                    let c: C = space.cost(&state, &a);
+                    let neigh_g = g.saturating_add(&c);
+
+                    self.push_new(space, &s, Some((node_index, a)), neigh_g, heuristic);
+                }
             }
         }
+        self.neighbours = neighbours;
 
-        None
+        state
     }
 
-    /// Checks if a state is an undiscovered goal.
+    /// Checks whether a state was generated (reached) by the search.
     #[inline(always)]
-    fn is_goal(&mut self, s: &St) -> bool {
-        self.remaining_goals_set.contains(s)
+    #[must_use]
+    pub(crate) fn is_generated(&self, s: &St) -> bool {
+        self.node_map.contains_key(s)
     }
 
-    /// Removes a state from the remaining goals.
-    ///
-    /// Updates the heap to re-rank states.
+    /// Gets the best known cost to reach a generated state.
     #[inline(always)]
-    fn remove_goal(&mut self, goal: &St) {
-        #[cfg(feature = "coz_profile")]
-        coz::scope!("RemoveGoal");
+    #[must_use]
+    pub(crate) fn g_of(&self, s: &St) -> Option<C> {
+        self.node_map
+            .get(s)
+            .map(|node_index| self.search_tree[*node_index].g)
+    }
 
-        // Remove the goal from the remaining goal set.
-        self.remaining_goals_set.remove(goal);
+    /// Gets the rank of the best open node without popping it.
+    #[inline(always)]
+    #[must_use]
+    pub(crate) fn peek_rank(&self) -> Option<R> {
+        self.open.peek().map(|hn| hn.rank)
+    }
 
-        // (swap-)remove the goal from the remaining goal list.
-        self.remaining_goals_list.swap_remove(
-            self.remaining_goals_list
-                .iter()
-                .position(|&s| s == *goal)
-                .unwrap(),
-        );
+    /// Gets the state of the best open node without popping it.
+    #[inline(always)]
+    #[must_use]
+    pub(crate) fn peek_state(&self) -> Option<St> {
+        self.open
+            .peek()
+            .map(|hn| *self.search_tree[hn.node_index].state())
+    }
 
-        // TODO: `ConditionProblem`s need something different.
-        // NOTE: We know the problem is a `ObjectiveProblem`, but it may also be a
-        //       `ConditionProblem`.
-        if self.remaining_goals_list.is_empty() {
-            self.open.clear();
-            return;
+    /// Reconstructs the path to an expanded state as `(state, action, g)`
+    /// steps, see `SearchTree::annotated_path`.
+    #[must_use]
+    pub(crate) fn annotated_path_to<Sp: Space<St, A, C>>(
+        &self,
+        space: &Sp,
+        s: &St,
+    ) -> Option<Vec<(St, A, C)>> {
+        let node_index = self.node_map.get(s)?;
+        if !node_index.is_closed() {
+            return None;
         }
+        Some(self.search_tree.annotated_path(space, *node_index))
+    }
 
-        // Update worsened heuristic and sift-down changed heap nodes.
-        let len = self.open.len();
-        for heap_index in (0..len).rev() {
-            let heap_node = &mut self.open[heap_index];
-            let node = &self.search_tree[heap_node.node_index];
-            let state = *node.state();
-
-            // `self.h` inlined to avoid overlapping self borrows
-            // TODO: `ConditionProblem`s need something different
-            let mut h = C::max_value();
-            for g in &self.remaining_goals_list {
-                h = min(h, OH::h(&state, g))
-            }
-
-            // Update node
-            if heap_node.rank.worsen_h(h) {
-                let new_index = self._unsafe_sift_down(heap_index);
-                // Drop the node if it became useless.
-                if h == C::max_value() && down_left(new_index) >= len {
-                    self.open.swap_remove(new_index);
-                }
-            }
+    /// Reconstructs the path to an expanded state.
+    #[must_use]
+    pub(crate) fn path_to<Sp: Space<St, A, C>>(
+        &self,
+        space: &Sp,
+        s: &St,
+    ) -> Option<Path<St, A, C>> {
+        let node_index = self.node_map.get(s)?;
+        if !node_index.is_closed() {
+            return None;
         }
-
-        self.verify_heap();
+        Some(self.path(space, *node_index))
     }
 
-    #[inline(always)]
+    /// Reconstructs the path to a node.
     #[must_use]
-    fn h(&self, s: &St) -> C {
-        let mut h = C::max_value();
-        for g in &self.remaining_goals_list {
-            h = min(h, OH::h(s, g))
+    pub(crate) fn path<Sp: Space<St, A, C>>(
+        &self,
+        space: &Sp,
+        node_index: SearchTreeIndex,
+    ) -> Path<St, A, C> {
+        // Reopening improves nodes without updating the g-values below them
+        // until they get re-expanded.
+        if self.allow_reopening {
+            return self.search_tree.stale_path(space, node_index);
         }
-        h
+        self.search_tree.path(space, node_index)
     }
 
     /// Checks if a Search Node is already Closed (was expanded and explored)
     #[inline(always)]
     #[must_use]
-    fn is_closed(&self, s: &St) -> bool {
+    pub(crate) fn is_closed(&self, s: &St) -> bool {
         match self.node_map.get(s) {
             Some(node_index) => node_index.is_closed(),
             None => false,
@@ -418,23 +675,63 @@ where
     /// Pops a node from the Heap, returning its `SearchTree` index.
     #[inline(always)]
     #[must_use]
-    fn pop(&mut self) -> Option<SearchTreeIndex> {
+    pub(crate) fn pop(&mut self) -> Option<SearchTreeIndex> {
         #[cfg(feature = "coz_profile")]
         coz::scope!("Pop");
 
-        match self.open.len() {
-            0 | 1 => self.open.pop().map(|n| n.node_index),
-            _ => {
-                self.verify_heap();
-                let node_index = self._unsafe_pop_non_trivial_heap();
-                self.verify_heap();
-                Some(node_index)
-            }
+        self.verify_heap();
+        let node_index = self.open.pop(&mut self.search_tree).map(|n| n.node_index);
+        self.verify_heap();
+        node_index
+    }
+
+    /// Drops an open node that can't reach any goal from the open list.
+    ///
+    /// It wasn't expanded, so it's left out of the open list without closing
+    /// it.
+    pub(crate) fn drop_open(&mut self, node_index: SearchTreeIndex) {
+        let heap_index = self.search_tree[node_index].heap_index;
+        self.open.remove(heap_index, &mut self.search_tree);
+        self.search_tree[node_index].heap_index = DROPPED;
+    }
+
+    /// Re-opens a closed node reached through a better path.
+    ///
+    /// The node keeps its cached heuristic, which stays admissible even if
+    /// its nearest goal was found since, but it won't be re-ranked when goals
+    /// are removed. Nodes that can't reach any remaining goal stay closed.
+    fn reopen(&mut self, s: &St, parent: (SearchTreeIndex, A), g: C, depth: u32) {
+        self.verify_heap();
+        let node_index = *self.node_map.get(s).unwrap();
+        let h = self.search_tree[node_index].h;
+        if h == C::max_value() {
+            return;
         }
+
+        let map_index = self.node_map.get_mut(s).unwrap();
+        map_index.set_open();
+        self.search_tree[node_index].reach(parent, g, depth);
+
+        self.generated += 1;
+        self.open.push(
+            AStarHeapNode {
+                rank: R::new(g, h),
+                node_index,
+            },
+            &mut self.search_tree,
+        );
+        self.verify_heap();
     }
 
     #[inline(always)]
-    fn push_new(&mut self, s: &St, parent: Option<(SearchTreeIndex, A)>, g: C, h: C) {
+    pub(crate) fn push_new<Sp, H: NodeHeuristic<Sp, St, C>>(
+        &mut self,
+        space: &Sp,
+        s: &St,
+        parent: Option<(SearchTreeIndex, A)>,
+        g: C,
+        heuristic: &mut H,
+    ) {
         self.verify_heap();
         debug_assert!(!self.is_closed(s));
 
@@ -446,20 +743,25 @@ where
         let node_index: SearchTreeIndex = self
             .search_tree
             .push(SearchTreeNode::<St, A, C>::new(heap_index, *s, parent, g));
+        let h = heuristic.h(space, s, node_index);
         let node = &mut self.search_tree[node_index];
         debug_assert_eq!(node.heap_index, heap_index);
         debug_assert_eq!(node.g, g);
+        node.h = h;
 
         // 2. Add entry to `node_map`
         debug_assert!(!node_index.is_closed());
         self.node_map.insert(*s, node_index);
 
         // 3. Add `AStarHeapNode` to open using it's `SearchTreeIndex`
-        self.open.push(AStarHeapNode {
-            rank: AStarRank::new(g, h),
-            node_index,
-        });
-        self._unsafe_sift_up(heap_index);
+        self.generated += 1;
+        self.open.push(
+            AStarHeapNode {
+                rank: R::new(g, h),
+                node_index,
+            },
+            &mut self.search_tree,
+        );
 
         self.verify_heap();
     }
@@ -472,296 +774,1750 @@ where
     #[inline(always)]
     #[cfg(feature = "verify")]
     pub(crate) fn verify_heap(&self) {
-        // Every node,
+        self.open.verify_heap();
+        // Every node has the right intrusive index set.
         for (i, e) in self.open.iter().enumerate() {
-            // - Has the right intrusive index set.
             debug_assert!(self.search_tree[e.node_index].heap_index == i);
-
-            // - Goes after its parent node, if any.
-            if i == 0 {
-                continue;
-            }
-            let p = up(i);
-            debug_assert!(
-                self.open[p].rank <= self.open[i].rank,
-                "Node[{p}]={:?} !<= child [{i}]={:?}. Out of heap of len={}",
-                self.open[p],
-                self.open[i],
-                self.open.len(),
-            );
         }
     }
 
-    /// Pops the top node from a Heap with at least 2 elements.
+    /// Checks that the heuristic doesn't drop by more than the cost of a
+    /// transition, which makes closing nodes unsound unless they can be
+    /// re-opened.
     ///
-    /// Works by unfairly sifting down the top-node to the last level, where it can
-    /// be swapped with the very last element of the array and popped
-    /// Temporarily breaks invariants around the node sifting down unfairly.
-    fn _unsafe_pop_non_trivial_heap(&mut self) -> SearchTreeIndex {
-        #[cfg(feature = "coz_profile")]
-        coz::scope!("PopNonTrivial");
-
-        debug_assert!(!self.open.is_empty(), "You can't pop from an empty heap");
-        debug_assert!(
-            self.open.len() != 1,
-            "It doesn't get easier. Why are you calling this?"
+    /// Weighted A* inflation is deliberate, so the raw heuristic is checked.
+    #[cfg(feature = "verify")]
+    fn verify_consistent<Sp, H>(space: &Sp, heuristic: &H, s: &St, a: &A, t: &St)
+    where
+        Sp: Space<St, A, C>,
+        H: NodeHeuristic<Sp, St, C>,
+    {
+        let (h_s, h_t) = (heuristic.raw_h(space, s), heuristic.raw_h(space, t));
+        let c = space.cost(s, a);
+        assert!(
+            h_s <= c.saturating_add(&h_t),
+            "Inconsistent heuristic: h({s}) = {h_s} > c({s}, {a}, {t}) + h({t}) = {c} + {h_t}"
         );
+    }
 
-        // Note that there's at least 2 nodes before we remove the best.
-        // 1. We pretend there's a hole at the root, and bubble elements up till the hole reaches the bottom.
-        // 2. If the hole is not the last element, we swap it for the last one.
-        // 3. Now the last element is the one that was at the top of the heap, we pop it.
-
-        let len = self.open.len();
-        let last = len - 1;
-
-        // Initialize bubble-down indices
-        let mut hole = 0;
-        let mut child = down_left(hole); // Initially left child, reused to track the best child
-        debug_assert!(hole < len, "The hole IS NOT a valid index");
-        debug_assert!(child < len, "Left child IS NOT a valid index");
-        debug_assert!(hole < child);
+    pub(crate) fn write_memory_stats<W: std::io::Write>(
+        &self,
+        mut out: W,
+        name: &str,
+    ) -> std::io::Result<()> {
+        use size::Size;
+        use std::mem::size_of;
+        use thousands::Separable;
 
-        loop {
-            debug_assert!(hole < len, "The hole IS NOT a valid index");
-            debug_assert!(child < len, "Left child IS NOT a valid index");
+        writeln!(out, "{name} Stats:")?;
+        let s = size_of::<SearchTreeNode<St, A, C>>();
+        let l = self.search_tree.len();
+        writeln!(
+            out,
+            "  - |Nodes|:   {} ({})",
+            l.separate_with_commas(),
+            Size::from_bytes(l * s)
+        )?;
 
-            // Find the best child
-            child = down_left(hole);
-            debug_assert_eq!(child + HEAP_ARITY, down_right(hole) + 1);
-            child += derank(&self.open[child..min(child + HEAP_ARITY, len)]);
+        let s = size_of::<AStarHeapNode<R>>();
+        let l = self.open.len();
+        let c = self.open.capacity();
+        writeln!(
+            out,
+            "  - |Open|:   {} ({})",
+            l.separate_with_commas(),
+            Size::from_bytes(l * s)
+        )?;
+        writeln!(
+            out,
+            "  - |Open|*:  {} ({})",
+            c.separate_with_commas(),
+            Size::from_bytes(c * s)
+        )?;
 
-            debug_assert!(self.open[hole].rank <= self.open[child].rank);
+        let s = size_of::<(St, SearchTreeIndex)>();
+        let l = self.node_map.len();
+        let c = self.node_map.capacity();
+        writeln!(
+            out,
+            "  - |Index|:  {} ({})",
+            l.separate_with_commas(),
+            Size::from_bytes(l * s)
+        )?;
+        writeln!(
+            out,
+            "  - |Index|*: {} ({})",
+            c.separate_with_commas(),
+            Size::from_bytes(c * s)
+        )?;
+
+        writeln!(
+            out,
+            "  - Expanded nodes: {}",
+            self.expanded.separate_with_commas()
+        )?;
+        writeln!(
+            out,
+            "  - Generated nodes: {}",
+            self.generated.separate_with_commas()
+        )?;
+
+        Ok(())
+    }
+}
+
+/// The goals an `AStarSearch` has left, which its heuristic estimates the
+/// cost to.
+#[derive(Debug)]
+struct RemainingGoals<OH, Sp, St, A, C> {
+    /// A list of remaining goals.
+    /// Used to compute objective heuristics.
+    remaining_goals_list: Vec<St>,
+    /// The open nodes ranked by each goal in `remaining_goals_list`, as it
+    /// was their nearest one.
+    ///
+    /// Removing a goal only changes the heuristic of the nodes it was nearest
+    /// to, so only those are re-ranked. Entries aren't removed as nodes leave
+    /// the open list, so they are checked before re-ranking.
+    open_by_goal: Vec<Vec<SearchTreeIndex>>,
+    /// A set of remaining goals. Used for goal checks and to cut the search
+    /// earlier.
+    /// NOTE: With short sets the list should be fine.
+    remaining_goals_set: FxHashSet<St>,
+
+    /// The heuristic inflation factor (Weighted A*).
+    weight: C,
+    /// Applies `weight` to an heuristic value.
+    ///
+    /// A function pointer avoids requiring multiplication on every `OrdCost`.
+    inflate_h: fn(C, C) -> C,
+
+    _phantom_heuristic: PhantomData<OH>,
+    _phantom_space: PhantomData<Sp>,
+    _phantom_action: PhantomData<A>,
+}
+
+impl<OH, Sp, St, A, C> RemainingGoals<OH, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[must_use]
+    fn new(goals: &[St]) -> Self {
+        Self {
+            remaining_goals_list: goals.to_vec(),
+            open_by_goal: vec![Vec::new(); goals.len()],
+            remaining_goals_set: FxHashSet::from_iter(goals.iter().cloned()),
+
+            weight: C::zero(),
+            inflate_h: |h, _weight| h,
+
+            _phantom_heuristic: PhantomData,
+            _phantom_space: PhantomData,
+            _phantom_action: PhantomData,
+        }
+    }
+
+    /// Replaces the goals, keeping the Weighted A* inflation.
+    fn reset(&mut self, goals: &[St]) {
+        self.remaining_goals_list.clear();
+        self.remaining_goals_list.extend_from_slice(goals);
+        self.open_by_goal.clear();
+        self.open_by_goal.resize(goals.len(), Vec::new());
+        self.remaining_goals_set.clear();
+        self.remaining_goals_set.extend(goals.iter().cloned());
+    }
+
+    /// Checks if a state is an undiscovered goal.
+    #[inline(always)]
+    fn is_goal(&self, s: &St) -> bool {
+        self.remaining_goals_set.contains(s)
+    }
+
+    /// Computes the heuristic of a state along with the position of its
+    /// nearest goal in `remaining_goals_list`.
+    ///
+    /// Without goals there's nothing to steer towards, so it's 0 and floods
+    /// expand nodes like Dijkstra.
+    #[inline(always)]
+    #[must_use]
+    fn nearest_goal(&self, space: &Sp, s: &St) -> (C, Option<usize>) {
+        if self.remaining_goals_list.is_empty() {
+            return (C::zero(), None);
+        }
+        let mut h = C::max_value();
+        let mut nearest = None;
+        for (i, g) in self.remaining_goals_list.iter().enumerate() {
+            let goal_h = OH::h_in(space, s, g);
+            if nearest.is_none() || goal_h < h {
+                h = goal_h;
+                nearest = Some(i);
+            }
+        }
+        ((self.inflate_h)(h, self.weight), nearest)
+    }
+
+    /// Records an open node as ranked by its nearest goal.
+    #[inline(always)]
+    fn track_nearest_goal(&mut self, nearest: Option<usize>, node_index: SearchTreeIndex) {
+        if let Some(i) = nearest {
+            self.open_by_goal[i].push(node_index);
+        }
+    }
+}
+
+impl<OH, Sp, St, A, C> NodeHeuristic<Sp, St, C> for RemainingGoals<OH, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn h(&mut self, space: &Sp, s: &St, node_index: SearchTreeIndex) -> C {
+        let (h, nearest) = self.nearest_goal(space, s);
+        self.track_nearest_goal(nearest, node_index);
+        h
+    }
+
+    #[cfg(feature = "verify")]
+    fn raw_h(&self, space: &Sp, s: &St) -> C {
+        self.remaining_goals_list
+            .iter()
+            .map(|goal| OH::h_in(space, s, goal))
+            .min()
+            .unwrap_or(C::zero())
+    }
+}
+
+/// A* search implementation for Objective Problems.
+///
+/// This initializes the search and offers an Iterator that goes around
+/// different solutions.
+///
+/// Having a list of objectives allows to drop the ones already reached when
+/// reaching multiple goals.
+///
+/// NOTE: Only `OrdCost` is required, but closed nodes are not re-opened by
+/// default, so paths are only guaranteed to be optimal for non-negative costs
+/// (`Cost`) and consistent heuristics. See `set_allow_reopening`.
+///
+/// Open nodes are ranked by `R`, which sets how f-value ties are broken (see
+/// `TieBreak`), e.g. `AStarSearch<OH, OP, Sp, St, A, C, AStarRank<C, LowG>>`.
+#[derive(Debug)]
+pub struct AStarSearch<OH, OP, Sp, St, A, C, R = AStarRank<C>>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+    R: Rank<C>,
+{
+    /// The Search Tree, open list and node map.
+    nodes: AStarNodes<St, A, C, R>,
+
+    /// The goals left, used for goal checks and the heuristic.
+    goals: RemainingGoals<OH, Sp, St, A, C>,
+
+    /// The number of open nodes re-ranked after removing goals.
+    reranked: usize,
+
+    /// The maximum number of actions a returned path can have.
+    max_path_len: Option<usize>,
+    /// Whether the last `find_next_goal` call rejected paths longer than
+    /// `max_path_len`.
+    rejected_too_long: bool,
+
+    problem: OP,
+}
+
+/// A* search implementation for Objective Problems.
+impl<OH, OP, Sp, St, A, C, R> AStarSearch<OH, OP, Sp, St, A, C, R>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+    R: Rank<C>,
+{
+    /// Initializes the Search with small default capacities.
+    ///
+    /// Searches expected to explore large spaces can ask for more with
+    /// `with_capacity`.
+    #[must_use]
+    pub fn new(op: OP) -> Self {
+        Self::with_capacity(op, DEFAULT_OPEN_CAPACITY, DEFAULT_NODE_MAP_CAPACITY)
+    }
+
+    /// Initializes the Search pre-allocating its open list, node map and
+    /// Search Tree.
+    ///
+    /// The node map and Search Tree hold every generated state, so on problems
+    /// exploring most of the space `node_capacity_hint` is a good
+    /// `map_capacity`. The open list only holds the frontier, which tends to be
+    /// much smaller.
+    #[must_use]
+    pub fn with_capacity(op: OP, open_capacity: usize, map_capacity: usize) -> Self {
+        let mut search = Self::with_capacity_unseeded(op, open_capacity, map_capacity);
+        search.push_starts();
+        search
+    }
+
+    /// Initializes the Search without pushing the starts.
+    #[must_use]
+    fn with_capacity_unseeded(op: OP, open_capacity: usize, map_capacity: usize) -> Self {
+        Self {
+            nodes: AStarNodes::with_capacity(open_capacity, map_capacity),
+            goals: RemainingGoals::new(op.goals()),
+            reranked: 0,
+            max_path_len: None,
+            rejected_too_long: false,
+            problem: op,
+        }
+    }
+
+    /// Initializes a Weighted A* Search ranking nodes by `g + weight * h`.
+    ///
+    /// With an admissible heuristic paths cost at most `weight` times the
+    /// optimal cost. Weights below 1 are raised to 1.
+    #[must_use]
+    pub fn new_weighted(op: OP, weight: C) -> Self
+    where
+        C: SaturatingMul + One,
+    {
+        let mut search =
+            Self::with_capacity_unseeded(op, DEFAULT_OPEN_CAPACITY, DEFAULT_NODE_MAP_CAPACITY);
+        search.goals.weight = max(weight, C::one());
+        search.goals.inflate_h = |h, weight| h.saturating_mul(&weight);
+        search.push_starts();
+
+        search
+    }
+
+    /// Re-initializes the Search for a new Problem.
+    ///
+    /// This keeps the allocated capacity around, which helps when solving many
+    /// problems on the same Space.
+    pub fn reset(&mut self, op: OP) {
+        self.nodes.clear();
+        self.goals.reset(op.goals());
+        self.reranked = 0;
+
+        self.problem = op;
+
+        self.push_starts();
+    }
+
+    /// Captures the progress of the Search so it can be resumed later.
+    ///
+    /// The Weighted A* weight, re-opening and the maximum path length are
+    /// saved along with it. The deadline, expansion budget and hooks are not.
+    #[cfg(feature = "serde")]
+    #[must_use]
+    pub fn snapshot(&self) -> AStarSnapshot<St, A, C> {
+        let nodes = &self.nodes;
+        let positions: FxHashMap<St, usize> = nodes
+            .node_map
+            .keys()
+            .enumerate()
+            .map(|(i, s)| (*s, i))
+            .collect();
+        let position = |i: SearchTreeIndex| positions[nodes.search_tree[i].state()];
+
+        let snapshot_nodes = nodes
+            .node_map
+            .iter()
+            .map(|(s, i)| {
+                let node = &nodes.search_tree[*i];
+                AStarSnapshotNode {
+                    state: *s,
+                    parent: node.parent.map(|(p, a)| (position(p), a)),
+                    g: node.g,
+                    h: node.h,
+                    depth: node.depth,
+                    closed: i.is_closed(),
+                }
+            })
+            .collect();
+        let open = nodes
+            .open
+            .iter()
+            .map(|hn| position(hn.node_index))
+            .collect();
+
+        AStarSnapshot {
+            nodes: snapshot_nodes,
+            open,
+            remaining_goals: self.goals.remaining_goals_list.clone(),
+            expanded: nodes.expanded,
+            generated: nodes.generated,
+
+            // Unweighted searches have no weight, see `RemainingGoals::new`.
+            weight: (!self.goals.weight.is_zero()).then_some(self.goals.weight),
+            allow_reopening: nodes.allow_reopening,
+            max_path_len: self.max_path_len,
+        }
+    }
+
+    /// Resumes a Search from a snapshot taken on the same Problem.
+    ///
+    /// # Panics
+    ///
+    /// Snapshots of Weighted A* searches need `resume_weighted`.
+    #[cfg(feature = "serde")]
+    #[must_use]
+    pub fn resume(op: OP, snapshot: AStarSnapshot<St, A, C>) -> Self {
+        assert!(
+            snapshot.weight.is_none(),
+            "Weighted A* snapshots need `resume_weighted`"
+        );
+        Self::restore(op, snapshot)
+    }
+
+    /// Resumes a Search, weighted or not, from a snapshot taken on the same
+    /// Problem.
+    #[cfg(feature = "serde")]
+    #[must_use]
+    pub fn resume_weighted(op: OP, snapshot: AStarSnapshot<St, A, C>) -> Self
+    where
+        C: SaturatingMul + One,
+    {
+        let weight = snapshot.weight;
+        let mut search = Self::restore(op, snapshot);
+        // Open nodes keep their inflated `h`, so only new nodes need it.
+        if let Some(weight) = weight {
+            search.goals.weight = weight;
+            search.goals.inflate_h = |h, weight| h.saturating_mul(&weight);
+        }
+        search
+    }
+
+    #[cfg(feature = "serde")]
+    #[must_use]
+    fn restore(op: OP, snapshot: AStarSnapshot<St, A, C>) -> Self {
+        let mut search = Self::with_capacity_unseeded(
+            op,
+            max(snapshot.open.len(), DEFAULT_OPEN_CAPACITY),
+            snapshot.nodes.len(),
+        );
+        search.goals.reset(&snapshot.remaining_goals);
+        search.max_path_len = snapshot.max_path_len;
+        let nodes = &mut search.nodes;
+        nodes.expanded = snapshot.expanded;
+        nodes.generated = snapshot.generated;
+        nodes.allow_reopening = snapshot.allow_reopening;
+
+        // Parents may come after their children, so they are linked once all
+        // the nodes exist.
+        let indices: Vec<SearchTreeIndex> = snapshot
+            .nodes
+            .iter()
+            .map(|n| {
+                let i = nodes
+                    .search_tree
+                    .push(SearchTreeNode::new(DROPPED, n.state, None, n.g));
+                let mut map_index = i;
+                if n.closed {
+                    map_index.set_closed();
+                }
+                nodes.node_map.insert(n.state, map_index);
+                i
+            })
+            .collect();
+        for (n, i) in snapshot.nodes.iter().zip(&indices) {
+            let node = &mut nodes.search_tree[*i];
+            node.parent = n.parent.map(|(p, a)| (indices[p], a));
+            node.h = n.h;
+            node.depth = n.depth;
+        }
+
+        // Pushing nodes in heap order doesn't move them.
+        for p in snapshot.open {
+            let node_index = indices[p];
+            let node = &nodes.search_tree[node_index];
+            let rank = R::new(node.g, node.h);
+            nodes
+                .open
+                .push(AStarHeapNode { rank, node_index }, &mut nodes.search_tree);
+            let (_h, nearest) = search
+                .goals
+                .nearest_goal(search.problem.space(), &snapshot.nodes[p].state);
+            search.goals.track_nearest_goal(nearest, node_index);
+        }
+        nodes.verify_heap();
+
+        search
+    }
+
+    #[inline(always)]
+    fn push_starts(&mut self) {
+        for s in self.problem.starts() {
+            let g: C = C::zero();
+            let parent: Option<(SearchTreeIndex, A)> = None;
+            self.nodes
+                .push_new(self.problem.space(), s, parent, g, &mut self.goals);
+        }
+    }
+
+    /// Runs the search until the first goal is found.
+    ///
+    /// It removes the goal from the remaining goals to help focus the search on
+    /// the remaining goals.
+    #[must_use]
+    pub fn find_next_goal(&mut self) -> Option<Path<St, A, C>> {
+        #[cfg(feature = "coz_profile")]
+        coz::scope!("FindNextGoal");
+
+        self.nodes.start_call();
+        self.rejected_too_long = false;
+        if self.goals.remaining_goals_set.is_empty() {
+            return None;
+        }
+
+        // Check remaining un-explored nodes
+        // NOTE: We could avoid a `Heap::pop()` by peeking and doing the goal-check.
+        // TODO: See if `pop_node()` would be the same or faster that `pop()`
+        while let Some(node_index) = self.nodes.next_node() {
+            let state = self
+                .nodes
+                .expand(self.problem.space(), node_index, &mut self.goals);
+
+            // NOTE: This should be done before expanding if we could yield or
+            // only want the path to the first goal.
+            if unlikely(self.goals.is_goal(&state)) {
+                #[cfg(feature = "coz_profile")]
+                coz::progress!("GoalFound");
+                self.remove_goal(&state);
+                if unlikely(self.exceeds_max_path_len(node_index)) {
+                    // Treat the goal as unreachable
+                    self.rejected_too_long = true;
+                    continue;
+                }
+                let path = self.nodes.path(self.problem.space(), node_index);
+                #[cfg(feature = "verify")]
+                self.verify_admissible(&path);
+                return Some(path);
+            }
+        }
+
+        None
+    }
+
+    /// Expands the open nodes in order until exhausting the open list,
+    /// yielding every expanded state.
+    ///
+    /// Unlike `find_next_goal`, it doesn't stop on goals, which floods the
+    /// whole reachable space even for problems without goals. Goals expanded
+    /// this way are dropped from the remaining goals without a path, but like
+    /// on `solve_all` they are kept in the heuristic, so no open node is
+    /// re-ranked or dropped.
+    pub fn expanded_states(&mut self) -> impl Iterator<Item = St> + '_ {
+        std::iter::from_fn(move || {
+            let node_index = self.nodes.pop()?;
+            let state = self
+                .nodes
+                .expand(self.problem.space(), node_index, &mut self.goals);
+            if unlikely(self.goals.is_goal(&state)) {
+                self.goals.remaining_goals_set.remove(&state);
+            }
+            Some(state)
+        })
+    }
+
+    /// Runs the search until the next goal is found, telling why it failed
+    /// otherwise.
+    #[must_use]
+    pub fn solve_reporting(&mut self) -> SearchOutcome<St, A, C> {
+        if self.goals.remaining_goals_set.is_empty() {
+            return SearchOutcome::NoGoals;
+        }
+        match self.find_next_goal() {
+            Some(path) => SearchOutcome::Found(path),
+            None if self.budget_exhausted() || self.timed_out() => SearchOutcome::Interrupted,
+            None if self.rejected_too_long => SearchOutcome::TooLong,
+            None => SearchOutcome::Unreachable,
+        }
+    }
+
+    /// Runs the search to completion finding a path to every remaining goal.
+    ///
+    /// Unlike iterating over the search, found goals are kept in the
+    /// heuristic, which avoids re-ranking the open list after each of them.
+    /// The minimum of consistent heuristics is consistent, so goals are still
+    /// closed through optimal paths. The deadline and expansion budget are
+    /// ignored.
+    ///
+    /// With re-opening, goals can be expanded again through better paths, so
+    /// the search goes on after reaching every goal until no open node can
+    /// improve them.
+    #[must_use]
+    pub fn solve_all(mut self) -> FxHashMap<St, Path<St, A, C>> {
+        let mut paths = FxHashMap::default();
+        // The cost each goal was expanded at.
+        let mut found: FxHashMap<St, C> = FxHashMap::default();
+        let goal_count = self.goals.remaining_goals_set.len();
+        let mut worst_g = C::zero();
+
+        loop {
+            if found.len() == goal_count {
+                // Open nodes with `f >= worst_g` can't improve any found goal.
+                let improvable = self.nodes.allow_reopening
+                    && self
+                        .nodes
+                        .peek_rank()
+                        .is_some_and(|rank| rank < R::new(worst_g, C::zero()));
+                if !improvable {
+                    break;
+                }
+            }
+            let Some(node_index) = self.nodes.pop() else {
+                break;
+            };
+            let state = self
+                .nodes
+                .expand(self.problem.space(), node_index, &mut self.goals);
+
+            if unlikely(self.goals.is_goal(&state)) {
+                found.insert(state, self.nodes.search_tree[node_index].g);
+                worst_g = found.values().copied().fold(C::zero(), max);
+                if unlikely(self.exceeds_max_path_len(node_index)) {
+                    // Treat the goal as unreachable
+                    paths.remove(&state);
+                } else {
+                    paths.insert(state, self.nodes.path(self.problem.space(), node_index));
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// Caps the number of actions of returned paths.
+    ///
+    /// Goals whose optimal path is longer are treated as unreachable.
+    pub fn set_max_path_len(&mut self, max_path_len: usize) {
+        self.max_path_len = Some(max_path_len);
+    }
+
+    /// Calls `hook(state, g, h)` on every expansion.
+    ///
+    /// `h` is the heuristic towards the remaining goals, inflated on Weighted
+    /// A*. Useful for animations and debugging.
+    pub fn set_expand_hook(&mut self, hook: impl FnMut(&St, C, C) + 'static) {
+        self.nodes.set_expand_hook(hook);
+    }
+
+    /// Re-opens closed nodes when they are reached through a better path.
+    ///
+    /// Inconsistent heuristics can close nodes before finding their best
+    /// path, so this is needed to keep paths optimal with them. Re-opened nodes
+    /// are expanded again, which in the worst case takes exponentially many
+    /// expansions, and it's pointless with consistent heuristics.
+    pub fn set_allow_reopening(&mut self, allow_reopening: bool) {
+        self.nodes.allow_reopening = allow_reopening;
+    }
+
+    /// Caps the number of expansions of each `find_next_goal` call.
+    ///
+    /// Calls running out of budget return `None` without dropping goals, and
+    /// the next call resumes the search.
+    pub fn set_expansion_budget(&mut self, max_expansions: usize) {
+        self.nodes.max_expansions = Some(max_expansions);
+    }
+
+    /// Whether the last `find_next_goal` call ran out of expansions instead
+    /// of running out of goals or nodes.
+    #[inline(always)]
+    #[must_use]
+    pub fn budget_exhausted(&self) -> bool {
+        self.nodes.budget_exhausted
+    }
+
+    #[inline(always)]
+    #[must_use]
+    fn exceeds_max_path_len(&self, node_index: SearchTreeIndex) -> bool {
+        match self.max_path_len {
+            Some(max_path_len) => self.nodes.search_tree[node_index].depth as usize > max_path_len,
+            None => false,
+        }
+    }
+
+    /// Checks whether a state was generated (reached) by the search.
+    #[inline(always)]
+    #[must_use]
+    pub fn is_generated(&self, s: &St) -> bool {
+        self.nodes.is_generated(s)
+    }
+
+    /// Gets the best known cost to reach a generated state.
+    ///
+    /// It's only optimal once the state is closed.
+    #[inline(always)]
+    #[must_use]
+    pub fn g_of(&self, s: &St) -> Option<C> {
+        self.nodes.g_of(s)
+    }
+
+    /// Makes `find_next_goal` calls give up after a deadline.
+    ///
+    /// Calls running out of time return `None` without dropping goals, and
+    /// the next call resumes the search. The clock is only checked every
+    /// `DEADLINE_CHECK_INTERVAL` expansions.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.nodes.deadline = Some(deadline);
+    }
+
+    /// Whether the last `find_next_goal` call ran out of time.
+    #[inline(always)]
+    #[must_use]
+    pub fn timed_out(&self) -> bool {
+        self.nodes.timed_out
+    }
+
+    /// Gets the rank of the best open node without popping it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek_rank(&self) -> Option<R> {
+        self.nodes.peek_rank()
+    }
+
+    /// Gets the state of the best open node without popping it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek_state(&self) -> Option<St> {
+        self.nodes.peek_state()
+    }
+
+    /// Iterates over the open nodes, in heap order.
+    #[cfg(feature = "inspect")]
+    pub fn frontier(&self) -> impl Iterator<Item = (St, R)> + '_ {
+        self.nodes
+            .open
+            .iter()
+            .map(|hn| (*self.nodes.search_tree[hn.node_index].state(), hn.rank))
+    }
+
+    /// The number of nodes expanded so far.
+    #[inline(always)]
+    #[must_use]
+    pub fn expanded_count(&self) -> usize {
+        self.nodes.expanded
+    }
+
+    /// The number of nodes generated so far.
+    ///
+    /// Nodes reached again through a better path count again.
+    #[inline(always)]
+    #[must_use]
+    pub fn generated_count(&self) -> usize {
+        self.nodes.generated
+    }
+
+    /// The number of open nodes re-ranked so far after finding goals.
+    #[inline(always)]
+    #[must_use]
+    pub fn reranked_count(&self) -> usize {
+        self.reranked
+    }
+
+    /// Reconstructs the optimal path to an expanded state.
+    ///
+    /// Returns `None` if the state wasn't reached or is still open, as its
+    /// cost may still improve.
+    #[must_use]
+    pub fn path_to(&self, s: &St) -> Option<Path<St, A, C>> {
+        self.nodes.path_to(self.problem.space(), s)
+    }
+
+    /// Reconstructs the optimal path to an expanded state as
+    /// `(state, action, g)` steps, with the cost accumulated up to each state.
+    ///
+    /// Returns `None` like `path_to`.
+    #[must_use]
+    pub fn annotated_path_to(&self, s: &St) -> Option<Vec<(St, A, C)>> {
+        self.nodes.annotated_path_to(self.problem.space(), s)
+    }
+
+    /// Removes a state from the remaining goals.
+    ///
+    /// Updates the heap to re-rank states.
+    #[inline(always)]
+    fn remove_goal(&mut self, goal: &St) {
+        #[cfg(feature = "coz_profile")]
+        coz::scope!("RemoveGoal");
+
+        let space = self.problem.space();
+        let goals = &mut self.goals;
+        let nodes = &mut self.nodes;
+
+        // Remove the goal from the remaining goal set.
+        goals.remaining_goals_set.remove(goal);
+
+        // (swap-)remove the goal from the remaining goal list, along with the
+        // open nodes it was nearest to.
+        let goal_index = goals
+            .remaining_goals_list
+            .iter()
+            .position(|&s| s == *goal)
+            .unwrap();
+        goals.remaining_goals_list.swap_remove(goal_index);
+        let nearest_open = goals.open_by_goal.swap_remove(goal_index);
+
+        if goals.remaining_goals_list.is_empty() {
+            nodes.open.clear();
+            return;
+        }
+
+        // Update worsened heuristic and sift-down changed heap nodes.
+        let mut useless = Vec::new();
+        for node_index in nearest_open {
+            // Skip nodes that left the open list since.
+            let heap_index = nodes.search_tree[node_index].heap_index;
+            if heap_index >= nodes.open.len() || nodes.open[heap_index].node_index != node_index {
+                continue;
+            }
+
+            let state = *nodes.search_tree[node_index].state();
+            let h = goals.h(space, &state, node_index);
+            nodes.search_tree[node_index].h = h;
+            self.reranked += 1;
+
+            // Update node
+            if nodes.open[heap_index].rank.worsen_h(h) {
+                if h == C::max_value() {
+                    useless.push(node_index);
+                }
+                nodes.open.increase_key(heap_index, &mut nodes.search_tree);
+            }
+        }
+
+        // Drop nodes that can't reach any remaining goal.
+        for node_index in useless {
+            nodes.drop_open(node_index);
+        }
+
+        nodes.verify_heap();
+    }
+
+    /// Checks that the heuristic doesn't overestimate the cost left along a
+    /// path to a goal, which would make it inadmissible.
+    ///
+    /// Weighted A* inflation is deliberate, so the raw heuristic is checked.
+    #[cfg(feature = "verify")]
+    fn verify_admissible(&self, path: &Path<St, A, C>) {
+        let Some(goal) = path.end() else {
+            return;
+        };
+        // A saturated cost doesn't tell how far the goal really is.
+        if !path.cost().valid() {
+            return;
+        }
+        let mut remaining = path.cost();
+        for (s, _a, c) in path.steps(self.problem.space()) {
+            let h = OH::h_in(self.problem.space(), &s, &goal);
+            assert!(
+                h <= remaining,
+                "Inadmissible heuristic: h({s}, {goal}) = {h}, but {goal} is {remaining} away"
+            );
+            remaining = remaining - c;
+        }
+    }
+
+    pub fn write_memory_stats<W: std::io::Write>(&self, out: W) -> std::io::Result<()> {
+        self.nodes.write_memory_stats(out, "AStarSearch")
+    }
+    pub fn print_memory_stats(&self) {
+        self.write_memory_stats(std::io::stdout().lock()).unwrap()
+    }
+}
+
+impl<OH, OP, Sp, St, A, C, R> Iterator for AStarSearch<OH, OP, Sp, St, A, C, R>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+    R: Rank<C>,
+{
+    type Item = Path<St, A, C>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.find_next_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::problems::maze_2d::Maze2DAction;
+    use crate::problems::maze_2d::Maze2DCost;
+    use crate::problems::maze_2d::Maze2DProblem;
+    use crate::problems::maze_2d::Maze2DSpace;
+    use crate::problems::maze_2d::Maze2DState;
+
+    #[cfg(feature = "inspect")]
+    #[test]
+    fn frontier() {
+        use indoc::indoc;
+
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S...
+            .G..
+            ...G
+        "})
+        .unwrap();
+        let start = problem.starts()[0];
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+
+        let frontier: Vec<_> = search.frontier().collect();
+        assert_eq!(frontier.len(), 1);
+        assert_eq!(frontier[0].0, start);
+
+        let first = search.find_next_goal().unwrap();
+        let remaining = search.goals.remaining_goals_list[0];
+        assert_ne!(first.end(), Some(remaining));
+
+        // Open nodes were re-ranked towards the remaining goal.
+        let frontier: Vec<_> = search.frontier().collect();
+        assert_eq!(frontier.len(), search.nodes.open.len());
+        for (s, rank) in &frontier {
+            assert!(!search.nodes.is_closed(s));
+            let h =
+                <Maze2DHeuristicDiagonalDistance as ObjectiveHeuristic<Maze2DSpace, _, _, _>>::h(
+                    s, &remaining,
+                );
+            assert_eq!(rank.h, h);
+            assert_eq!(rank.f, search.g_of(s).unwrap() + h);
+        }
+        assert_eq!(
+            frontier.iter().map(|(_s, rank)| *rank).min(),
+            search.peek_rank()
+        );
+    }
+
+    #[test]
+    fn rank_saturation() {
+        let near_max = u32::MAX - 10;
+
+        // Infinite values saturate on purpose.
+        assert_eq!(AStarRank::<_>::new(u32::MAX, 5).f, u32::MAX);
+        assert_eq!(AStarRank::<_>::new(near_max, u32::MAX).f, u32::MAX);
+
+        let mut rank = AStarRank::<_>::new(near_max, 9);
+        assert_eq!(rank.f, u32::MAX - 1);
+        rank.improve_g(near_max - 1);
+        assert_eq!(rank.f, u32::MAX - 2);
+        assert!(rank.worsen_h(u32::MAX));
+        assert_eq!(rank.f, u32::MAX);
+    }
+
+    #[test]
+    fn rank_parts() {
+        let mut rank = AStarRank::<_>::new(300, 200);
+        assert_eq!((rank.f(), rank.h()), (500, 200));
+        assert_eq!(AStarRank::from_parts(rank.f(), rank.h()), rank);
+
+        rank.improve_g(100);
+        assert!(rank.worsen_h(250));
+        assert_eq!((rank.f(), rank.h()), (350, 250));
+        assert_eq!(AStarRank::from_parts(rank.f(), rank.h()), rank);
+
+        // Rebuilt ranks keep breaking ties like the originals.
+        let low = AStarRank::<_, LowG>::new(200, 100);
+        let high = AStarRank::<_, LowG>::new(100, 200);
+        let rebuilt = |r: AStarRank<u32, LowG>| AStarRank::<u32, LowG>::from_parts(r.f(), r.h());
+        assert_eq!(rebuilt(low), low);
+        assert!(rebuilt(high) < rebuilt(low));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "overflowed")]
+    fn rank_overflow() {
+        let _rank = AStarRank::<_>::new(u32::MAX - 10, 10);
+    }
+
+    #[test]
+    fn peek() {
+        use indoc::indoc;
+
+        use crate::problem::BaseProblem;
+        use crate::problem::ObjectiveProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.....
+            ...#..
+            ...#.G
+        "})
+        .unwrap();
+        let start = problem.starts()[0];
+        let goal = problem.goals()[0];
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+
+        let h = <Maze2DHeuristicDiagonalDistance as ObjectiveHeuristic<
+            Maze2DSpace,
+            _,
+            Maze2DAction,
+            _,
+        >>::h(&start, &goal);
+        assert_eq!(search.peek_rank(), Some(AStarRank::<_>::new(0, h)));
+        assert_eq!(search.peek_state(), Some(start));
+
+        assert!(search.find_next_goal().is_some());
+        assert_eq!(search.peek_rank(), None);
+        assert_eq!(search.peek_state(), None);
+    }
+
+    #[test]
+    fn solve_all() {
+        use indoc::indoc;
+
+        use crate::algorithms::dijkstra::DijkstraSearch;
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            G...#...G
+            .##.#.#..
+            ..S...#.G
+        "})
+        .unwrap();
+        let space = problem.space().clone();
+        let distances = DijkstraSearch::new(problem.clone()).into_distance_map();
+
+        let paths =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone())
+                .solve_all();
+        assert_eq!(paths.len(), 3);
+        for goal in problem.goals() {
+            let path = &paths[goal];
+            assert!(space.valid_path(path));
+            assert_eq!(path.end(), Some(*goal));
+            assert_eq!(path.cost(), distances[goal]);
+        }
+
+        // The same paths are found one goal at a time.
+        let search = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        for path in search {
+            assert_eq!(path.cost(), paths[&path.end().unwrap()].cost());
+        }
+    }
+
+    #[test]
+    fn flood() {
+        use crate::problem::FloodProblem;
+        use crate::problems::maze_2d::Connectivity;
+        use crate::problems::maze_2d::Maze2DCell;
+        use crate::problems::maze_2d::Maze2DHeuristicManhattanDistance;
+
+        let mut space =
+            Maze2DSpace::new_empty_with_dimensions(4, 3).with_connectivity(Connectivity::Four);
+        // Walls off the bottom-right corner.
+        space.set_cell(&Maze2DState::new(2, 2).unwrap(), Maze2DCell::Wall);
+        space.set_cell(&Maze2DState::new(3, 1).unwrap(), Maze2DCell::Wall);
+        let start = Maze2DState::new(0, 0).unwrap();
+        let open_cells = 4 * 3 - 3;
+
+        let mut search = AStarSearch::<Maze2DHeuristicManhattanDistance, _, _, _, _, _>::new(
+            FloodProblem::new(space.clone(), start),
+        );
+        assert_eq!(search.find_next_goal(), None);
+        assert_eq!(search.expanded_count(), 0);
+
+        let visited: Vec<_> = search.expanded_states().collect();
+        assert_eq!(visited.len(), open_cells);
+        assert!(!visited.contains(&Maze2DState::new(3, 2).unwrap()));
+        assert_eq!(
+            FxHashSet::from_iter(visited.iter().cloned()).len(),
+            visited.len()
+        );
+        assert_eq!(search.expanded_count(), visited.len());
+
+        // Goals don't cut the flood short.
+        let goal = Maze2DState::new(1, 0).unwrap();
+        let mut search = AStarSearch::<Maze2DHeuristicManhattanDistance, _, _, _, _, _>::new(
+            FloodProblem::new(space, start).with_goal(goal),
+        );
+        let visited: Vec<_> = search.expanded_states().collect();
+        assert_eq!(visited.len(), open_cells);
+        assert_eq!(search.find_next_goal(), None);
+    }
+
+    #[test]
+    fn flood_goals() {
+        use indoc::indoc;
+
+        use crate::algorithms::dijkstra::DijkstraSearch;
+
+        /// Only the goals on the left column are reachable, so re-ranking
+        /// towards the others would drop every open node.
+        #[derive(Debug)]
+        struct LeftColumn;
+        impl ObjectiveHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for LeftColumn {
+            fn h(_a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+                if b.x.get() == 0 { 0 } else { Maze2DCost::MAX }
+            }
+        }
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S...#.
+            ..#.#G
+            G.#..G
+        "})
+        .unwrap();
+        let reachable = DijkstraSearch::new(problem.clone()).into_distance_map();
+
+        let mut search = AStarSearch::<LeftColumn, _, _, _, _, _>::new(problem);
+        let visited: FxHashSet<_> = search.expanded_states().collect();
+        assert_eq!(visited.len(), search.expanded_count());
+        assert_eq!(visited, reachable.keys().copied().collect());
+        assert_eq!(search.find_next_goal(), None);
+    }
+
+    // `verify` evaluates the heuristic on its own to check it.
+    #[test]
+    #[cfg(not(feature = "verify"))]
+    fn cached_h() {
+        use std::cell::RefCell;
+
+        use indoc::indoc;
+
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        thread_local! {
+            /// The heuristic evaluations of each `(state, goal)` pair.
+            static CALLS: RefCell<FxHashMap<(Maze2DState, Maze2DState), usize>> =
+                RefCell::new(FxHashMap::default());
+        }
+        let take_calls = || CALLS.with(|calls| calls.take());
+
+        #[derive(Debug)]
+        struct Counting;
+        impl ObjectiveHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Counting {
+            fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+                CALLS.with(|calls| *calls.borrow_mut().entry((*a, *b)).or_default() += 1);
+                <Maze2DHeuristicDiagonalDistance as ObjectiveHeuristic<
+                    Maze2DSpace,
+                    Maze2DState,
+                    Maze2DAction,
+                    Maze2DCost,
+                >>::h(a, b)
+            }
+        }
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S....#...
+            .###.#.#.
+            ...#...#G
+        "})
+        .unwrap();
+        let goal = problem.goals()[0];
+        take_calls();
+        let mut search = AStarSearch::<Counting, _, _, _, _, _>::new(problem);
+        let _ = search.find_next_goal().unwrap();
+        let calls = take_calls();
+        assert!(calls.values().all(|&n| n == 1));
+        assert_eq!(calls.len(), search.nodes.node_map.len());
+        assert!(
+            search
+                .nodes
+                .node_map
+                .keys()
+                .all(|s| calls.contains_key(&(*s, goal)))
+        );
+
+        // Removing a goal only re-computes the nodes ranked by it, towards the
+        // remaining goal.
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S....#...
+            .###.#.#.
+            G..#...#G
+        "})
+        .unwrap();
+        let goals = problem.goals().to_vec();
+        let mut search = AStarSearch::<Counting, _, _, _, _, _>::new(problem);
+        let first = search.find_next_goal().unwrap().end().unwrap();
+        let remaining = *goals.iter().find(|&&g| g != first).unwrap();
+        let calls = take_calls();
+        let generated: Vec<Maze2DState> = search.nodes.node_map.keys().copied().collect();
+        for s in &generated {
+            // Once on generation, and once more if re-ranked.
+            assert_eq!(calls[&(*s, first)], 1);
+            assert!((1..=2).contains(&calls[&(*s, remaining)]));
+        }
+        assert_eq!(calls.len(), 2 * generated.len());
+        assert!(search.reranked_count() > 0);
+        assert_eq!(
+            calls.values().filter(|&&n| n == 2).count(),
+            search.reranked_count()
+        );
+
+        // New nodes only compute `h` towards the remaining goal.
+        let reranked = search.reranked_count();
+        let _ = search.find_next_goal().unwrap();
+        assert_eq!(search.reranked_count(), reranked);
+        let calls = take_calls();
+        assert!(calls.values().all(|&n| n == 1));
+        assert!(
+            calls
+                .keys()
+                .all(|(s, g)| *g == remaining && !generated.contains(s))
+        );
+        assert_eq!(calls.len(), search.nodes.node_map.len() - generated.len());
+    }
+
+    #[test]
+    fn rerank_nearest_goal_only() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::algorithms::dijkstra::DijkstraSearch;
+        use crate::problem::ObjectiveProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        // Walls every 6 columns, with a gap at a different row each time.
+        let (width, height) = (120, 60);
+        let maze: String = (0..height)
+            .map(|y| {
+                let mut row: String = (0..width)
+                    .map(|x| {
+                        if x % 6 == 5 && y != (x * 7) % height {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect();
+                row.push('\n');
+                row
+            })
+            .collect();
+        let mut base = Maze2DProblem::try_from(maze.as_str()).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let problem = base.randomize(&mut rng, 1, 10).unwrap();
+        let distances = DijkstraSearch::new(problem.clone()).into_distance_map();
+
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        // A full scan re-ranks at least the nodes left open after each goal.
+        let mut full_scan = 0;
+        let mut found = 0;
+        while let Some(path) = search.find_next_goal() {
+            full_scan += search.nodes.open.len();
+            found += 1;
+            assert_eq!(path.cost(), distances[&path.end().unwrap()]);
+        }
+        let reachable = problem
+            .goals()
+            .iter()
+            .filter(|g| distances.contains_key(g))
+            .count();
+        assert_eq!(found, reachable);
+        assert!(found > 1);
+        assert!(search.reranked_count() < full_scan);
+    }
+
+    #[test]
+    fn solve_reporting() {
+        use indoc::indoc;
+
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S..#G
+            ...##
+            G....
+        "})
+        .unwrap();
+
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        assert!(matches!(search.solve_reporting(), SearchOutcome::Found(_)));
+        assert_eq!(search.solve_reporting(), SearchOutcome::Unreachable);
+        assert_eq!(search.solve_reporting(), SearchOutcome::Unreachable);
+
+        let mut search = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(
+            Maze2DProblem::try_from("S...\n").unwrap(),
+        );
+        assert_eq!(search.solve_reporting(), SearchOutcome::NoGoals);
+
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        search.set_expansion_budget(1);
+        assert_eq!(search.solve_reporting(), SearchOutcome::Interrupted);
+    }
+
+    #[test]
+    fn expand_hook() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        use indoc::indoc;
+
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#..
+            ..#.G
+            .....
+        "})
+        .unwrap();
+        let start = problem.starts()[0];
+
+        let expanded = Rc::new(RefCell::new(vec![]));
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        let hook_expanded = expanded.clone();
+        search.set_expand_hook(move |s, g, h| hook_expanded.borrow_mut().push((*s, g, h)));
+        assert!(search.find_next_goal().is_some());
+
+        let expanded = expanded.borrow();
+        assert_eq!(expanded.len(), search.expanded_count());
+        assert_eq!(expanded[0].0, start);
+        assert_eq!(expanded[0].1, 0);
+        assert!(expanded.iter().any(|(_s, _g, h)| *h == 0));
+    }
+
+    #[cfg(all(feature = "serde", feature = "image"))]
+    #[test]
+    fn snapshot() {
+        use std::path::PathBuf;
+
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        let path = search.find_next_goal().unwrap();
+
+        let mut interrupted =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        interrupted.set_expansion_budget(search.expanded_count() / 2);
+        assert_eq!(interrupted.find_next_goal(), None);
+        assert!(interrupted.budget_exhausted());
+
+        let ron = ron::to_string(&interrupted.snapshot()).unwrap();
+        let snapshot: AStarSnapshot<Maze2DState, Maze2DAction, Maze2DCost> =
+            ron::from_str(&ron).unwrap();
+        let mut resumed = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::resume(
+            problem, snapshot,
+        );
+        assert_eq!(resumed.nodes.open.len(), interrupted.nodes.open.len());
+        assert_eq!(
+            resumed.nodes.node_map.len(),
+            interrupted.nodes.node_map.len()
+        );
+
+        let resumed_path = resumed.find_next_goal().unwrap();
+        assert_eq!(resumed_path.cost(), path.cost());
+        assert_eq!(resumed.expanded_count(), search.expanded_count());
+    }
+
+    #[cfg(all(feature = "serde", feature = "image"))]
+    #[test]
+    fn snapshot_settings() {
+        use std::path::PathBuf;
+
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let weighted = |problem: Maze2DProblem| {
+            let mut search =
+                AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new_weighted(
+                    problem, 3,
+                );
+            search.set_allow_reopening(true);
+            search.set_max_path_len(1000);
+            search
+        };
+
+        let mut search = weighted(problem.clone());
+        let path = search.find_next_goal().unwrap();
+
+        let mut interrupted = weighted(problem.clone());
+        interrupted.set_expansion_budget(search.expanded_count() / 2);
+        assert_eq!(interrupted.find_next_goal(), None);
+
+        let ron = ron::to_string(&interrupted.snapshot()).unwrap();
+        let snapshot: AStarSnapshot<Maze2DState, Maze2DAction, Maze2DCost> =
+            ron::from_str(&ron).unwrap();
+        let mut resumed =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::resume_weighted(
+                problem, snapshot,
+            );
+        assert_eq!(resumed.goals.weight, 3);
+        assert!(resumed.nodes.allow_reopening);
+        assert_eq!(resumed.max_path_len, Some(1000));
 
-            // Swap and update internal indices
-            self._unsafe_half_swap_down(hole, child);
+        let resumed_path = resumed.find_next_goal().unwrap();
+        assert_eq!(resumed_path.cost(), path.cost());
+        assert_eq!(resumed.expanded_count(), search.expanded_count());
+    }
 
-            // Update bubble-down indices
-            hole = child;
-            child = down_left(hole); // New left child
-            if child >= self.open.len() {
-                break;
+    /// The exact distance along the bottom detour of `inconsistent_problem`,
+    /// and 0 elsewhere.
+    ///
+    /// It's admissible, but inconsistent, so the cell above the detour's end
+    /// gets closed through the muddy top row first.
+    #[derive(Debug)]
+    struct Inconsistent;
+    impl ObjectiveHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Inconsistent {
+        fn h(a: &Maze2DState, _b: &Maze2DState) -> Maze2DCost {
+            match (a.x.get(), a.y.get()) {
+                (0, 1) => 500,
+                (1, 1) => 400,
+                (2, 1) => 300,
+                _ => 0,
             }
         }
-        // NOTE: So far the hole made it to the last level, but it may not be at the end of the array.
-        debug_assert!(hole <= last, "The hole={hole} is < last={last}");
-        debug_assert!(hole > up(last), "The hole={hole} is < last={last}");
-        if hole != last {
-            // Swap and update internal indices
-            self._unsafe_half_swap_down(hole, last);
-            self._unsafe_sift_up(hole);
-        }
+    }
 
-        let heap_node = self.open.pop().unwrap();
-        debug_assert_eq!(
-            self.search_tree[heap_node.node_index].heap_index, 0,
-            "Top node half-assed swapped down should still have it's 0 index"
+    fn inconsistent_problem() -> Maze2DProblem {
+        use indoc::indoc;
+
+        use crate::problems::maze_2d::Connectivity;
+        use crate::problems::maze_2d::Maze2DTerrain;
+
+        let mut problem = Maze2DProblem::try_from(indoc! {"
+            S...G
+            ...##
+        "})
+        .unwrap()
+        .with_connectivity(Connectivity::Four);
+        problem.set_terrain(
+            &Maze2DState::new(1, 0).unwrap(),
+            Maze2DTerrain::new(5).unwrap(),
         );
+        problem
+    }
+
+    #[test]
+    fn reopening() {
+        let problem = inconsistent_problem();
+
+        // Under `verify` the inconsistency is caught instead.
+        #[cfg(not(feature = "verify"))]
+        {
+            let mut search = AStarSearch::<Inconsistent, _, _, _, _, _>::new(problem.clone());
+            assert_eq!(search.find_next_goal().unwrap().cost(), 800);
+        }
 
-        heap_node.node_index
+        let mut search = AStarSearch::<Inconsistent, _, _, _, _, _>::new(problem);
+        search.set_allow_reopening(true);
+        assert_eq!(search.find_next_goal().unwrap().cost(), 600);
     }
 
-    /// Raises a node
-    /// Returns it's new index
-    #[inline(always)]
-    fn _unsafe_sift_up(&mut self, index: usize) -> usize {
-        debug_assert!(
-            index < self.open.len(),
-            "Node is way out of sync. Index out of bounds..."
-        );
-        debug_assert_eq!(
-            self.search_tree[self.open[index].node_index].heap_index, index,
-            "Node is out of sync."
+    #[test]
+    fn solve_all_reopening() {
+        use indoc::indoc;
+
+        use crate::problems::maze_2d::Connectivity;
+        use crate::problems::maze_2d::Maze2DTerrain;
+
+        // The goal in the middle is first expanded through the muddy top row,
+        // and then re-opened through the bottom detour.
+        let mut problem = Maze2DProblem::try_from(indoc! {"
+            S.G.G
+            ...##
+        "})
+        .unwrap()
+        .with_connectivity(Connectivity::Four);
+        problem.set_terrain(
+            &Maze2DState::new(1, 0).unwrap(),
+            Maze2DTerrain::new(5).unwrap(),
         );
 
-        // Can't improve
-        if index == 0 {
-            return index;
-        }
+        let mut search = AStarSearch::<Inconsistent, _, _, _, _, _>::new(problem);
+        search.set_allow_reopening(true);
+        let paths = search.solve_all();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[&Maze2DState::new(2, 0).unwrap()].cost(), 400);
+        assert_eq!(paths[&Maze2DState::new(4, 0).unwrap()].cost(), 600);
+    }
 
-        let mut pos = index;
-        let mut parent = up(pos);
-        while self.open[parent].rank > self.open[pos].rank {
-            // Nodes are different and swapped. Swap the nodes to fix the order.
-            self._unsafe_swap(parent, pos);
-            debug_assert!(self.open[parent].rank < self.open[pos].rank);
+    #[test]
+    #[cfg(feature = "verify")]
+    #[should_panic(expected = "Inconsistent heuristic")]
+    fn inconsistent() {
+        let _ = AStarSearch::<Inconsistent, _, _, _, _, _>::new(inconsistent_problem())
+            .find_next_goal();
+    }
+
+    #[test]
+    #[cfg(feature = "verify")]
+    #[should_panic(expected = "Inadmissible heuristic")]
+    fn inadmissible() {
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
 
-            // Continue swapping upwards if needed..
-            if parent == 0 {
-                return parent;
+        /// Consistent, but overestimating every distance.
+        #[derive(Debug)]
+        struct Offset;
+        impl ObjectiveHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Offset {
+            fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+                100 + <Maze2DHeuristicDiagonalDistance as ObjectiveHeuristic<
+                    Maze2DSpace,
+                    Maze2DState,
+                    Maze2DAction,
+                    Maze2DCost,
+                >>::h(a, b)
             }
-            pos = parent;
-            parent = up(pos);
         }
-        pos
+
+        let problem = Maze2DProblem::try_from("S...G\n").unwrap();
+        let _ = AStarSearch::<Offset, _, _, _, _, _>::new(problem).find_next_goal();
     }
 
-    /// Lowers a node
-    /// Returns it's new index
-    #[inline(always)]
-    fn _unsafe_sift_down(&mut self, mut index: usize) -> usize {
-        let len = self.open.len();
-        debug_assert!(
-            index < len,
-            "Node is way out of sync. Index out of bounds..."
-        );
-        debug_assert_eq!(
-            self.search_tree[self.open[index].node_index].heap_index, index,
-            "Node is out of sync."
+    #[test]
+    fn path_to() {
+        use indoc::indoc;
+
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            ##########
+            #S......G#
+            ##########
+        "})
+        .unwrap();
+        let space = problem.space().clone();
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+
+        let middle = Maze2DState::new_from_usize(4, 1).unwrap();
+        let wall = Maze2DState::new_from_usize(4, 0).unwrap();
+        assert_eq!(search.path_to(&middle), None);
+
+        let goal_path = search.find_next_goal().unwrap();
+        let path = search.path_to(&middle).unwrap();
+        assert!(space.valid_path(&path));
+        assert_eq!(path.start(), goal_path.start());
+        assert_eq!(path.end(), Some(middle));
+        assert_eq!(path.cost(), 300);
+        assert_eq!(path.actions(), &goal_path.actions()[..3]);
+
+        let steps = search.annotated_path_to(&middle).unwrap();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps.last(), Some(&(middle, Maze2DAction::Right, 300)));
+        assert!(
+            steps
+                .iter()
+                .map(|(_s, a, _g)| *a)
+                .eq(path.actions().iter().copied())
         );
 
-        loop {
-            // Find the best child
-            let mut child = down_left(index);
-            if child >= len {
-                break;
-            }
+        assert_eq!(search.path_to(&wall), None);
+        assert_eq!(search.annotated_path_to(&wall), None);
+    }
 
-            debug_assert_eq!(child + HEAP_ARITY, down_right(index) + 1);
-            child += derank(&self.open[child..min(child + HEAP_ARITY, len)]);
+    #[test]
+    fn drop_useless_nodes() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
 
-            if self.open[index].rank <= self.open[child].rank {
-                break;
+        use indoc::indoc;
+
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        /// Knows the wall splits the maze in two.
+        #[derive(Debug)]
+        struct Sides;
+        impl ObjectiveHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Sides {
+            fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+                if (a.x.get() < 2) != (b.x.get() < 2) {
+                    return Maze2DCost::MAX;
+                }
+                <Maze2DHeuristicDiagonalDistance as ObjectiveHeuristic<Maze2DSpace, _, _, _>>::h(
+                    a, b,
+                )
             }
+        }
 
-            self._unsafe_swap(index, child);
-            debug_assert!(self.open[index].rank <= self.open[child].rank);
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#..
+            ..#.G
+            ..#..
+            G.#..
+        "})
+        .unwrap();
+        let mut search = AStarSearch::<Sides, _, _, _, _, _>::new(problem);
+        let expanded = Rc::new(RefCell::new(FxHashSet::default()));
+        let hook_expanded = expanded.clone();
+        search.set_expand_hook(move |s, _g, _h| {
+            hook_expanded.borrow_mut().insert(*s);
+        });
 
-            index = child;
+        assert!(search.find_next_goal().is_some());
+        // Nodes left open can only reach the goal past the wall.
+        assert!(search.nodes.open.is_empty());
+        let expanded = expanded.borrow();
+        assert!(search.nodes.node_map.len() > expanded.len());
+        for s in search.nodes.node_map.keys() {
+            assert_eq!(search.nodes.is_closed(s), expanded.contains(s), "{s}");
+            assert_eq!(search.path_to(s).is_some(), expanded.contains(s), "{s}");
         }
-        index
+        assert_eq!(search.find_next_goal(), None);
     }
 
-    // Swapping primitives
-    /// Swaps two elements in the heap.
-    ///
-    /// For consistency in calling code `l < r` is checked.
-    ///
-    /// Keeps the intrusive indices in sync.
-    #[inline(always)]
-    fn _unsafe_swap(&mut self, l: usize, r: usize) {
-        debug_assert!(l < r, "Swap({l}, {r}) uses wrong argument order");
+    #[test]
+    fn deadline() {
+        use std::time::Duration;
 
-        let len = self.open.len();
-        debug_assert!(l < len, "Left  swap index {l} is OUT OF BOUNDS({len})");
-        debug_assert!(r < len, "Right swap index {r} is OUT OF BOUNDS({len})");
-        self.open.swap(l, r);
-        self.search_tree[self.open[l].node_index].heap_index = l;
-        self.search_tree[self.open[r].node_index].heap_index = r;
-        debug_assert!(
-            self.open[l].rank <= self.open[r].rank,
-            "Swaps must locally restore the heap invariant."
-        );
-    }
-    /// Swaps two elements in the heap.
-    ///
-    /// For consistency in calling code `l < r` is checked.
-    ///
-    /// Only keeps the index of the element going up in sync as we should shortly
-    /// after remove the element that goes down.
-    #[inline(always)]
-    fn _unsafe_half_swap_down(&mut self, l: usize, r: usize) {
-        debug_assert!(l < r, "HalfSwapDown({l}, {r}) is wrong");
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
 
-        let len = self.open.len();
-        debug_assert!(l < len, "Left  swap index {l} is OUT OF BOUNDS({len})");
-        debug_assert!(r < len, "Right swap index {r} is OUT OF BOUNDS({len})");
-        self.open.swap(l, r);
-        self.search_tree[self.open[l].node_index].heap_index = l;
-        debug_assert!(
-            self.open[l].rank >= self.open[r].rank, // (Q: What if there's only one value? We still push node at the top down)
-            "Half-assed swap down must be unfairly pushing a node down."
-        );
-        debug_assert!(
-            self.search_tree[self.open[r].node_index].heap_index < r,
-            "Node half-assed swapped down should still point to it's original index."
-        );
-    }
+        let mut problem = Maze2DProblem::from(Maze2DSpace::new_empty_with_dimensions(300, 300));
+        assert!(problem.add_start(&Maze2DState::new_from_usize(0, 0).unwrap()));
+        assert!(problem.add_goal(&Maze2DState::new_from_usize(299, 150).unwrap()));
 
-    pub fn write_memory_stats<W: std::io::Write>(&self, mut out: W) -> std::io::Result<()> {
-        use size::Size;
-        use std::mem::size_of;
-        use thousands::Separable;
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        search.set_deadline(Instant::now());
+        assert_eq!(search.find_next_goal(), None);
+        assert!(search.timed_out());
 
-        writeln!(out, "AStarSearch Stats:")?;
-        let s = size_of::<SearchTreeNode<St, A, C>>();
-        let l = self.search_tree.len();
-        writeln!(
-            out,
-            "  - |Nodes|:   {} ({})",
-            l.separate_with_commas(),
-            Size::from_bytes(l * s)
-        )?;
+        search.set_deadline(Instant::now() + Duration::from_secs(60));
+        assert!(search.find_next_goal().is_some());
+        assert!(!search.timed_out());
+    }
 
-        let s = size_of::<AStarHeapNode<C>>();
-        let l = self.open.len();
-        let c = self.open.capacity();
-        writeln!(
-            out,
-            "  - |Open|:   {} ({})",
-            l.separate_with_commas(),
-            Size::from_bytes(l * s)
-        )?;
-        writeln!(
-            out,
-            "  - |Open|*:  {} ({})",
-            c.separate_with_commas(),
-            Size::from_bytes(c * s)
-        )?;
+    #[test]
+    fn counters() {
+        use indoc::indoc;
 
-        let s = size_of::<(St, SearchTreeIndex)>();
-        let l = self.node_map.len();
-        let c = self.node_map.capacity();
-        writeln!(
-            out,
-            "  - |Index|:  {} ({})",
-            l.separate_with_commas(),
-            Size::from_bytes(l * s)
-        )?;
-        writeln!(
-            out,
-            "  - |Index|*: {} ({})",
-            c.separate_with_commas(),
-            Size::from_bytes(c * s)
-        )?;
+        use crate::problems::maze_2d::Connectivity;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+        use crate::problems::maze_2d::Maze2DHeuristicManhattanDistance;
 
-        let expanded_nodes = self.search_tree.len() - self.open.len();
-        writeln!(
-            out,
-            "  - Expanded nodes: {}",
-            expanded_nodes.separate_with_commas()
-        )?;
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S..#....
+            .#.#.##.
+            .#...#G.
+            .####.#.
+            ......#G
+        "})
+        .unwrap();
 
-        Ok(())
-    }
-    pub fn print_memory_stats(&self) {
-        self.write_memory_stats(std::io::stdout().lock()).unwrap()
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        assert_eq!(search.expanded_count(), 0);
+        assert_eq!(search.generated_count(), 1);
+        assert_eq!(search.by_ref().count(), 2);
+        assert!(search.expanded_count() > 0);
+        assert!(search.expanded_count() <= search.generated_count());
+
+        // Manhattan distance is only admissible without diagonals.
+        let problem = problem.with_connectivity(Connectivity::Four);
+        let mut search =
+            AStarSearch::<Maze2DHeuristicManhattanDistance, _, _, _, _, _>::new(problem);
+        assert_eq!(search.by_ref().count(), 2);
+        assert!(search.expanded_count() <= search.generated_count());
     }
-}
 
-impl<OH, OP, Sp, St, A, C> Iterator for AStarSearch<OH, OP, Sp, St, A, C>
-where
-    OH: ObjectiveHeuristic<Sp, St, A, C>,
-    OP: ObjectiveProblem<Sp, St, A, C>,
-    Sp: Space<St, A, C>,
-    St: State,
-    A: Action,
-    C: Cost,
-{
-    type Item = Path<St, A, C>;
-    fn next(&mut self) -> Option<Self::Item> {
-        self.find_next_goal()
+    #[test]
+    #[cfg(feature = "image")]
+    fn tie_break() {
+        use std::path::PathBuf;
+
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+
+        fn solve<T: TieBreak>(problem: Maze2DProblem) -> usize {
+            let mut search = AStarSearch::<
+                Maze2DHeuristicDiagonalDistance,
+                _,
+                _,
+                _,
+                _,
+                _,
+                AStarRank<_, T>,
+            >::new(problem);
+            let path = search.find_next_goal().unwrap();
+            assert_eq!(path.cost(), 1805);
+            search.expanded_count()
+        }
+        let low_h = solve::<LowH>(problem.clone());
+        let low_g = solve::<LowG>(problem);
+        assert!(low_h < low_g, "{low_h} >= {low_g}");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn tie_break_plateau() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        use indoc::indoc;
+
+        use crate::problems::maze_2d::Connectivity;
+        use crate::problems::maze_2d::Maze2DHeuristicManhattanDistance;
+
+        // Every cell is on a shortest path, so they all tie on f.
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S....
+            .....
+            .....
+            .....
+            ....G
+        "})
+        .unwrap()
+        .with_connectivity(Connectivity::Four);
+
+        /// Solves the problem getting the g-values of the expanded nodes.
+        fn expanded_g<T: TieBreak>(problem: Maze2DProblem) -> Vec<Maze2DCost> {
+            let expanded = Rc::new(RefCell::new(vec![]));
+            let mut search = AStarSearch::<
+                Maze2DHeuristicManhattanDistance,
+                _,
+                _,
+                _,
+                _,
+                _,
+                AStarRank<_, T>,
+            >::new(problem);
+            let hook_expanded = expanded.clone();
+            search.set_expand_hook(move |_s, g, _h| hook_expanded.borrow_mut().push(g));
+            assert_eq!(search.find_next_goal().unwrap().cost(), 8 * 100);
+            expanded.take()
+        }
+
+        // Diving towards the goal only expands the path.
+        let low_h = expanded_g::<LowH>(problem.clone());
+        assert_eq!(low_h, (0..=8).map(|i| i * 100).collect::<Vec<_>>());
+
+        // Staying close to the starts expands the whole plateau, layer by
+        // layer.
+        let low_g = expanded_g::<LowG>(problem);
+        assert_eq!(low_g.len(), 5 * 5);
+        assert!(low_g.is_sorted());
+        assert_ne!(low_h[2], low_g[2]);
+    }
 
     #[test]
     fn ranking_maze2d() {
-        use crate::problems::maze_2d::Maze2DCost;
-
         let c0: Maze2DCost = 0u32;
         let c1: Maze2DCost = 1u32;
         let c2: Maze2DCost = 2u32;
@@ -769,25 +2525,29 @@ mod tests {
         let g = c2;
         let h_low = c0;
         let h_high = c1;
-        assert!(AStarRank::new(g, h_low) < AStarRank::new(g, h_high));
-        assert!(AStarRank::new(g, h_high) == AStarRank::new(g, h_high));
-        assert!(AStarRank::new(g, h_high) > AStarRank::new(g, h_low));
+        assert!(AStarRank::<_>::new(g, h_low) < AStarRank::<_>::new(g, h_high));
+        assert!(AStarRank::<_>::new(g, h_high) == AStarRank::<_>::new(g, h_high));
+        assert!(AStarRank::<_>::new(g, h_high) > AStarRank::<_>::new(g, h_low));
 
         // Same f-value, needs tie-breaking on h
-        let low = AStarRank::new(c2, c0);
-        let high = AStarRank::new(c0, c2);
+        let low = AStarRank::<_>::new(c2, c0);
+        let high = AStarRank::<_>::new(c0, c2);
         assert!(low < high);
         assert!(low.f == high.f);
         assert!(low.h < high.h);
+
+        let low = AStarRank::<_, LowG>::new(c2, c0);
+        let high = AStarRank::<_, LowG>::new(c0, c2);
+        assert!(high < low);
     }
 
     #[test]
+    #[cfg(feature = "image")]
     fn solve_maze2d() {
         use std::path::PathBuf;
 
         use crate::problem::BaseProblem;
         use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
-        use crate::problems::maze_2d::Maze2DProblem;
 
         // Solve `two-paths.png` (`://data/problems/Maze2D/two-paths.png`)
         let problem =
@@ -799,8 +2559,205 @@ mod tests {
             AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
         let path = search.find_next_goal().unwrap();
 
-        assert!(starts.contains(&path.start.unwrap()));
-        assert!(goals.contains(&path.end.unwrap()));
-        assert_eq!(path.cost, 1805);
+        assert!(starts.contains(&path.start().unwrap()));
+        assert!(goals.contains(&path.end().unwrap()));
+        assert_eq!(path.cost(), 1805);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn path_steps() {
+        use std::path::PathBuf;
+
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+        use crate::space::Space;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let space = problem.space().clone();
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        let path = search.find_next_goal().unwrap();
+
+        let steps: Vec<_> = path.steps(&space).collect();
+        assert_eq!(steps.len(), path.len());
+        assert_eq!(steps[0].0, path.start().unwrap());
+        assert_eq!(
+            steps.iter().map(|(_, _, c)| c).sum::<Maze2DCost>(),
+            path.cost()
+        );
+
+        let (last_state, last_action, _) = *steps.last().unwrap();
+        assert_eq!(space.apply(&last_state, &last_action), path.end());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn path_states() {
+        use std::path::PathBuf;
+
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let space = problem.space().clone();
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        let path = search.find_next_goal().unwrap();
+
+        let states = path.into_state_vec(&space).unwrap();
+        assert_eq!(states.len(), path.actions().len() + 1);
+        assert_eq!(states.first().copied(), path.start());
+        assert_eq!(states.last().copied(), path.end());
+        assert_eq!(states, path.states(&space).collect::<Vec<_>>());
+
+        // The path walks out of a smaller space.
+        let blank = Maze2DSpace::new_empty_with_dimensions(2, 2);
+        assert_eq!(path.into_state_vec(&blank), None);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn g_of() {
+        use std::path::PathBuf;
+
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let start = problem.starts()[0];
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        assert!(search.is_generated(&start));
+        assert_eq!(search.g_of(&start), Some(0));
+
+        let path = search.find_next_goal().unwrap();
+        let end = path.end().unwrap();
+        assert!(search.is_generated(&end));
+        assert_eq!(search.g_of(&end), Some(path.cost()));
+        let end_index = search.nodes.node_map[&end];
+        assert_eq!(
+            search.nodes.search_tree[end_index].depth as usize,
+            path.len()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn reset() {
+        use std::path::PathBuf;
+
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::problem::ObjectiveProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let mut problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        let _ = search.find_next_goal().unwrap();
+
+        let open_capacity = search.nodes.open.capacity();
+        let map_capacity = search.nodes.node_map.capacity();
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..10 {
+            let p = problem.randomize(&mut rng, 1, 2).unwrap();
+            let mut fresh =
+                AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(p.clone());
+            search.reset(p);
+            // Allocations are kept around.
+            assert!(search.nodes.open.capacity() >= open_capacity);
+            assert!(search.nodes.node_map.capacity() >= map_capacity);
+
+            for _ in 0..2 {
+                assert_eq!(search.find_next_goal(), fresh.find_next_goal());
+            }
+            assert_eq!(
+                search.nodes.search_tree.len(),
+                fresh.nodes.search_tree.len()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn max_path_len() {
+        use std::path::PathBuf;
+
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        let len = search.find_next_goal().unwrap().len();
+
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        search.set_max_path_len(len);
+        assert_eq!(search.find_next_goal().unwrap().len(), len);
+
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        search.set_max_path_len(len - 1);
+        assert_eq!(search.solve_reporting(), SearchOutcome::TooLong);
+        assert_eq!(search.solve_reporting(), SearchOutcome::NoGoals);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn expansion_budget() {
+        use std::path::PathBuf;
+
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+
+        search.set_expansion_budget(10);
+        assert_eq!(search.find_next_goal(), None);
+        assert!(search.budget_exhausted());
+        assert_eq!(search.expanded_count(), 10);
+
+        search.set_expansion_budget(1_000_000);
+        let path = search.find_next_goal().unwrap();
+        assert!(!search.budget_exhausted());
+        assert_eq!(path.cost(), 1805);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn solve_maze2d_weighted() {
+        use std::path::PathBuf;
+
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        for weight in [0, 1, 2, 5] {
+            let mut search =
+                AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new_weighted(
+                    problem.clone(),
+                    weight,
+                );
+            let cost = search.find_next_goal().unwrap().cost();
+            assert!(cost >= 1805);
+            assert!(cost <= 1805 * weight.max(1));
+        }
     }
 }
@@ -0,0 +1,367 @@
+//! Implementation of Beam Search.
+//!
+//! Beam Search expands the search layer by layer, only keeping the best
+//! `width` nodes of each layer by their A* rank.
+//!
+//! NOTE: Beam Search is incomplete. Discarded nodes are never revisited, so it
+//! may fail to find a path even when one exists.
+
+use core::intrinsics::unlikely;
+use std::cmp::min;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
+
+use crate::algorithms::astar::AStarHeapNode;
+use crate::algorithms::astar::AStarRank;
+use crate::algorithms::astar::Rank;
+use crate::problem::ObjectiveProblem;
+use crate::search::DEFAULT_NODE_MAP_CAPACITY;
+use crate::search::DEFAULT_OPEN_CAPACITY;
+use crate::search::SearchTree;
+use crate::search::SearchTreeIndex;
+use crate::search::SearchTreeNode;
+use crate::space::Action;
+use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::Space;
+use crate::space::State;
+
+/// Beam search implementation for Objective Problems.
+///
+/// This initializes the search and offers an Iterator that goes around
+/// different solutions.
+#[derive(Debug)]
+pub struct BeamSearch<OH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// All the Search Nodes. Naturally forms a Search Forest as each node may
+    /// have a parent Node.
+    search_tree: SearchTree<St, A, C>,
+
+    /// The current layer of `(AStarRank, SearchTreeIndex)`.
+    ///
+    /// Unlike A* this is not kept as a heap, but the referenced nodes are kept
+    /// updated (`SearchTreeNode::heap_index`) to allow improving them while
+    /// the layer is being generated.
+    ///
+    /// ```pseudocode
+    /// for (i, hn) in self.open.enumerate():
+    ///   assert_eq(self.search_tree[hn.node_index].heap_index, i)
+    /// ```
+    open: Vec<AStarHeapNode<AStarRank<C>>>,
+
+    /// Finds existing Search Nodes from their `State` and tracks whether they
+    /// were closed (either expanded or discarded).
+    node_map: FxHashMap<St, SearchTreeIndex>,
+
+    /// A list of remaining goals.
+    /// Used to compute objective heuristics.
+    remaining_goals_list: Vec<St>,
+    /// A set of remaining goals. Used for goal checks.
+    remaining_goals_set: FxHashSet<St>,
+
+    /// The maximum number of nodes kept on each layer.
+    width: usize,
+
+    problem: OP,
+
+    _phantom_heuristic: PhantomData<OH>,
+    _phantom_space: PhantomData<Sp>,
+}
+
+impl<OH, OP, Sp, St, A, C> BeamSearch<OH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// Initializes the Search keeping at most `width` nodes per layer.
+    ///
+    /// Widths below 1 are raised to 1.
+    #[must_use]
+    pub fn new(op: OP, width: usize) -> Self {
+        let starts = op.starts().to_vec();
+        let goals = op.goals().to_vec();
+        let width = width.max(1);
+
+        let mut search = Self {
+            search_tree: SearchTree::<St, A, C>::new(),
+            open: Vec::with_capacity(min(width, DEFAULT_OPEN_CAPACITY)),
+            node_map: FxHashMap::with_capacity_and_hasher(
+                DEFAULT_NODE_MAP_CAPACITY,
+                Default::default(),
+            ),
+            remaining_goals_list: goals.clone(),
+            remaining_goals_set: FxHashSet::from_iter(goals.iter().cloned()),
+
+            width,
+
+            problem: op,
+
+            _phantom_heuristic: PhantomData,
+            _phantom_space: PhantomData,
+        };
+
+        for s in starts {
+            let h = search.h(&s);
+            search.push_new(&s, None, C::zero(), h);
+        }
+        search.prune();
+
+        search
+    }
+
+    /// Runs the search until the first goal is found.
+    #[must_use]
+    pub fn find_next_goal(&mut self) -> Option<Path<St, A, C>> {
+        if self.remaining_goals_set.is_empty() {
+            return None;
+        }
+
+        while !self.open.is_empty() {
+            // Goal-check the current layer before expanding it.
+            if let Some(heap_index) = self
+                .open
+                .iter()
+                .position(|hn| self.is_goal(self.search_tree[hn.node_index].state()))
+            {
+                let node_index = self._swap_remove(heap_index);
+                let state = *self.search_tree[node_index].state();
+                self.mark_closed(&state);
+                self.remove_goal(&state);
+                return Some(self.search_tree.path(self.problem.space(), node_index));
+            }
+
+            self.expand_layer();
+            self.prune();
+        }
+
+        None
+    }
+
+    /// Replaces the current layer with all its successors.
+    fn expand_layer(&mut self) {
+        let layer = std::mem::take(&mut self.open);
+
+        // Close the whole layer first so `heap_index` only refers to the new
+        // layer on open nodes.
+        for hn in &layer {
+            let state = *self.search_tree[hn.node_index].state();
+            self.mark_closed(&state);
+        }
+
+        for hn in &layer {
+            let node_index = hn.node_index;
+            let state = *self.search_tree[node_index].state();
+            let g: C = self.search_tree[node_index].g;
+            let depth = self.search_tree[node_index].depth;
+
+            for (s, a) in self.problem.space().neighbours(&state) {
+                let c: C = self.problem.space().cost(&state, &a);
+                let new_g = g + c;
+
+                match self.node_map.get(&s) {
+                    Some(neigh_index) => {
+                        if neigh_index.is_closed() {
+                            continue;
+                        }
+                        let neigh = &mut self.search_tree[*neigh_index];
+                        if new_g < neigh.g {
+                            neigh.reach((node_index, a), new_g, depth + 1);
+                            self.open[neigh.heap_index].rank.improve_g(new_g);
+                        }
+                    }
+                    None => {
+                        let h = self.h(&s);
+                        self.push_new(&s, Some((node_index, a)), new_g, h);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Discards the worst-ranked nodes exceeding the beam width.
+    fn prune(&mut self) {
+        if self.open.len() > self.width {
+            // Partial sort putting the best `width` nodes first.
+            self.open.select_nth_unstable(self.width - 1);
+
+            for hn in self.open.split_off(self.width) {
+                let state = *self.search_tree[hn.node_index].state();
+                self.mark_closed(&state);
+            }
+        }
+
+        // Restore intrusive indices.
+        for (heap_index, hn) in self.open.iter().enumerate() {
+            self.search_tree[hn.node_index].heap_index = heap_index;
+        }
+        self.verify_open();
+    }
+
+    /// Checks if a state is an undiscovered goal.
+    #[inline(always)]
+    #[must_use]
+    fn is_goal(&self, s: &St) -> bool {
+        unlikely(self.remaining_goals_set.contains(s))
+    }
+
+    /// Removes a state from the remaining goals.
+    #[inline(always)]
+    fn remove_goal(&mut self, goal: &St) {
+        self.remaining_goals_set.remove(goal);
+        self.remaining_goals_list.swap_remove(
+            self.remaining_goals_list
+                .iter()
+                .position(|&s| s == *goal)
+                .unwrap(),
+        );
+    }
+
+    #[inline(always)]
+    #[must_use]
+    fn h(&self, s: &St) -> C {
+        let mut h = C::max_value();
+        for g in &self.remaining_goals_list {
+            h = min(h, OH::h_in(self.problem.space(), s, g))
+        }
+        h
+    }
+
+    /// Marks a Search Node as Closed (expanded or discarded)
+    #[inline(always)]
+    fn mark_closed(&mut self, s: &St) {
+        match self.node_map.get_mut(s) {
+            Some(node_index) => {
+                if !node_index.is_closed() {
+                    node_index.set_closed();
+                }
+            }
+            None => {
+                unreachable!("Tried closing a state without a node");
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn push_new(&mut self, s: &St, parent: Option<(SearchTreeIndex, A)>, g: C, h: C) {
+        let heap_index = self.open.len(); // Future `heap_index`
+
+        let mut node = SearchTreeNode::<St, A, C>::new(heap_index, *s, parent, g);
+        if let Some((parent_index, _a)) = parent {
+            node.depth = self.search_tree[parent_index].depth + 1;
+        }
+        let node_index: SearchTreeIndex = self.search_tree.push(node);
+        self.node_map.insert(*s, node_index);
+
+        self.open.push(AStarHeapNode {
+            rank: AStarRank::<C>::new(g, h),
+            node_index,
+        });
+    }
+
+    /// Removes a node from the current layer, keeping intrusive indices.
+    #[inline(always)]
+    #[must_use]
+    fn _swap_remove(&mut self, heap_index: usize) -> SearchTreeIndex {
+        let hn = self.open.swap_remove(heap_index);
+        if let Some(moved) = self.open.get(heap_index) {
+            self.search_tree[moved.node_index].heap_index = heap_index;
+        }
+        self.verify_open();
+        hn.node_index
+    }
+
+    #[inline(always)]
+    #[cfg(not(feature = "verify"))]
+    fn verify_open(&self) {
+        // All good... (hopefully)
+    }
+    #[inline(always)]
+    #[cfg(feature = "verify")]
+    fn verify_open(&self) {
+        debug_assert!(self.open.len() <= self.width);
+        for (i, e) in self.open.iter().enumerate() {
+            debug_assert!(self.search_tree[e.node_index].heap_index == i);
+        }
+    }
+}
+
+impl<OH, OP, Sp, St, A, C> Iterator for BeamSearch<OH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    type Item = Path<St, A, C>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.find_next_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::problem::BaseProblem;
+    use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+    use crate::problems::maze_2d::Maze2DProblem;
+
+    #[test]
+    fn corridor() {
+        let problem = Maze2DProblem::try_from(indoc! {"
+            ..........
+            S........G
+            ..........
+        "})
+        .unwrap();
+        let space = problem.space().clone();
+        let mut search =
+            BeamSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem, 1);
+        let path = search.find_next_goal().unwrap();
+
+        assert!(space.valid_path(&path));
+        assert_eq!(path.cost(), 900);
+    }
+
+    #[test]
+    fn dead_end() {
+        // The goal lures narrow beams into the dead-end corridor.
+        let maze = indoc! {"
+            .......
+            .#####.
+            .S...#G
+            .#####.
+        "};
+
+        let problem = Maze2DProblem::try_from(maze).unwrap();
+        let mut search =
+            BeamSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem, 1);
+        assert_eq!(search.find_next_goal(), None);
+
+        let problem = Maze2DProblem::try_from(maze).unwrap();
+        let space = problem.space().clone();
+        let mut search =
+            BeamSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem, 8);
+        let path = search.find_next_goal().unwrap();
+        assert!(space.valid_path(&path));
+    }
+}
@@ -0,0 +1,345 @@
+//! Implementation of Bidirectional Dijkstra.
+//!
+//! Runs a forward search from the starts and a backward search from the goals,
+//! stitching both halves together once the frontiers meet.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::marker::PhantomData;
+
+use rustc_hash::FxHashMap;
+
+use crate::algorithms::dijkstra::DijkstraHeapNode;
+use crate::algorithms::dijkstra::DijkstraRank;
+use crate::problem::ObjectiveProblem;
+use crate::search::DEFAULT_NODE_MAP_CAPACITY;
+use crate::search::DEFAULT_OPEN_CAPACITY;
+use crate::search::SearchTree;
+use crate::search::SearchTreeIndex;
+use crate::search::SearchTreeNode;
+use crate::space::Action;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::ReversibleSpace;
+use crate::space::State;
+
+/// One of the directions of a Bidirectional search.
+///
+/// The open list is a plain `BinaryHeap` that keeps stale entries around when
+/// a node is reached through a better path. Stale entries are skipped when
+/// they reach the top.
+#[derive(Debug)]
+struct Frontier<St, A, C>
+where
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    search_tree: SearchTree<St, A, C>,
+    open: BinaryHeap<Reverse<DijkstraHeapNode<C>>>,
+    node_map: FxHashMap<St, SearchTreeIndex>,
+}
+
+impl<St, A, C> Frontier<St, A, C>
+where
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    fn new() -> Self {
+        Self {
+            search_tree: SearchTree::new(),
+            open: BinaryHeap::with_capacity(DEFAULT_OPEN_CAPACITY),
+            node_map: FxHashMap::with_capacity_and_hasher(
+                DEFAULT_NODE_MAP_CAPACITY,
+                Default::default(),
+            ),
+        }
+    }
+
+    /// Reaches a state, creating its node or improving its path.
+    fn reach(&mut self, s: &St, parent: Option<(SearchTreeIndex, A)>, g: C) {
+        let node_index = match self.node_map.get(s) {
+            Some(node_index) if node_index.is_closed() => return,
+            Some(node_index) => {
+                let node_index = *node_index;
+                if g >= self.search_tree[node_index].g {
+                    return;
+                }
+                let (parent_index, a) = parent.unwrap();
+                let depth = self.search_tree[parent_index].depth + 1;
+                self.search_tree[node_index].reach((parent_index, a), g, depth);
+                node_index
+            }
+            None => {
+                let mut node = SearchTreeNode::<St, A, C>::new(0, *s, parent, g);
+                if let Some((parent_index, _a)) = parent {
+                    node.depth = self.search_tree[parent_index].depth + 1;
+                }
+                let node_index = self.search_tree.push(node);
+                self.node_map.insert(*s, node_index);
+                node_index
+            }
+        };
+
+        self.open.push(Reverse(DijkstraHeapNode {
+            rank: DijkstraRank::new(g),
+            node_index,
+        }));
+    }
+
+    /// Discards stale entries and gets the best g-value in the open list.
+    #[must_use]
+    fn top_g(&mut self) -> Option<C> {
+        while let Some(Reverse(top)) = self.open.peek() {
+            let node = &self.search_tree[top.node_index];
+            let closed = self.node_map[node.state()].is_closed();
+            if closed || top.rank != DijkstraRank::new(node.g) {
+                self.open.pop();
+                continue;
+            }
+            return Some(node.g);
+        }
+        None
+    }
+
+    /// Pops the best open node and marks it as closed.
+    #[must_use]
+    fn pop(&mut self) -> Option<SearchTreeIndex> {
+        self.top_g()?;
+        let Reverse(top) = self.open.pop().unwrap();
+        let state = *self.search_tree[top.node_index].state();
+        self.node_map.get_mut(&state).unwrap().set_closed();
+        Some(top.node_index)
+    }
+
+    #[must_use]
+    fn get(&self, s: &St) -> Option<(SearchTreeIndex, C)> {
+        self.node_map
+            .get(s)
+            .map(|node_index| (*node_index, self.search_tree[*node_index].g))
+    }
+}
+
+/// Bidirectional Dijkstra search implementation for Objective Problems.
+///
+/// Expansions alternate between searching forward from the starts and
+/// backward from the goals. The best meeting point seen so far (`mu`) is only
+/// known to be optimal once the best open nodes on both sides can't improve
+/// it, that is, when `top_f + top_b >= mu`.
+///
+/// NOTE: The path found leads to the closest goal, so there's no Iterator
+/// over different solutions.
+#[derive(Debug)]
+pub struct BiDijkstraSearch<OP, Sp, St, A, C>
+where
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: ReversibleSpace<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    forward: Frontier<St, A, C>,
+    backward: Frontier<St, A, C>,
+
+    /// The cost of the best path through a meeting point, and the nodes of
+    /// the meeting point on each Search Tree.
+    best: Option<(C, SearchTreeIndex, SearchTreeIndex)>,
+    /// Whether the next expansion goes forward.
+    forward_turn: bool,
+
+    problem: OP,
+
+    _phantom_space: PhantomData<Sp>,
+}
+
+impl<OP, Sp, St, A, C> BiDijkstraSearch<OP, Sp, St, A, C>
+where
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: ReversibleSpace<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// Initializes the Search
+    #[must_use]
+    pub fn new(op: OP) -> Self {
+        let mut search = Self {
+            forward: Frontier::new(),
+            backward: Frontier::new(),
+            best: None,
+            forward_turn: true,
+
+            problem: op,
+
+            _phantom_space: PhantomData,
+        };
+
+        for s in search.problem.starts().to_vec() {
+            search.forward.reach(&s, None, C::zero());
+        }
+        for s in search.problem.goals().to_vec() {
+            search.backward.reach(&s, None, C::zero());
+        }
+
+        search
+    }
+
+    /// Runs the search until both frontiers prove the best meeting point.
+    #[must_use]
+    pub fn find_path(&mut self) -> Option<Path<St, A, C>> {
+        while let (Some(top_f), Some(top_b)) = (self.forward.top_g(), self.backward.top_g()) {
+            if let Some((mu, _, _)) = self.best
+                && top_f.saturating_add(&top_b) >= mu
+            {
+                break;
+            }
+
+            if self.forward_turn {
+                self.expand_forward();
+            } else {
+                self.expand_backward();
+            }
+            self.forward_turn = !self.forward_turn;
+        }
+
+        let (_mu, forward_index, backward_index) = self.best.take()?;
+        Some(self.stitch(forward_index, backward_index))
+    }
+
+    fn expand_forward(&mut self) {
+        let Some(node_index) = self.forward.pop() else {
+            return;
+        };
+        let state = *self.forward.search_tree[node_index].state();
+        let g = self.forward.search_tree[node_index].g;
+        self.meet(&state);
+
+        for (s, a) in self.problem.space().neighbours(&state) {
+            let c: C = self.problem.space().cost(&state, &a);
+            self.forward.reach(&s, Some((node_index, a)), g + c);
+            self.meet(&s);
+        }
+    }
+
+    fn expand_backward(&mut self) {
+        let Some(node_index) = self.backward.pop() else {
+            return;
+        };
+        let state = *self.backward.search_tree[node_index].state();
+        let g = self.backward.search_tree[node_index].g;
+        self.meet(&state);
+
+        for (s, a) in self.problem.space().reverse_neighbours(&state) {
+            let c: C = self.problem.space().cost(&s, &a);
+            self.backward.reach(&s, Some((node_index, a)), g + c);
+            self.meet(&s);
+        }
+    }
+
+    /// Updates the best meeting point if both searches reached a state.
+    fn meet(&mut self, s: &St) {
+        let (Some((forward_index, g_f)), Some((backward_index, g_b))) =
+            (self.forward.get(s), self.backward.get(s))
+        else {
+            return;
+        };
+
+        let mu = g_f.saturating_add(&g_b);
+        if self.best.is_none_or(|(best_mu, _, _)| mu < best_mu) {
+            self.best = Some((mu, forward_index, backward_index));
+        }
+    }
+
+    /// Joins the forward path to the meeting point with the path from the
+    /// meeting point to a goal.
+    #[must_use]
+    fn stitch(
+        &mut self,
+        forward_index: SearchTreeIndex,
+        backward_index: SearchTreeIndex,
+    ) -> Path<St, A, C> {
+        let space = self.problem.space();
+        let mut path = self.forward.search_tree.path(space, forward_index);
+
+        let tree = &self.backward.search_tree;
+        let mut node_index = backward_index;
+        while let Some((parent_index, a)) = tree[node_index].parent() {
+            let from = tree[node_index].state();
+            let c: C = space.cost(from, &a);
+            path.append((*tree[parent_index].state(), a), c);
+            node_index = parent_index;
+        }
+
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn solve_maze2d() {
+        use std::path::PathBuf;
+
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DProblem;
+        use crate::space::Space;
+
+        // Solve `two-paths.png` (`://data/problems/Maze2D/two-paths.png`)
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let starts = problem.starts().to_vec();
+        let goals = problem.goals().to_vec();
+        let space = problem.space().clone();
+        let mut search = BiDijkstraSearch::new(problem);
+        let path = search.find_path().unwrap();
+
+        assert!(starts.contains(&path.start().unwrap()));
+        assert!(goals.contains(&path.end().unwrap()));
+        assert!(space.valid_path(&path));
+        assert_eq!(path.cost(), 1805);
+    }
+
+    #[test]
+    fn matches_dijkstra() {
+        use indoc::indoc;
+
+        use crate::algorithms::dijkstra::DijkstraSearch;
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DProblem;
+        use crate::space::Space;
+
+        for maze in [
+            indoc! {"
+                S..#....
+                .#.#.##.
+                .#...#..
+                .####.#.
+                ......#G
+            "},
+            indoc! {"
+                S#G
+            "},
+            indoc! {"
+                .S.
+                .G.
+            "},
+        ] {
+            let problem = Maze2DProblem::try_from(maze).unwrap();
+            let space = problem.space().clone();
+
+            let expected = DijkstraSearch::new(problem.clone()).find_next_goal();
+            let path = BiDijkstraSearch::new(problem).find_path();
+
+            assert_eq!(path.is_some(), expected.is_some());
+            if let (Some(path), Some(expected)) = (path, expected) {
+                assert!(space.valid_path(&path));
+                assert_eq!(path.cost(), expected.cost());
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Implementation of Dijkstra's algorithm over a bucket queue (Dial's
+//! algorithm).
+//!
+//! Integer costs can index buckets directly, which avoids paying the heap's
+//! O(log n) on every push and pop.
+
+use core::intrinsics::unlikely;
+use std::marker::PhantomData;
+
+use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
+
+use crate::data_structures::bucket_queue::BucketQueue;
+use crate::problem::ObjectiveProblem;
+use crate::search::DEFAULT_NODE_MAP_CAPACITY;
+use crate::search::SearchTree;
+use crate::search::SearchTreeIndex;
+use crate::search::SearchTreeNode;
+use crate::space::Action;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::Space;
+use crate::space::State;
+
+/// Dijkstra search implementation for Objective Problems with integer costs.
+///
+/// The open list is a `BucketQueue` keyed by g-value. Buckets can't re-rank
+/// their items, so nodes reached through a better path are queued again and
+/// the stale entries are skipped once their node is closed.
+///
+/// NOTE: Costs must be non-negative, as the bucket queue relies on expansions
+/// never reaching nodes cheaper than the expanded one.
+#[derive(Debug)]
+pub struct BucketDijkstraSearch<OP, Sp, St, A, C>
+where
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost + Into<u32>,
+{
+    /// All the Search Nodes. Naturally forms a Search Forest as each node may
+    /// have a parent Node.
+    search_tree: SearchTree<St, A, C>,
+
+    /// The open nodes by g-value, including stale entries of nodes that were
+    /// reached again through a better path.
+    open: BucketQueue<SearchTreeIndex>,
+
+    /// Finds existing Search Nodes from their `State` and tracks whether they
+    /// were closed.
+    node_map: FxHashMap<St, SearchTreeIndex>,
+
+    /// Set of remaining goal states.
+    ///
+    /// Used to cut the search earlier.
+    remaining_goals_set: FxHashSet<St>,
+
+    /// The number of expanded nodes.
+    expanded: usize,
+    /// The number of generated nodes, counting again the ones reached
+    /// through a better path.
+    generated: usize,
+
+    problem: OP,
+
+    _phantom_space: PhantomData<Sp>,
+    _phantom_action: PhantomData<A>,
+}
+
+impl<OP, Sp, St, A, C> BucketDijkstraSearch<OP, Sp, St, A, C>
+where
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost + Into<u32>,
+{
+    /// Initializes the Search
+    #[must_use]
+    pub fn new(op: OP) -> Self {
+        let starts = op.starts().to_vec();
+        let goals = op.goals().to_vec();
+
+        let mut search = Self {
+            search_tree: SearchTree::<St, A, C>::new(),
+            open: BucketQueue::new(),
+            node_map: FxHashMap::with_capacity_and_hasher(
+                DEFAULT_NODE_MAP_CAPACITY,
+                Default::default(),
+            ),
+            remaining_goals_set: FxHashSet::from_iter(goals.iter().cloned()),
+
+            expanded: 0,
+            generated: 0,
+
+            problem: op,
+
+            _phantom_space: PhantomData,
+            _phantom_action: PhantomData,
+        };
+
+        for s in starts {
+            search.reach(&s, None, C::zero());
+        }
+
+        search
+    }
+
+    /// Runs the search until the first goal is found.
+    #[must_use]
+    pub fn find_next_goal(&mut self) -> Option<Path<St, A, C>> {
+        if self.remaining_goals_set.is_empty() {
+            return None;
+        }
+
+        while let Some(node_index) = self.pop() {
+            let state = *self.search_tree[node_index].state();
+            let g: C = self.search_tree[node_index].g;
+            self.expanded += 1;
+
+            for (s, a) in self.problem.space().neighbours(&state) {
+                let c: C = self.problem.space().cost(&state, &a);
+                self.reach(&s, Some((node_index, a)), g + c);
+            }
+
+            if unlikely(self.remaining_goals_set.remove(&state)) {
+                return Some(self.search_tree.path(self.problem.space(), node_index));
+            }
+        }
+
+        None
+    }
+
+    /// The number of nodes expanded so far.
+    #[inline(always)]
+    #[must_use]
+    pub fn expanded_count(&self) -> usize {
+        self.expanded
+    }
+
+    /// The number of nodes generated so far.
+    ///
+    /// Nodes reached again through a better path count again.
+    #[inline(always)]
+    #[must_use]
+    pub fn generated_count(&self) -> usize {
+        self.generated
+    }
+
+    /// Reaches a state, creating its node or improving its path.
+    fn reach(&mut self, s: &St, parent: Option<(SearchTreeIndex, A)>, g: C) {
+        let node_index = match self.node_map.get(s) {
+            Some(node_index) if node_index.is_closed() => return,
+            Some(node_index) => {
+                let node_index = *node_index;
+                if g >= self.search_tree[node_index].g {
+                    return;
+                }
+                let (parent_index, a) = parent.unwrap();
+                let depth = self.search_tree[parent_index].depth + 1;
+                self.search_tree[node_index].reach((parent_index, a), g, depth);
+                node_index
+            }
+            None => {
+                let mut node = SearchTreeNode::<St, A, C>::new(0, *s, parent, g);
+                if let Some((parent_index, _a)) = parent {
+                    node.depth = self.search_tree[parent_index].depth + 1;
+                }
+                let node_index = self.search_tree.push(node);
+                self.node_map.insert(*s, node_index);
+                node_index
+            }
+        };
+
+        self.generated += 1;
+        self.open.push(g.into(), node_index);
+    }
+
+    /// Pops the best open node, skipping stale entries, and marks it as
+    /// closed.
+    #[must_use]
+    fn pop(&mut self) -> Option<SearchTreeIndex> {
+        while let Some((_g, node_index)) = self.open.pop() {
+            let state = *self.search_tree[node_index].state();
+            let map_index = self.node_map.get_mut(&state).unwrap();
+            if map_index.is_closed() {
+                continue;
+            }
+            map_index.set_closed();
+            return Some(node_index);
+        }
+        None
+    }
+}
+
+impl<OP, Sp, St, A, C> Iterator for BucketDijkstraSearch<OP, Sp, St, A, C>
+where
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost + Into<u32>,
+{
+    type Item = Path<St, A, C>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.find_next_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::dijkstra::DijkstraSearch;
+    use crate::problem::BaseProblem;
+    use crate::problems::maze_2d::Maze2DProblem;
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn matches_dijkstra() {
+        use std::path::PathBuf;
+
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::problem::ObjectiveProblem;
+
+        for file in ["blank_10.png", "mini-chile.png", "two-paths.png"] {
+            let path = PathBuf::from("data/problems/Maze2D").join(file);
+            let mut base_problem = Maze2DProblem::try_from(path.as_path()).unwrap();
+            let space = base_problem.space().clone();
+
+            for seed in 0..3 {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let problem = base_problem.randomize(&mut rng, 3, 3).unwrap();
+
+                let expected: Vec<_> = DijkstraSearch::new(problem.clone())
+                    .map(|p| p.cost())
+                    .collect();
+                let mut costs = vec![];
+                for path in BucketDijkstraSearch::new(problem) {
+                    assert!(space.valid_path(&path));
+                    costs.push(path.cost());
+                }
+                assert_eq!(costs, expected, "Costs differ on {file}:{seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let problem = Maze2DProblem::try_from("S#G\n").unwrap();
+        let mut search = BucketDijkstraSearch::new(problem);
+        assert_eq!(search.find_next_goal(), None);
+        assert_eq!(search.expanded_count(), 1);
+    }
+}
@@ -0,0 +1,316 @@
+//! Implementation of the A* path-finding algorithm for Condition Problems.
+//!
+//! Goals are states satisfying a condition instead of a list of objectives,
+//! so the heuristic only depends on the state and never changes.
+
+use core::intrinsics::unlikely;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use crate::algorithms::astar::AStarNodes;
+use crate::algorithms::astar::AStarRank;
+use crate::algorithms::astar::NodeHeuristic;
+use crate::problem::ConditionProblem;
+use crate::search::DEFAULT_NODE_MAP_CAPACITY;
+use crate::search::DEFAULT_OPEN_CAPACITY;
+use crate::search::SearchTreeIndex;
+use crate::space::Action;
+use crate::space::ConditionHeuristic;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::Space;
+use crate::space::State;
+
+/// Ranks the nodes of a `ConditionAStarSearch` by a `ConditionHeuristic`.
+#[derive(Debug)]
+struct ConditionNodeHeuristic<CH, Sp, A> {
+    _phantom_heuristic: PhantomData<CH>,
+    _phantom_space: PhantomData<Sp>,
+    _phantom_action: PhantomData<A>,
+}
+
+impl<CH, Sp, St, A, C> NodeHeuristic<Sp, St, C> for ConditionNodeHeuristic<CH, Sp, A>
+where
+    CH: ConditionHeuristic<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn h(&mut self, _space: &Sp, s: &St, _node_index: SearchTreeIndex) -> C {
+        CH::h(s)
+    }
+
+    #[cfg(feature = "verify")]
+    fn raw_h(&self, _space: &Sp, s: &St) -> C {
+        CH::h(s)
+    }
+}
+
+/// A* search implementation for Condition Problems.
+///
+/// This initializes the search and offers an Iterator that goes around
+/// different solutions, yielding goal states in the order they are reached.
+///
+/// Goals found are closed like any other state, so each is yielded once and
+/// with an optimal path when the heuristic is consistent.
+#[derive(Debug)]
+pub struct ConditionAStarSearch<CH, CP, Sp, St, A, C>
+where
+    CH: ConditionHeuristic<Sp, St, A, C>,
+    CP: ConditionProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// The Search Tree, open list and node map.
+    nodes: AStarNodes<St, A, C, AStarRank<C>>,
+
+    heuristic: ConditionNodeHeuristic<CH, Sp, A>,
+
+    problem: CP,
+}
+
+impl<CH, CP, Sp, St, A, C> ConditionAStarSearch<CH, CP, Sp, St, A, C>
+where
+    CH: ConditionHeuristic<Sp, St, A, C>,
+    CP: ConditionProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// Initializes the Search
+    #[must_use]
+    pub fn new(cp: CP) -> Self {
+        Self::with_capacity(cp, DEFAULT_OPEN_CAPACITY, DEFAULT_NODE_MAP_CAPACITY)
+    }
+
+    /// Initializes the Search pre-allocating its open list and node map.
+    #[must_use]
+    pub fn with_capacity(cp: CP, open_capacity: usize, map_capacity: usize) -> Self {
+        let mut search = Self {
+            nodes: AStarNodes::with_capacity(open_capacity, map_capacity),
+
+            heuristic: ConditionNodeHeuristic {
+                _phantom_heuristic: PhantomData,
+                _phantom_space: PhantomData,
+                _phantom_action: PhantomData,
+            },
+
+            problem: cp,
+        };
+
+        for s in search.problem.starts() {
+            let g: C = C::zero();
+            let parent: Option<(SearchTreeIndex, A)> = None;
+            search
+                .nodes
+                .push_new(search.problem.space(), s, parent, g, &mut search.heuristic);
+        }
+
+        search
+    }
+
+    /// Runs the search until the next goal is found.
+    #[must_use]
+    pub fn find_next_goal(&mut self) -> Option<Path<St, A, C>> {
+        #[cfg(feature = "coz_profile")]
+        coz::scope!("FindNextGoal");
+
+        self.nodes.start_call();
+        while let Some(node_index) = self.nodes.next_node() {
+            let state = self
+                .nodes
+                .expand(self.problem.space(), node_index, &mut self.heuristic);
+
+            // NOTE: The goal is closed already, so the next call won't find it
+            // again.
+            if unlikely(self.problem.is_goal(&state)) {
+                #[cfg(feature = "coz_profile")]
+                coz::progress!("GoalFound");
+                return Some(self.nodes.path(self.problem.space(), node_index));
+            }
+        }
+
+        None
+    }
+
+    /// Caps the number of expansions of each `find_next_goal` call.
+    ///
+    /// Calls running out of budget return `None`, and the next call resumes
+    /// the search.
+    pub fn set_expansion_budget(&mut self, max_expansions: usize) {
+        self.nodes.max_expansions = Some(max_expansions);
+    }
+
+    /// Whether the last `find_next_goal` call ran out of expansions.
+    #[inline(always)]
+    #[must_use]
+    pub fn budget_exhausted(&self) -> bool {
+        self.nodes.budget_exhausted
+    }
+
+    /// Makes `find_next_goal` calls give up after a deadline.
+    ///
+    /// Calls running out of time return `None`, and the next call resumes the
+    /// search.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.nodes.deadline = Some(deadline);
+    }
+
+    /// Whether the last `find_next_goal` call ran out of time.
+    #[inline(always)]
+    #[must_use]
+    pub fn timed_out(&self) -> bool {
+        self.nodes.timed_out
+    }
+
+    /// The number of nodes expanded so far.
+    #[inline(always)]
+    #[must_use]
+    pub fn expanded_count(&self) -> usize {
+        self.nodes.expanded
+    }
+
+    /// The number of nodes generated so far.
+    ///
+    /// Nodes reached again through a better path count again.
+    #[inline(always)]
+    #[must_use]
+    pub fn generated_count(&self) -> usize {
+        self.nodes.generated
+    }
+
+    /// Reconstructs the optimal path to an expanded state.
+    ///
+    /// Returns `None` if the state wasn't reached or is still open.
+    #[must_use]
+    pub fn path_to(&self, s: &St) -> Option<Path<St, A, C>> {
+        self.nodes.path_to(self.problem.space(), s)
+    }
+
+    pub fn write_memory_stats<W: std::io::Write>(&self, out: W) -> std::io::Result<()> {
+        self.nodes.write_memory_stats(out, "ConditionAStarSearch")
+    }
+    pub fn print_memory_stats(&self) {
+        self.write_memory_stats(std::io::stdout().lock()).unwrap()
+    }
+}
+
+impl<CH, CP, Sp, St, A, C> Iterator for ConditionAStarSearch<CH, CP, Sp, St, A, C>
+where
+    CH: ConditionHeuristic<Sp, St, A, C>,
+    CP: ConditionProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    type Item = Path<St, A, C>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.find_next_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::problem::BaseProblem;
+    use crate::problems::maze_2d::Maze2DCost;
+    use crate::problems::maze_2d::Maze2DEscapeProblem;
+    use crate::problems::maze_2d::Maze2DHeuristicRegionDistance;
+    use crate::problems::maze_2d::Maze2DProblem;
+    use crate::problems::maze_2d::Maze2DRegionProblem;
+    use crate::problems::maze_2d::Maze2DState;
+    use crate::space::NoHeuristic;
+
+    #[test]
+    fn escape_maze2d() {
+        let problem = Maze2DEscapeProblem(
+            Maze2DProblem::try_from(indoc! {"
+                #######
+                #.....#
+                #.#.#.#
+                #.#S#.#
+                #.###..
+                #######
+            "})
+            .unwrap(),
+        );
+        let space = problem.space().clone();
+        let mut search = ConditionAStarSearch::<NoHeuristic, _, _, _, _, _>::new(problem);
+
+        // Out through the only gap on the border.
+        let path = search.find_next_goal().unwrap();
+        assert!(space.valid_path(&path));
+        assert_eq!(path.end(), Maze2DState::new_from_usize(6, 4));
+        assert_eq!(path.cost(), 2 * 100 + 3 * 141);
+
+        assert_eq!(search.find_next_goal(), None);
+    }
+
+    #[test]
+    fn reach_region_maze2d() {
+        let maze = indoc! {"
+            S.........
+            .#######..
+            .#........
+            .#........
+            .#........
+            ..........
+        "};
+        type ToRegion = Maze2DHeuristicRegionDistance<6, 3, 8, 4>;
+        let problem = || {
+            let problem = Maze2DProblem::try_from(maze).unwrap();
+            Maze2DRegionProblem::new(
+                problem.space().clone(),
+                problem.starts().to_vec(),
+                ToRegion::region(),
+            )
+        };
+        let space = problem().space().clone();
+        let mut informed = ConditionAStarSearch::<ToRegion, _, _, _, _, _>::new(problem());
+        let mut blind = ConditionAStarSearch::<NoHeuristic, _, _, _, _, _>::new(problem());
+
+        let path = informed.find_next_goal().unwrap();
+        let expected = blind.find_next_goal().unwrap();
+        assert!(space.valid_path(&path));
+        assert!(problem().is_goal(&path.end().unwrap()));
+        assert_eq!(path.cost(), expected.cost());
+        assert!(
+            informed.expanded_count() < blind.expanded_count(),
+            "{} >= {}",
+            informed.expanded_count(),
+            blind.expanded_count()
+        );
+    }
+
+    #[test]
+    fn escape_empty_maze2d() {
+        let problem = Maze2DEscapeProblem(
+            Maze2DProblem::try_from(indoc! {"
+                .....
+                .....
+                ..S..
+                .....
+                .....
+            "})
+            .unwrap(),
+        );
+        let search = ConditionAStarSearch::<NoHeuristic, _, _, _, _, _>::new(problem);
+
+        // Every border cell is a goal, found in order of cost.
+        let costs: Vec<Maze2DCost> = search.map(|p| p.cost()).collect();
+        assert_eq!(costs.len(), 16);
+        assert!(costs.is_sorted());
+        assert_eq!(costs[0], 200);
+        assert_eq!(costs[15], 282);
+    }
+}
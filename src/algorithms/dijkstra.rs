@@ -1,20 +1,26 @@
 //! Implementation of Dijkstra's path-finding algorithm.
 
 use core::intrinsics::unlikely;
-use std::cmp::min;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::time::Instant;
 
 use rustc_hash::FxHashMap;
 use rustc_hash::FxHashSet;
 
-use crate::derank::derank;
+use crate::data_structures::intrusive_heap::HeapIndexTracker;
+use crate::data_structures::intrusive_heap::IntrusiveHeap;
+use crate::problem::FloodProblem;
 use crate::problem::ObjectiveProblem;
+use crate::search::DEADLINE_CHECK_INTERVAL;
+use crate::search::DEFAULT_NODE_MAP_CAPACITY;
+use crate::search::DEFAULT_OPEN_CAPACITY;
+use crate::search::SearchOutcome;
 use crate::search::SearchTree;
 use crate::search::SearchTreeIndex;
 use crate::search::SearchTreeNode;
 use crate::space::Action;
-use crate::space::Cost;
+use crate::space::OrdCost;
 use crate::space::Path;
 use crate::space::Space;
 use crate::space::State;
@@ -25,19 +31,19 @@ use crate::space::State;
 ///
 // ```
 // use search::algorithms::dijkstra::DijkstraRank;
-// use search::space::Cost;
+// use search::space::OrdCost;
 //
 // let l0 = LittleCost::new(0);
 // let l1 = LittleCost::new(1);
 // assert!(DijkstraRank::new(l0) < DijkstraRank::new(l1));
 // ```
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct DijkstraRank<C: Cost> {
+pub struct DijkstraRank<C: OrdCost> {
     g: C,
 }
 impl<C> DijkstraRank<C>
 where
-    C: Cost,
+    C: OrdCost,
 {
     pub fn new(g: C) -> Self {
         Self { g }
@@ -49,23 +55,6 @@ where
     }
 }
 
-const HEAP_ARITY: usize = 8usize;
-#[inline(always)]
-#[must_use]
-fn up(i: usize) -> usize {
-    crate::heap_primitives::index_parent::<HEAP_ARITY>(i)
-}
-#[inline(always)]
-#[must_use]
-fn down_left(i: usize) -> usize {
-    crate::heap_primitives::index_first_children::<HEAP_ARITY>(i)
-}
-#[inline(always)]
-#[must_use]
-fn down_right(i: usize) -> usize {
-    crate::heap_primitives::index_last_children::<HEAP_ARITY>(i)
-}
-
 /// A heap node for Dijkstra
 ///
 /// Heap nodes carry just ranking information and a reference/index to the
@@ -76,7 +65,7 @@ fn down_right(i: usize) -> usize {
 #[cfg_attr(feature = "inspect", derive(Clone))]
 pub struct DijkstraHeapNode<C>
 where
-    C: Cost,
+    C: OrdCost,
 {
     /// The rank of this node that defines how good it is.
     pub rank: DijkstraRank<C>,
@@ -85,7 +74,7 @@ where
 }
 
 /// `PartialEq` is forwarded to `self.rank`
-impl<C: Cost> PartialEq for DijkstraHeapNode<C> {
+impl<C: OrdCost> PartialEq for DijkstraHeapNode<C> {
     #[inline(always)]
     fn eq(&self, other: &Self) -> bool {
         self.rank.eq(&other.rank)
@@ -93,27 +82,43 @@ impl<C: Cost> PartialEq for DijkstraHeapNode<C> {
 }
 /// `Eq` just says our `PartialEq` is also reflexive (`∀a. a==a`).
 /// `https://doc.rust-lang.org/std/cmp/trait.Eq.html`
-impl<C: Cost> Eq for DijkstraHeapNode<C> {}
+impl<C: OrdCost> Eq for DijkstraHeapNode<C> {}
 
 /// `PartialOrd` is forwarded to `Ord::cmp`
-impl<C: Cost> PartialOrd for DijkstraHeapNode<C> {
+impl<C: OrdCost> PartialOrd for DijkstraHeapNode<C> {
     #[inline(always)]
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.rank.cmp(&other.rank))
     }
 }
 /// `Ord` is forwarded to `self.rank`
-impl<C: Cost> Ord for DijkstraHeapNode<C> {
+impl<C: OrdCost> Ord for DijkstraHeapNode<C> {
     #[inline(always)]
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.rank.cmp(&other.rank)
     }
 }
 
+/// Keeps `SearchTreeNode::heap_index` in sync with the open list.
+impl<St, A, C> HeapIndexTracker<DijkstraHeapNode<C>> for SearchTree<St, A, C>
+where
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn set_heap_index(&mut self, node: &DijkstraHeapNode<C>, heap_index: usize) {
+        self[node.node_index].heap_index = heap_index;
+    }
+}
+
 /// Dijkstra search implementation for Objective Problems.
 ///
 /// This initializes the search and offers an Iterator that goes around
 /// different solutions.
+///
+/// NOTE: Only `OrdCost` is required, but closed nodes are never re-opened, so
+/// paths are only guaranteed to be optimal for non-negative costs (`Cost`).
 #[derive(Debug)]
 pub struct DijkstraSearch<OP, Sp, St, A, C>
 where
@@ -121,7 +126,7 @@ where
     Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     /// All the Search Nodes. Naturally forms a Search Forest as each node may
     /// have a parent Node.
@@ -139,7 +144,7 @@ where
     /// for (i, hn) in self.open.enumerate():
     ///   assert_eq(self.search_tree[hn.node_index].heap_index, i)
     /// ```
-    open: Vec<DijkstraHeapNode<C>>,
+    open: IntrusiveHeap<DijkstraHeapNode<C>>,
 
     /// Amalgamation of,
     /// - The `HashMap<St, &mut SearchTreeNode>`, but using `SearchTreeIndex`
@@ -155,6 +160,21 @@ where
     /// Used to cut the search earlier.
     remaining_goals_set: FxHashSet<St>,
 
+    /// The number of expanded nodes.
+    expanded: usize,
+    /// The number of generated nodes, counting again the ones reached
+    /// through a better path.
+    generated: usize,
+
+    /// When `find_next_goal` calls should give up.
+    deadline: Option<Instant>,
+    /// Whether the last `find_next_goal` call stopped due to `deadline`.
+    timed_out: bool,
+
+    /// The neighbours of the node being expanded, kept to re-use their
+    /// allocation across expansions.
+    neighbours: Vec<(St, A)>,
+
     problem: OP,
 
     _phantom_space: PhantomData<Sp>,
@@ -167,20 +187,43 @@ where
     Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
-    /// Initializes the Search
+    /// Initializes the Search with small default capacities.
+    ///
+    /// Searches expected to explore large spaces can ask for more with
+    /// `with_capacity`.
     #[must_use]
     pub fn new(op: OP) -> Self {
+        Self::with_capacity(op, DEFAULT_OPEN_CAPACITY, DEFAULT_NODE_MAP_CAPACITY)
+    }
+
+    /// Initializes the Search pre-allocating its open list, node map and
+    /// Search Tree.
+    ///
+    /// The node map and Search Tree hold every generated state, so on problems
+    /// exploring most of the space `node_capacity_hint` is a good
+    /// `map_capacity`. The open list only holds the frontier, which tends to be
+    /// much smaller.
+    #[must_use]
+    pub fn with_capacity(op: OP, open_capacity: usize, map_capacity: usize) -> Self {
         let starts = op.starts().to_vec();
         let goals = op.goals().to_vec();
 
         let mut search = Self {
-            search_tree: SearchTree::<St, A, C>::new(),
-            open: Vec::with_capacity(2048),
-            node_map: FxHashMap::default(),
+            search_tree: SearchTree::<St, A, C>::with_capacity(map_capacity),
+            open: IntrusiveHeap::with_capacity(open_capacity),
+            node_map: FxHashMap::with_capacity_and_hasher(map_capacity, Default::default()),
             remaining_goals_set: FxHashSet::from_iter(goals.iter().cloned()),
 
+            expanded: 0,
+            generated: 0,
+
+            deadline: None,
+            timed_out: false,
+
+            neighbours: Vec::new(),
+
             problem: op,
 
             _phantom_space: PhantomData,
@@ -202,6 +245,7 @@ where
         #[cfg(feature = "coz_profile")]
         coz::scope!("FindNextGoal");
 
+        self.timed_out = false;
         if self.remaining_goals_set.is_empty() {
             return None;
         }
@@ -209,62 +253,23 @@ where
         // Check remaining un-explored nodes
         // NOTE: We could avoid a `Heap::pop()` by peeking and doing the goal-check.
         // TODO: See if `pop_node()` would be the same or faster that `pop()`
-        while let Some(node_index) = self.pop() {
+        let mut expansions = 0usize;
+        while !self.open.is_empty() {
+            if unlikely(self.past_deadline(expansions)) {
+                // Leave the search untouched so the next call resumes it.
+                self.timed_out = true;
+                return None;
+            }
+            expansions += 1;
+
+            let node_index = self.pop().unwrap();
+
             #[cfg(feature = "coz_profile")]
             coz::scope!("NodeExpansion");
 
-            let state = *self.search_tree[node_index].state();
-            let g: C = self.search_tree[node_index].g;
-            debug_assert!(!self.is_closed(&state));
-
             // NOTE: We can do a goal-check and return here if we only need one
             // path or can yield a result
-
-            // Mark as closed
-            self.mark_closed(&state);
-
-            // Expand state
-            for (s, a) in self.problem.space().neighbours(&state) {
-                #[cfg(feature = "coz_profile")]
-                coz::scope!("ReachNode");
-
-                // Have we seen this State?
-                match self.node_map.get(&s) {
-                    Some(neigh_index) => {
-                        #[cfg(feature = "coz_profile")]
-                        coz::scope!("ReachExistingNode");
-                        if neigh_index.is_closed() {
-                            // Yes, and we expanded the State already.
-                            // NOTE: Could be a goal we had already found through a
-                            // sub-optimal path. Currently we only search for
-                            // an optimal path to a new goal.
-                            continue;
-                        }
-
-                        // Yes, but it's still unexplored. Update the existing
-                        // Node if needed.
-                        let neigh = &mut self.search_tree[*neigh_index];
-                        let neigh_heap_index = neigh.heap_index;
-                        let c: C = self.problem.space().cost(&s, &a);
-                        let new_g = g + c;
-                        if new_g < neigh.g {
-                            // Found better path to existing node
-                            neigh.reach((node_index, a), new_g);
-                            self.open[neigh_heap_index].rank.improve_g(new_g);
-                            self._unsafe_sift_up(neigh_heap_index);
-                        }
-                    }
-                    None => {
-                        #[cfg(feature = "coz_profile")]
-                        coz::scope!("ReachNewNode");
-                        // No, let's create a new Node for it.
-                        let c: C = self.problem.space().cost(&s, &a);
-                        let new_g = g + c;
-
-                        self.push_new(&s, Some((node_index, a)), new_g);
-                    }
-                }
-            }
+            let state = self.expand(node_index);
 
             // NOTE: This should be done before expanding if we could yield or
             // only want the path to the first goal.
@@ -279,6 +284,228 @@ where
         None
     }
 
+    /// Runs the search until the next goal is found, telling why it failed
+    /// otherwise.
+    #[must_use]
+    pub fn solve_reporting(&mut self) -> SearchOutcome<St, A, C> {
+        if self.remaining_goals_set.is_empty() {
+            return SearchOutcome::NoGoals;
+        }
+        match self.find_next_goal() {
+            Some(path) => SearchOutcome::Found(path),
+            None if self.timed_out() => SearchOutcome::Interrupted,
+            None => SearchOutcome::Unreachable,
+        }
+    }
+
+    /// Reconstructs the optimal path to an expanded state as
+    /// `(state, action, g)` steps, with the cost accumulated up to each state.
+    ///
+    /// Returns `None` if the state wasn't reached or is still open, as its
+    /// cost may still improve.
+    #[must_use]
+    pub fn annotated_path_to(&self, s: &St) -> Option<Vec<(St, A, C)>> {
+        let node_index = self.node_map.get(s)?;
+        if !node_index.is_closed() {
+            return None;
+        }
+        Some(
+            self.search_tree
+                .annotated_path(self.problem.space(), *node_index),
+        )
+    }
+
+    /// Closes and expands a node, reaching its neighbours.
+    ///
+    /// Returns the expanded state.
+    #[inline(always)]
+    fn expand(&mut self, node_index: SearchTreeIndex) -> St {
+        let state = *self.search_tree[node_index].state();
+        let g: C = self.search_tree[node_index].g;
+        let depth = self.search_tree[node_index].depth;
+        debug_assert!(!self.is_closed(&state));
+
+        // Mark as closed
+        self.mark_closed(&state);
+        self.expanded += 1;
+
+        // Expand state
+        let mut neighbours = std::mem::take(&mut self.neighbours);
+        neighbours.clear();
+        self.problem
+            .space()
+            .for_each_neighbour(&state, |s, a| neighbours.push((s, a)));
+        for &(s, a) in &neighbours {
+            #[cfg(feature = "coz_profile")]
+            coz::scope!("ReachNode");
+
+            // Have we seen this State?
+            match self.node_map.get(&s) {
+                Some(neigh_index) => {
+                    #[cfg(feature = "coz_profile")]
+                    coz::scope!("ReachExistingNode");
+                    if neigh_index.is_closed() {
+                        // Yes, and we expanded the State already.
+                        // NOTE: Could be a goal we had already found through a
+                        // sub-optimal path. Currently we only search for
+                        // an optimal path to a new goal.
+                        continue;
+                    }
+
+                    // Yes, but it's still unexplored. Update the existing
+                    // Node if needed.
+                    let neigh = &mut self.search_tree[*neigh_index];
+                    let neigh_heap_index = neigh.heap_index;
+                    let c: C = self.problem.space().cost(&state, &a);
+                    let new_g = g.saturating_add(&c);
+                    if new_g < neigh.g {
+                        // Found better path to existing node
+                        neigh.reach((node_index, a), new_g, depth + 1);
+                        self.open[neigh_heap_index].rank.improve_g(new_g);
+                        self.open
+                            .decrease_key(neigh_heap_index, &mut self.search_tree);
+                        self.generated += 1;
+                    }
+                }
+                None => {
+                    #[cfg(feature = "coz_profile")]
+                    coz::scope!("ReachNewNode");
+                    // No, let's create a new Node for it.
+                    let c: C = self.problem.space().cost(&state, &a);
+                    let new_g = g.saturating_add(&c);
+
+                    self.push_new(&s, Some((node_index, a)), new_g);
+                }
+            }
+        }
+        self.neighbours = neighbours;
+
+        state
+    }
+
+    /// Expands the open nodes in order until exhausting the open list,
+    /// yielding every expanded state.
+    ///
+    /// Unlike `find_next_goal`, it doesn't stop on goals, which floods the
+    /// whole reachable space even for problems without goals. Goals expanded
+    /// this way are dropped from the remaining goals without a path.
+    pub fn expanded_states(&mut self) -> impl Iterator<Item = St> + '_ {
+        std::iter::from_fn(move || {
+            let node_index = self.pop()?;
+            let state = self.expand(node_index);
+            if unlikely(self.is_goal(&state)) {
+                self.remove_goal(&state);
+            }
+            Some(state)
+        })
+    }
+
+    /// Runs the search until exhausting the open list, ignoring goals.
+    fn exhaust(&mut self) {
+        while let Some(node_index) = self.pop() {
+            self.expand(node_index);
+        }
+    }
+
+    /// Consumes the search getting the optimal cost to every reachable state.
+    ///
+    /// The search is first run to completion, so this explores the whole
+    /// reachable space regardless of the goals.
+    #[must_use]
+    pub fn into_distance_map(mut self) -> FxHashMap<St, C> {
+        self.exhaust();
+        self.node_map
+            .iter()
+            .map(|(s, node_index)| (*s, self.search_tree[*node_index].g))
+            .collect()
+    }
+
+    /// Consumes the search getting the optimal parent link of every reachable
+    /// state.
+    ///
+    /// Starts have no parent. The search is first run to completion, so this
+    /// explores the whole reachable space regardless of the goals.
+    #[must_use]
+    pub fn into_shortest_path_tree(mut self) -> FxHashMap<St, Option<(St, A)>> {
+        self.exhaust();
+        self.node_map
+            .iter()
+            .map(|(s, node_index)| {
+                let parent = self.search_tree[*node_index]
+                    .parent()
+                    .map(|(parent_index, a)| (*self.search_tree[parent_index].state(), a));
+                (*s, parent)
+            })
+            .collect()
+    }
+
+    /// Makes `find_next_goal` calls give up after a deadline.
+    ///
+    /// Calls running out of time return `None` without dropping goals, and
+    /// the next call resumes the search. The clock is only checked every
+    /// `DEADLINE_CHECK_INTERVAL` expansions.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Whether the last `find_next_goal` call ran out of time.
+    #[inline(always)]
+    #[must_use]
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    #[inline(always)]
+    #[must_use]
+    fn past_deadline(&self, expansions: usize) -> bool {
+        match self.deadline {
+            Some(deadline) => {
+                expansions.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() >= deadline
+            }
+            None => false,
+        }
+    }
+
+    /// Gets the rank of the best open node without popping it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek_rank(&self) -> Option<DijkstraRank<C>> {
+        self.open.peek().map(|hn| hn.rank)
+    }
+
+    /// Gets the state of the best open node without popping it.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek_state(&self) -> Option<St> {
+        self.open
+            .peek()
+            .map(|hn| *self.search_tree[hn.node_index].state())
+    }
+
+    /// Iterates over the open nodes, in heap order.
+    #[cfg(feature = "inspect")]
+    pub fn frontier(&self) -> impl Iterator<Item = (St, DijkstraRank<C>)> + '_ {
+        self.open
+            .iter()
+            .map(|hn| (*self.search_tree[hn.node_index].state(), hn.rank))
+    }
+
+    /// The number of nodes expanded so far.
+    #[inline(always)]
+    #[must_use]
+    pub fn expanded_count(&self) -> usize {
+        self.expanded
+    }
+
+    /// The number of nodes generated so far.
+    ///
+    /// Nodes reached again through a better path count again.
+    #[inline(always)]
+    #[must_use]
+    pub fn generated_count(&self) -> usize {
+        self.generated
+    }
+
     /// Checks if a state is an undiscovered goal.
     #[inline(always)]
     fn is_goal(&mut self, s: &St) -> bool {
@@ -327,15 +554,10 @@ where
         #[cfg(feature = "coz_profile")]
         coz::scope!("Pop");
 
-        match self.open.len() {
-            0 | 1 => self.open.pop().map(|n| n.node_index),
-            _ => {
-                self.verify_heap();
-                let node_index = self._unsafe_pop_non_trivial_heap();
-                self.verify_heap();
-                Some(node_index)
-            }
-        }
+        self.verify_heap();
+        let node_index = self.open.pop(&mut self.search_tree).map(|n| n.node_index);
+        self.verify_heap();
+        node_index
     }
 
     #[inline(always)]
@@ -360,11 +582,14 @@ where
         self.node_map.insert(*s, node_index);
 
         // 3. Add `DijkstraHeapNode` to open using it's `SearchTreeIndex`
-        self.open.push(DijkstraHeapNode {
-            rank: DijkstraRank::new(g),
-            node_index,
-        });
-        self._unsafe_sift_up(heap_index);
+        self.generated += 1;
+        self.open.push(
+            DijkstraHeapNode {
+                rank: DijkstraRank::new(g),
+                node_index,
+            },
+            &mut self.search_tree,
+        );
 
         self.verify_heap();
     }
@@ -377,174 +602,11 @@ where
     #[inline(always)]
     #[cfg(feature = "verify")]
     pub(crate) fn verify_heap(&self) {
-        // Every node,
+        self.open.verify_heap();
+        // Every node has the right intrusive index set.
         for (i, e) in self.open.iter().enumerate() {
-            // - Has the right intrusive index set.
             debug_assert!(self.search_tree[e.node_index].heap_index == i);
-
-            // - Goes after its parent node, if any.
-            if i == 0 {
-                continue;
-            }
-            let p = up(i);
-            debug_assert!(
-                self.open[p].rank <= self.open[i].rank,
-                "Node[{p}]={:?} !<= child [{i}]={:?}. Out of heap of len={}",
-                self.open[p],
-                self.open[i],
-                self.open.len(),
-            );
-        }
-    }
-
-    /// Pops the top node from a Heap with at least 2 elements.
-    ///
-    /// Works by unfairly sifting down the top-node to the last level, where it can
-    /// be swapped with the very last element of the array and popped
-    /// Temporarily breaks invariants around the node sifting down unfairly.
-    fn _unsafe_pop_non_trivial_heap(&mut self) -> SearchTreeIndex {
-        #[cfg(feature = "coz_profile")]
-        coz::scope!("PopNonTrivial");
-
-        debug_assert!(!self.open.is_empty(), "You can't pop from an empty heap");
-        debug_assert!(
-            self.open.len() != 1,
-            "It doesn't get easier. Why are you calling this?"
-        );
-
-        // Note that there's at least 2 nodes before we remove the best.
-        // 1. We pretend there's a hole at the root, and bubble elements up till the hole reaches the bottom.
-        // 2. If the hole is not the last element, we swap it for the last one.
-        // 3. Now the last element is the one that was at the top of the heap, we pop it.
-
-        let len = self.open.len();
-        let last = len - 1;
-
-        // Initialize bubble-down indices
-        let mut hole = 0;
-        let mut child = down_left(hole); // Initially left child, reused to track the best child
-        debug_assert!(hole < len, "The hole IS NOT a valid index");
-        debug_assert!(child < len, "Left child IS NOT a valid index");
-        debug_assert!(hole < child);
-
-        loop {
-            debug_assert!(hole < len, "The hole IS NOT a valid index");
-            debug_assert!(child < len, "Left child IS NOT a valid index");
-
-            // Find the best child
-            child = down_left(hole);
-            debug_assert_eq!(child + HEAP_ARITY, down_right(hole) + 1);
-            child += derank(&self.open[child..min(child + HEAP_ARITY, len)]);
-
-            debug_assert!(self.open[hole].rank <= self.open[child].rank);
-
-            // Swap and update internal indices
-            self._unsafe_half_swap_down(hole, child);
-
-            // Update bubble-down indices
-            hole = child;
-            child = down_left(hole); // New left child
-            if child >= self.open.len() {
-                break;
-            }
-        }
-        // NOTE: So far the hole made it to the last level, but it may not be at the end of the array.
-        debug_assert!(hole <= last, "The hole={hole} is < last={last}");
-        debug_assert!(hole > up(last), "The hole={hole} is < last={last}");
-        if hole != last {
-            // Swap and update internal indices
-            self._unsafe_half_swap_down(hole, last);
-            self._unsafe_sift_up(hole);
-        }
-
-        let heap_node = self.open.pop().unwrap();
-        debug_assert_eq!(
-            self.search_tree[heap_node.node_index].heap_index, 0,
-            "Top node half-assed swapped down should still have it's 0 index"
-        );
-
-        heap_node.node_index
-    }
-
-    /// Raises a node
-    /// Returns it's new index
-    #[inline(always)]
-    fn _unsafe_sift_up(&mut self, index: usize) -> usize {
-        debug_assert!(
-            index < self.open.len(),
-            "Node is way out of sync. Index out of bounds..."
-        );
-        debug_assert_eq!(
-            self.search_tree[self.open[index].node_index].heap_index, index,
-            "Node is out of sync."
-        );
-
-        // Can't improve
-        if index == 0 {
-            return index;
-        }
-
-        let mut pos = index;
-        let mut parent = up(pos);
-        while self.open[parent].rank > self.open[pos].rank {
-            // Nodes are different and swapped. Swap the nodes to fix the order.
-            self._unsafe_swap(parent, pos);
-            debug_assert!(self.open[parent].rank < self.open[pos].rank);
-
-            // Continue swapping upwards if needed..
-            if parent == 0 {
-                return parent;
-            }
-            pos = parent;
-            parent = up(pos);
         }
-        pos
-    }
-
-    // Swapping primitives
-    /// Swaps two elements in the heap.
-    ///
-    /// For consistency in calling code `l < r` is checked.
-    ///
-    /// Keeps the intrusive indices in sync.
-    #[inline(always)]
-    fn _unsafe_swap(&mut self, l: usize, r: usize) {
-        debug_assert!(l < r, "Swap({l}, {r}) uses wrong argument order");
-
-        let len = self.open.len();
-        debug_assert!(l < len, "Left  swap index {l} is OUT OF BOUNDS({len})");
-        debug_assert!(r < len, "Right swap index {r} is OUT OF BOUNDS({len})");
-        self.open.swap(l, r);
-        self.search_tree[self.open[l].node_index].heap_index = l;
-        self.search_tree[self.open[r].node_index].heap_index = r;
-        debug_assert!(
-            self.open[l].rank <= self.open[r].rank,
-            "Swaps must locally restore the heap invariant."
-        );
-    }
-    /// Swaps two elements in the heap.
-    ///
-    /// For consistency in calling code `l < r` is checked.
-    ///
-    /// Only keeps the index of the element going up in sync as we should shortly
-    /// after remove the element that goes down.
-    #[inline(always)]
-    fn _unsafe_half_swap_down(&mut self, l: usize, r: usize) {
-        debug_assert!(l < r, "HalfSwapDown({l}, {r}) is wrong");
-
-        let len = self.open.len();
-        debug_assert!(l < len, "Left  swap index {l} is OUT OF BOUNDS({len})");
-        debug_assert!(r < len, "Right swap index {r} is OUT OF BOUNDS({len})");
-        self.open.swap(l, r);
-        self.search_tree[self.open[l].node_index].heap_index = l;
-        debug_assert!(
-            self.open[l].rank >= self.open[r].rank, // (Q: What if there's only one value? We still push node at the top down)
-            "Half-assed swap down must be unfairly pushing a node down."
-        );
-        debug_assert!(
-            self.search_tree[self.open[r].node_index].heap_index < r,
-            "Node half-assed swapped down should still point to it's original index."
-        );
     }
 
     pub fn write_memory_stats<W: std::io::Write>(&self, mut out: W) -> std::io::Result<()> {
@@ -594,11 +656,15 @@ where
             Size::from_bytes(c * s)
         )?;
 
-        let expanded_nodes = self.search_tree.len() - self.open.len();
         writeln!(
             out,
             "  - Expanded nodes: {}",
-            expanded_nodes.separate_with_commas()
+            self.expanded.separate_with_commas()
+        )?;
+        writeln!(
+            out,
+            "  - Generated nodes: {}",
+            self.generated.separate_with_commas()
         )?;
 
         Ok(())
@@ -614,7 +680,7 @@ where
     Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     type Item = Path<St, A, C>;
     fn next(&mut self) -> Option<Self::Item> {
@@ -622,10 +688,298 @@ where
     }
 }
 
+/// Gets the optimal cost from each source to every state it reaches.
+///
+/// Sources are split across up to `std::thread::available_parallelism()`
+/// threads sharing the space. Maps are returned in the same order as the
+/// sources.
+#[must_use]
+pub fn parallel_distance_maps<Sp, St, A, C>(space: &Sp, sources: &[St]) -> Vec<FxHashMap<St, C>>
+where
+    Sp: Space<St, A, C> + Sync,
+    St: State + Send + Sync,
+    A: Action,
+    C: OrdCost + Send,
+{
+    if sources.is_empty() {
+        return Vec::new();
+    }
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(sources.len());
+    let chunk_size = sources.len().div_ceil(workers);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = sources
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|&source| {
+                            let problem = FloodProblem::new(space, source);
+                            DijkstraSearch::new(problem).into_distance_map()
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "inspect")]
+    #[test]
+    fn frontier() {
+        use indoc::indoc;
+
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DProblem;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S...
+            .G..
+            ...G
+        "})
+        .unwrap();
+        let start = problem.starts()[0];
+        let mut search = DijkstraSearch::new(problem);
+
+        let frontier: Vec<_> = search.frontier().collect();
+        assert_eq!(frontier.len(), 1);
+        assert_eq!(frontier[0].0, start);
+
+        let first = search.find_next_goal().unwrap();
+
+        // Nodes are expanded by increasing g, so nothing open is closer.
+        let frontier: Vec<_> = search.frontier().collect();
+        assert_eq!(frontier.len(), search.open.len());
+        for (s, rank) in &frontier {
+            assert!(!search.is_closed(s));
+            assert_eq!(rank.g, search.search_tree[search.node_map[s]].g);
+            assert!(rank.g >= first.cost());
+        }
+        assert_eq!(
+            frontier.iter().map(|(_s, rank)| *rank).min(),
+            search.peek_rank()
+        );
+    }
+
+    #[test]
+    fn peek() {
+        use indoc::indoc;
+
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DProblem;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S..
+            .G.
+            ...
+        "})
+        .unwrap();
+        let start = problem.starts()[0];
+        let mut search = DijkstraSearch::new(problem);
+
+        assert_eq!(search.peek_rank(), Some(DijkstraRank::new(0)));
+        assert_eq!(search.peek_state(), Some(start));
+        assert!(search.find_next_goal().is_some());
+        assert!(
+            search
+                .peek_rank()
+                .is_some_and(|r| r >= DijkstraRank::new(100))
+        );
+    }
+
+    #[test]
+    fn flood() {
+        use crate::problems::maze_2d::Connectivity;
+        use crate::problems::maze_2d::Maze2DCell;
+        use crate::problems::maze_2d::Maze2DProblem;
+        use crate::problems::maze_2d::Maze2DSpace;
+        use crate::problems::maze_2d::Maze2DState;
+
+        let mut space =
+            Maze2DSpace::new_empty_with_dimensions(4, 3).with_connectivity(Connectivity::Four);
+        // Walls off the bottom-right corner.
+        space.set_cell(&Maze2DState::new(2, 2).unwrap(), Maze2DCell::Wall);
+        space.set_cell(&Maze2DState::new(3, 1).unwrap(), Maze2DCell::Wall);
+        let mut problem = Maze2DProblem::from(space);
+        assert!(problem.add_start(&Maze2DState::new(0, 0).unwrap()));
+
+        let mut search = DijkstraSearch::new(problem);
+        assert_eq!(search.find_next_goal(), None);
+        assert_eq!(search.expanded_count(), 0);
+
+        let visited: Vec<_> = search.expanded_states().collect();
+        assert_eq!(visited.len(), 4 * 3 - 3);
+        assert!(!visited.contains(&Maze2DState::new(3, 2).unwrap()));
+        assert_eq!(
+            FxHashSet::from_iter(visited.iter().cloned()).len(),
+            visited.len()
+        );
+        assert_eq!(search.expanded_count(), visited.len());
+    }
+
+    #[test]
+    fn parallel_distance_maps() {
+        use indoc::indoc;
+
+        use crate::problem::BaseProblem;
+        use crate::problem::ObjectiveProblem;
+        use crate::problems::maze_2d::Maze2DProblem;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S..#.
+            .#...
+            ...#G
+        "})
+        .unwrap();
+        let sources = [problem.starts()[0], problem.goals()[0]];
+        let maps = super::parallel_distance_maps(problem.space(), &sources);
+
+        assert_eq!(maps.len(), 2);
+        assert_eq!(maps[0][&sources[0]], 0);
+        assert_eq!(maps[1][&sources[1]], 0);
+        assert_eq!(maps[0][&sources[1]], maps[1][&sources[0]]);
+        assert_eq!(
+            maps[0],
+            DijkstraSearch::new(problem.clone()).into_distance_map()
+        );
+
+        // Sources split across threads keep their order.
+        let sources: Vec<_> = maps[0].keys().copied().collect();
+        let maps = super::parallel_distance_maps(problem.space(), &sources);
+        assert_eq!(maps.len(), sources.len());
+        for (source, map) in sources.iter().zip(&maps) {
+            assert_eq!(map[source], 0);
+        }
+    }
+
+    #[test]
+    fn distance_map() {
+        use crate::problems::maze_2d::Maze2DAction;
+        use crate::problems::maze_2d::Maze2DProblem;
+        use crate::problems::maze_2d::Maze2DSpace;
+        use crate::problems::maze_2d::Maze2DState;
+
+        let mut problem = Maze2DProblem::from(Maze2DSpace::new_empty_with_dimensions(5, 5));
+        let start = Maze2DState::new_from_usize(2, 2).unwrap();
+        let right = Maze2DState::new_from_usize(3, 2).unwrap();
+        let corner = Maze2DState::new_from_usize(4, 4).unwrap();
+        assert!(problem.add_start(&start));
+        assert!(problem.add_goal(&right));
+
+        let distances = DijkstraSearch::new(problem.clone()).into_distance_map();
+        assert_eq!(distances.len(), 25);
+        assert_eq!(distances[&start], 0);
+        assert_eq!(distances[&right], 100);
+        assert_eq!(distances[&corner], 282);
+
+        let tree = DijkstraSearch::new(problem).into_shortest_path_tree();
+        assert_eq!(tree.len(), 25);
+        assert_eq!(tree[&start], None);
+        assert_eq!(tree[&right], Some((start, Maze2DAction::Right)));
+    }
+
+    #[test]
+    fn deadline() {
+        use std::time::Duration;
+
+        use crate::problems::maze_2d::Maze2DProblem;
+        use crate::problems::maze_2d::Maze2DSpace;
+        use crate::problems::maze_2d::Maze2DState;
+
+        let mut problem = Maze2DProblem::from(Maze2DSpace::new_empty_with_dimensions(300, 300));
+        assert!(problem.add_start(&Maze2DState::new_from_usize(0, 0).unwrap()));
+        assert!(problem.add_goal(&Maze2DState::new_from_usize(299, 150).unwrap()));
+
+        let mut search = DijkstraSearch::new(problem);
+        search.set_deadline(Instant::now());
+        assert_eq!(search.find_next_goal(), None);
+        assert!(search.timed_out());
+
+        search.set_deadline(Instant::now() + Duration::from_secs(60));
+        assert!(search.find_next_goal().is_some());
+        assert!(!search.timed_out());
+    }
+
+    #[test]
+    fn solve_reporting() {
+        use crate::problems::maze_2d::Maze2DProblem;
+
+        let problem = Maze2DProblem::try_from("S.G#G").unwrap();
+        let mut search = DijkstraSearch::new(problem);
+        search.set_deadline(Instant::now());
+        assert_eq!(search.solve_reporting(), SearchOutcome::Interrupted);
+
+        search.set_deadline(Instant::now() + std::time::Duration::from_secs(60));
+        assert!(matches!(search.solve_reporting(), SearchOutcome::Found(_)));
+        assert_eq!(search.solve_reporting(), SearchOutcome::Unreachable);
+
+        let problem = Maze2DProblem::try_from("S.G").unwrap();
+        let mut search = DijkstraSearch::new(problem);
+        assert!(matches!(search.solve_reporting(), SearchOutcome::Found(_)));
+        assert_eq!(search.solve_reporting(), SearchOutcome::NoGoals);
+    }
+
+    #[test]
+    fn annotated_path_to() {
+        use crate::problems::maze_2d::Maze2DAction;
+        use crate::problems::maze_2d::Maze2DProblem;
+        use crate::problems::maze_2d::Maze2DState;
+
+        let problem = Maze2DProblem::try_from("S..G.").unwrap();
+        let mut search = DijkstraSearch::new(problem);
+        let goal = Maze2DState::new_from_usize(3, 0).unwrap();
+        let beyond = Maze2DState::new_from_usize(4, 0).unwrap();
+        assert_eq!(search.annotated_path_to(&goal), None);
+
+        let path = search.find_next_goal().unwrap();
+        let steps = search.annotated_path_to(&goal).unwrap();
+        assert_eq!(steps.len(), path.len());
+        assert_eq!(
+            steps.last(),
+            Some(&(goal, Maze2DAction::Right, path.cost()))
+        );
+        assert_eq!(steps[0].2, 100);
+        assert_eq!(search.annotated_path_to(&beyond), None);
+    }
+
+    #[test]
+    fn counters() {
+        use indoc::indoc;
+
+        use crate::problems::maze_2d::Maze2DProblem;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S..#....
+            .#.#.##.
+            .#...#G.
+            .####.#.
+            ......#G
+        "})
+        .unwrap();
+
+        let mut search = DijkstraSearch::new(problem);
+        assert_eq!(search.expanded_count(), 0);
+        assert_eq!(search.generated_count(), 1);
+        assert_eq!(search.by_ref().count(), 2);
+        assert!(search.expanded_count() > 0);
+        assert!(search.expanded_count() <= search.generated_count());
+    }
+
     #[test]
     fn ranking_maze2d() {
         use crate::problems::maze_2d::Maze2DCost;
@@ -642,6 +996,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "image")]
     fn solve_maze2d() {
         use std::path::PathBuf;
 
@@ -657,8 +1012,8 @@ mod tests {
         let mut search = DijkstraSearch::new(problem);
         let path = search.find_next_goal().unwrap();
 
-        assert!(starts.contains(&path.start.unwrap()));
-        assert!(goals.contains(&path.end.unwrap()));
-        assert_eq!(path.cost, 1805);
+        assert!(starts.contains(&path.start().unwrap()));
+        assert!(goals.contains(&path.end().unwrap()));
+        assert_eq!(path.cost(), 1805);
     }
 }
@@ -0,0 +1,378 @@
+//! Bounded-suboptimal Focal Search (A*ε).
+//!
+//! Weighted A* gets its speed from trusting an inflated heuristic. Focal
+//! Search instead keeps the admissible `f = g + h` ordering to know the bound
+//! `weight * f_min`, and among the open nodes within it (the focal list)
+//! expands the one estimated to be fewest actions away from a goal.
+
+use std::cmp::max;
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+use std::ops::Bound::Excluded;
+use std::ops::Bound::Included;
+
+use num_traits::One;
+use num_traits::SaturatingMul;
+use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
+
+use crate::problem::ObjectiveProblem;
+use crate::search::SearchTree;
+use crate::search::SearchTreeIndex;
+use crate::search::SearchTreeNode;
+use crate::space::Action;
+use crate::space::DistanceHeuristic;
+use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::Space;
+use crate::space::State;
+
+/// An open node, referenced by its position in `FocalSearch::entries`.
+#[derive(Copy, Clone, Debug)]
+struct FocalEntry<C: OrdCost> {
+    node_index: SearchTreeIndex,
+    f: C,
+    h: C,
+    /// The estimated number of actions to the nearest goal.
+    d: usize,
+}
+
+/// Focal Search for Objective Problems.
+///
+/// Paths cost at most `weight` times the optimal cost when `OH` is admissible.
+/// Closed nodes reached through a better path are re-opened to keep `f_min` a
+/// lower bound of the optimal cost.
+#[derive(Debug)]
+pub struct FocalSearch<OH, DH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    DH: DistanceHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// All the Search Nodes.
+    ///
+    /// `SearchTreeNode::heap_index` holds the position of the node's entry.
+    search_tree: SearchTree<St, A, C>,
+    node_map: FxHashMap<St, SearchTreeIndex>,
+
+    /// Entries of the nodes that have been open, re-used as they are updated.
+    entries: Vec<FocalEntry<C>>,
+    /// The open nodes as `(f, entry)`.
+    open: BTreeSet<(C, usize)>,
+    /// The open nodes within `bound` as `(d, f, entry)`.
+    focal: BTreeSet<(usize, C, usize)>,
+    /// The largest f-value allowed in `focal`.
+    bound: C,
+    /// The sub-optimality bound.
+    weight: C,
+
+    remaining_goals_list: Vec<St>,
+    remaining_goals_set: FxHashSet<St>,
+
+    /// The number of expanded nodes.
+    expanded: usize,
+    /// The number of generated nodes, counting again the ones reached
+    /// through a better path.
+    generated: usize,
+
+    problem: OP,
+
+    _phantom_heuristic: PhantomData<OH>,
+    _phantom_distance: PhantomData<DH>,
+    _phantom_space: PhantomData<Sp>,
+}
+
+impl<OH, DH, OP, Sp, St, A, C> FocalSearch<OH, DH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    DH: DistanceHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost + SaturatingMul + One,
+{
+    /// Initializes the Search, bounding costs by `weight` times the optimum.
+    ///
+    /// Weights below 1 are raised to 1.
+    #[must_use]
+    pub fn new(op: OP, weight: C) -> Self {
+        let goals = op.goals().to_vec();
+
+        let mut search = Self {
+            search_tree: SearchTree::new(),
+            node_map: FxHashMap::default(),
+            entries: Vec::new(),
+            open: BTreeSet::new(),
+            focal: BTreeSet::new(),
+            bound: C::zero(),
+            weight: max(weight, C::one()),
+            remaining_goals_set: FxHashSet::from_iter(goals.iter().cloned()),
+            remaining_goals_list: goals,
+            expanded: 0,
+            generated: 0,
+            problem: op,
+            _phantom_heuristic: PhantomData,
+            _phantom_distance: PhantomData,
+            _phantom_space: PhantomData,
+        };
+
+        let starts = search.problem.starts().to_vec();
+        for s in starts {
+            search.push_new(&s, None, C::zero());
+        }
+        search
+    }
+
+    /// Runs the search until a goal is found.
+    ///
+    /// Found goals are dropped from the remaining ones, but open nodes keep
+    /// their heuristic towards them, which is still admissible.
+    #[must_use]
+    pub fn find_next_goal(&mut self) -> Option<Path<St, A, C>> {
+        if self.remaining_goals_set.is_empty() {
+            return None;
+        }
+
+        while let Some(&(f_min, _entry)) = self.open.first() {
+            self.update_bound(max(f_min, f_min.saturating_mul(&self.weight)));
+
+            // The best open node is always within the bound.
+            let (_d, f, entry) = self.focal.pop_first().unwrap();
+            self.open.remove(&(f, entry));
+            let node_index = self.entries[entry].node_index;
+            let state = self.expand(node_index);
+
+            if self.remaining_goals_set.remove(&state) {
+                let i = self
+                    .remaining_goals_list
+                    .iter()
+                    .position(|g| *g == state)
+                    .unwrap();
+                self.remaining_goals_list.swap_remove(i);
+                return Some(self.search_tree.path(self.problem.space(), node_index));
+            }
+        }
+
+        None
+    }
+
+    /// The number of nodes expanded so far.
+    #[inline(always)]
+    #[must_use]
+    pub fn expanded_count(&self) -> usize {
+        self.expanded
+    }
+
+    /// The number of nodes generated so far.
+    ///
+    /// Nodes reached again through a better path count again.
+    #[inline(always)]
+    #[must_use]
+    pub fn generated_count(&self) -> usize {
+        self.generated
+    }
+
+    /// Moves the open nodes entering or leaving the bound in or out of focal.
+    fn update_bound(&mut self, bound: C) {
+        if bound > self.bound {
+            let range = (
+                Excluded((self.bound, usize::MAX)),
+                Included((bound, usize::MAX)),
+            );
+            for &(f, entry) in self.open.range(range) {
+                self.focal.insert((self.entries[entry].d, f, entry));
+            }
+        } else if bound < self.bound {
+            let range = (
+                Excluded((bound, usize::MAX)),
+                Included((self.bound, usize::MAX)),
+            );
+            for &(f, entry) in self.open.range(range) {
+                self.focal.remove(&(self.entries[entry].d, f, entry));
+            }
+        }
+        self.bound = bound;
+    }
+
+    /// Closes a node and generates its neighbours.
+    ///
+    /// Returns the expanded state.
+    fn expand(&mut self, node_index: SearchTreeIndex) -> St {
+        let state = *self.search_tree[node_index].state();
+        let g = self.search_tree[node_index].g;
+        let depth = self.search_tree[node_index].depth;
+        self.node_map.get_mut(&state).unwrap().set_closed();
+        self.expanded += 1;
+
+        for (s, a) in self.problem.space().neighbours(&state) {
+            let new_g = g + self.problem.space().cost(&state, &a);
+            let parent = (node_index, a);
+
+            let Some(neigh_index) = self.node_map.get(&s).copied() else {
+                self.push_new(&s, Some(parent), new_g);
+                continue;
+            };
+            if new_g >= self.search_tree[neigh_index].g {
+                continue;
+            }
+
+            self.generated += 1;
+            self.search_tree[neigh_index].reach(parent, new_g, depth + 1);
+            if neigh_index.is_closed() {
+                // Re-open it with a new entry.
+                let map_index = self.node_map.get_mut(&s).unwrap();
+                map_index.set_open();
+                let node_index = *map_index;
+                let (h, d) = self.estimate(&s);
+                self.push_entry(node_index, new_g, h, d);
+            } else {
+                let entry = self.search_tree[neigh_index].heap_index;
+                let FocalEntry { f, h, d, .. } = self.entries[entry];
+                self.open.remove(&(f, entry));
+                let in_focal = self.focal.remove(&(d, f, entry));
+
+                let f = new_g + h;
+                self.entries[entry].f = f;
+                self.open.insert((f, entry));
+                if in_focal || f <= self.bound {
+                    self.focal.insert((d, f, entry));
+                }
+            }
+        }
+
+        state
+    }
+
+    /// The cost and distance estimates towards the nearest goal.
+    fn estimate(&self, s: &St) -> (C, usize) {
+        let mut h = C::max_value();
+        let mut d = usize::MAX;
+        for goal in &self.remaining_goals_list {
+            h = h.min(OH::h_in(self.problem.space(), s, goal));
+            d = d.min(DH::d(s, goal));
+        }
+        (h, d)
+    }
+
+    fn push_new(&mut self, s: &St, parent: Option<(SearchTreeIndex, A)>, g: C) {
+        let (h, d) = self.estimate(s);
+        let node_index = self
+            .search_tree
+            .push(SearchTreeNode::new(usize::MAX, *s, parent, g));
+        self.node_map.insert(*s, node_index);
+        self.push_entry(node_index, g, h, d);
+    }
+
+    fn push_entry(&mut self, node_index: SearchTreeIndex, g: C, h: C, d: usize) {
+        let f = g.saturating_add(&h);
+        let entry = self.entries.len();
+        self.entries.push(FocalEntry {
+            node_index,
+            f,
+            h,
+            d,
+        });
+        self.search_tree[node_index].heap_index = entry;
+
+        self.generated += 1;
+        self.open.insert((f, entry));
+        if f <= self.bound {
+            self.focal.insert((d, f, entry));
+        }
+    }
+}
+
+impl<OH, DH, OP, Sp, St, A, C> Iterator for FocalSearch<OH, DH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    DH: DistanceHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost + SaturatingMul + One,
+{
+    type Item = Path<St, A, C>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.find_next_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::algorithms::astar::AStarSearch;
+    use crate::problem::BaseProblem;
+    use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+    use crate::problems::maze_2d::Maze2DProblem;
+
+    type Focal<OP> = FocalSearch<
+        Maze2DHeuristicDiagonalDistance,
+        Maze2DHeuristicDiagonalDistance,
+        OP,
+        crate::problems::maze_2d::Maze2DSpace,
+        crate::problems::maze_2d::Maze2DState,
+        crate::problems::maze_2d::Maze2DAction,
+        crate::problems::maze_2d::Maze2DCost,
+    >;
+
+    #[test]
+    fn bounded() {
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.....#.......
+            .####.#.####..
+            .#....#....#..
+            .#.######..#..
+            .#.........#.G
+            .###########..
+            ..............
+        "})
+        .unwrap();
+        let optimal =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone())
+                .find_next_goal()
+                .unwrap()
+                .cost();
+
+        for weight in [1, 2, 3] {
+            let path = Focal::new(problem.clone(), weight)
+                .find_next_goal()
+                .unwrap();
+            assert!(problem.space().valid_path(&path));
+            assert_eq!(path.end(), Some(problem.goals()[0]));
+            assert!(path.cost() >= optimal);
+            assert!(path.cost() <= weight * optimal);
+        }
+
+        let path = Focal::new(problem, 1).find_next_goal().unwrap();
+        assert_eq!(path.cost(), optimal);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn two_paths() {
+        use std::path::PathBuf;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let mut astar =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        let optimal = astar.find_next_goal().unwrap().cost();
+
+        let mut focal = Focal::new(problem, 2);
+        let path = focal.find_next_goal().unwrap();
+        assert!(path.cost() <= 2 * optimal);
+        assert!(focal.expanded_count() <= astar.expanded_count());
+        assert_eq!(focal.find_next_goal(), None);
+    }
+}
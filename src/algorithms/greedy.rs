@@ -0,0 +1,93 @@
+//! Implementation of Greedy Best-First Search.
+//!
+//! Nodes are ranked only by their heuristic value, which tends to find paths
+//! quickly, but without optimality guarantees.
+//!
+//! Apart from the ranking it's the same as A*, so it's an `AStarSearch` ranking
+//! nodes by `GreedyRank`.
+
+use crate::algorithms::astar::AStarSearch;
+use crate::algorithms::astar::Rank;
+use crate::space::OrdCost;
+
+/// The ranking value for Greedy Best-First Search
+///
+/// We prefer better h-values, `g` is ignored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GreedyRank<C: OrdCost> {
+    h: C,
+}
+impl<C> GreedyRank<C>
+where
+    C: OrdCost,
+{
+    /// The estimated cost left to a goal.
+    #[inline(always)]
+    #[must_use]
+    pub fn h(&self) -> C {
+        self.h
+    }
+}
+
+impl<C> Rank<C> for GreedyRank<C>
+where
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn new(_g: C, h: C) -> Self {
+        Self { h }
+    }
+    /// `g` doesn't affect the rank, so it's left untouched.
+    #[inline(always)]
+    fn improve_g(&mut self, _new_g: C) {}
+    #[inline(always)]
+    fn worsen_h(&mut self, new_h: C) -> bool {
+        if new_h > self.h {
+            self.h = new_h;
+            return true;
+        }
+        false
+    }
+}
+
+/// Greedy Best-First search implementation for Objective Problems.
+///
+/// This initializes the search and offers an Iterator that goes around
+/// different solutions.
+///
+/// NOTE: Paths are not optimal, `g` is only tracked to report path costs.
+pub type GreedySearch<OH, OP, Sp, St, A, C> = AStarSearch<OH, OP, Sp, St, A, C, GreedyRank<C>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn solve_maze2d() {
+        use std::path::PathBuf;
+
+        use crate::problem::BaseProblem;
+        use crate::problem::ObjectiveProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+        use crate::problems::maze_2d::Maze2DProblem;
+        use crate::space::Space;
+
+        // Solve `two-paths.png` (`://data/problems/Maze2D/two-paths.png`)
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let starts = problem.starts().to_vec();
+        let goals = problem.goals().to_vec();
+        let space = problem.space().clone();
+        let mut search =
+            GreedySearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        let path = search.find_next_goal().unwrap();
+
+        assert!(starts.contains(&path.start().unwrap()));
+        assert!(goals.contains(&path.end().unwrap()));
+        assert!(space.valid_path(&path));
+        // Not necessarily optimal
+        assert!(path.cost() >= 1805);
+    }
+}
@@ -0,0 +1,245 @@
+//! Implementation of Iterative Deepening A* (IDA*).
+//!
+//! IDA* runs depth-first searches bounded by an f-value threshold, raising the
+//! threshold to the smallest f-value that exceeded it after every iteration.
+//!
+//! Memory is linear in the path length as there's no Search Tree nor open list,
+//! but states may be expanded many times.
+
+use std::cmp::min;
+use std::marker::PhantomData;
+
+use rustc_hash::FxHashSet;
+
+use crate::problem::ObjectiveProblem;
+use crate::space::Action;
+use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::Space;
+use crate::space::State;
+
+/// The outcome of a bounded depth-first search.
+enum Bounded<C: OrdCost> {
+    /// A goal was found. The current path leads to it.
+    Found,
+    /// No goal within the bound. Carries the smallest f-value that exceeded it.
+    Exceeded(C),
+}
+
+/// IDA* search implementation for Objective Problems.
+///
+/// This initializes the search and offers an Iterator that goes around
+/// different solutions.
+///
+/// NOTE: The depth-first search is recursive, so very long paths may overflow
+/// the stack.
+#[derive(Debug)]
+pub struct IDASearch<OH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// The states on the current path, used to avoid cycles.
+    path_states: Vec<St>,
+    /// The actions on the current path.
+    path_actions: Vec<(A, C)>,
+
+    /// A list of remaining goals.
+    /// Used to compute objective heuristics.
+    remaining_goals_list: Vec<St>,
+    /// A set of remaining goals. Used for goal checks.
+    remaining_goals_set: FxHashSet<St>,
+
+    problem: OP,
+
+    _phantom_heuristic: PhantomData<OH>,
+    _phantom_space: PhantomData<Sp>,
+}
+
+impl<OH, OP, Sp, St, A, C> IDASearch<OH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// Initializes the Search
+    #[must_use]
+    pub fn new(op: OP) -> Self {
+        let goals = op.goals().to_vec();
+
+        Self {
+            path_states: vec![],
+            path_actions: vec![],
+            remaining_goals_list: goals.clone(),
+            remaining_goals_set: FxHashSet::from_iter(goals.iter().cloned()),
+
+            problem: op,
+
+            _phantom_heuristic: PhantomData,
+            _phantom_space: PhantomData,
+        }
+    }
+
+    /// Runs the search until the first goal is found.
+    ///
+    /// It removes the goal from the remaining goals so the next call looks for
+    /// another one.
+    #[must_use]
+    pub fn find_next_goal(&mut self) -> Option<Path<St, A, C>> {
+        if self.remaining_goals_list.is_empty() {
+            return None;
+        }
+
+        let starts = self.problem.starts().to_vec();
+        let mut bound = starts
+            .iter()
+            .map(|s| self.h(s))
+            .min()
+            .unwrap_or(C::max_value());
+
+        while bound != C::max_value() {
+            let mut next_bound = C::max_value();
+            for start in &starts {
+                self.path_states.clear();
+                self.path_actions.clear();
+                self.path_states.push(*start);
+
+                match self.bounded_dfs(C::zero(), bound) {
+                    Bounded::Found => {
+                        let path = self.current_path();
+                        self.remove_goal(&path.end().unwrap());
+                        return Some(path);
+                    }
+                    Bounded::Exceeded(f) => next_bound = min(next_bound, f),
+                }
+            }
+            bound = next_bound;
+        }
+
+        None
+    }
+
+    /// Depth-first search from the last state in the current path.
+    fn bounded_dfs(&mut self, g: C, bound: C) -> Bounded<C> {
+        let state = *self.path_states.last().unwrap();
+        let f = g.saturating_add(&self.h(&state));
+        if f > bound {
+            return Bounded::Exceeded(f);
+        }
+        if self.remaining_goals_set.contains(&state) {
+            return Bounded::Found;
+        }
+
+        let mut min_exceeded = C::max_value();
+        for (s, a) in self.problem.space().neighbours(&state) {
+            // Avoid cycles
+            if self.path_states.contains(&s) {
+                continue;
+            }
+            let c: C = self.problem.space().cost(&state, &a);
+
+            self.path_states.push(s);
+            self.path_actions.push((a, c));
+            match self.bounded_dfs(g + c, bound) {
+                Bounded::Found => return Bounded::Found,
+                Bounded::Exceeded(f) => min_exceeded = min(min_exceeded, f),
+            }
+            self.path_states.pop();
+            self.path_actions.pop();
+        }
+
+        Bounded::Exceeded(min_exceeded)
+    }
+
+    #[must_use]
+    fn current_path(&self) -> Path<St, A, C> {
+        let mut path = Path::with_capacity(self.path_states[0], self.path_actions.len());
+        for (s, (a, c)) in self.path_states[1..].iter().zip(&self.path_actions) {
+            path.append((*s, *a), *c);
+        }
+        path
+    }
+
+    /// Removes a state from the remaining goals.
+    #[inline(always)]
+    fn remove_goal(&mut self, goal: &St) {
+        self.remaining_goals_set.remove(goal);
+        self.remaining_goals_list.swap_remove(
+            self.remaining_goals_list
+                .iter()
+                .position(|&s| s == *goal)
+                .unwrap(),
+        );
+    }
+
+    #[inline(always)]
+    #[must_use]
+    fn h(&self, s: &St) -> C {
+        let mut h = C::max_value();
+        for g in &self.remaining_goals_list {
+            h = min(h, OH::h_in(self.problem.space(), s, g))
+        }
+        h
+    }
+}
+
+impl<OH, OP, Sp, St, A, C> Iterator for IDASearch<OH, OP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    type Item = Path<St, A, C>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.find_next_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_maze2d() {
+        use indoc::indoc;
+
+        use crate::algorithms::astar::AStarSearch;
+        use crate::problem::BaseProblem;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+        use crate::problems::maze_2d::Maze2DProblem;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S..#....
+            .#.#.##.
+            .#...#G.
+            .####.#.
+            ......#G
+        "})
+        .unwrap();
+        let space = problem.space().clone();
+
+        let astar =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        let ida = IDASearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+
+        let mut solutions = 0;
+        for (expected, path) in astar.zip(ida) {
+            assert!(space.valid_path(&path));
+            assert_eq!(path.cost(), expected.cost());
+            assert_eq!(path.end(), expected.end());
+            solutions += 1;
+        }
+        assert_eq!(solutions, 2);
+    }
+}
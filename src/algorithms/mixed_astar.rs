@@ -0,0 +1,426 @@
+//! Implementation of the A* path-finding algorithm for Mixed Problems.
+//!
+//! Goals are either objectives or states satisfying a condition, so the
+//! heuristic combines an objective and a condition heuristic. Like on
+//! Condition Problems, it never changes as goals are found.
+
+use core::intrinsics::unlikely;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use rustc_hash::FxHashSet;
+
+use crate::algorithms::astar::AStarNodes;
+use crate::algorithms::astar::AStarRank;
+use crate::algorithms::astar::NodeHeuristic;
+use crate::problem::MixedProblem;
+use crate::search::DEFAULT_NODE_MAP_CAPACITY;
+use crate::search::DEFAULT_OPEN_CAPACITY;
+use crate::search::SearchTreeIndex;
+use crate::space::Action;
+use crate::space::ConditionHeuristic;
+use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::Space;
+use crate::space::State;
+
+/// Combines an objective and a condition heuristic.
+///
+/// Reaching either kind of goal is enough, so in general only the minimum of
+/// both heuristics is admissible. When the objectives are exactly the states
+/// satisfying the condition both bound the same distance and the maximum can
+/// be used instead.
+#[derive(Copy, Clone, Debug)]
+pub struct MixedHeuristic<OH, CH> {
+    /// Whether the objectives match the goal condition.
+    matching: bool,
+
+    _phantom_objective: PhantomData<OH>,
+    _phantom_condition: PhantomData<CH>,
+}
+
+impl<OH, CH> MixedHeuristic<OH, CH> {
+    /// Takes the maximum of both heuristics if the objectives match the goal
+    /// condition, and the minimum otherwise.
+    #[must_use]
+    pub fn new(matching: bool) -> Self {
+        Self {
+            matching,
+            _phantom_objective: PhantomData,
+            _phantom_condition: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn matching(&self) -> bool {
+        self.matching
+    }
+
+    /// Estimates the cost from a state to the nearest goal.
+    ///
+    /// Without objectives only the condition heuristic is left.
+    #[must_use]
+    pub fn h<Sp, St, A, C>(&self, space: &Sp, s: &St, objectives: &[St]) -> C
+    where
+        OH: ObjectiveHeuristic<Sp, St, A, C>,
+        CH: ConditionHeuristic<Sp, St, A, C>,
+        Sp: Space<St, A, C>,
+        St: State,
+        A: Action,
+        C: OrdCost,
+    {
+        let condition_h = CH::h(s);
+        let Some(objective_h) = objectives.iter().map(|goal| OH::h_in(space, s, goal)).min() else {
+            return condition_h;
+        };
+        if self.matching {
+            objective_h.max(condition_h)
+        } else {
+            objective_h.min(condition_h)
+        }
+    }
+}
+
+/// Ranks the nodes of a `MixedAStarSearch` by its `MixedHeuristic`.
+struct MixedNodeHeuristic<'a, OH, CH, Sp, St, A> {
+    heuristic: &'a MixedHeuristic<OH, CH>,
+    objectives: &'a [St],
+
+    _phantom_space: PhantomData<Sp>,
+    _phantom_action: PhantomData<A>,
+}
+
+impl<OH, CH, Sp, St, A, C> NodeHeuristic<Sp, St, C> for MixedNodeHeuristic<'_, OH, CH, Sp, St, A>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    CH: ConditionHeuristic<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn h(&mut self, space: &Sp, s: &St, _node_index: SearchTreeIndex) -> C {
+        self.heuristic.h::<Sp, St, A, C>(space, s, self.objectives)
+    }
+
+    #[cfg(feature = "verify")]
+    fn raw_h(&self, space: &Sp, s: &St) -> C {
+        self.heuristic.h::<Sp, St, A, C>(space, s, self.objectives)
+    }
+}
+
+/// A* search implementation for Mixed Problems.
+///
+/// This initializes the search and offers an Iterator that goes around
+/// different solutions, yielding goals of either kind in the order they are
+/// reached.
+#[derive(Debug)]
+pub struct MixedAStarSearch<OH, CH, MP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    CH: ConditionHeuristic<Sp, St, A, C>,
+    MP: MixedProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// The Search Tree, open list and node map.
+    nodes: AStarNodes<St, A, C, AStarRank<C>>,
+
+    /// The objectives, for goal-checks.
+    objectives: FxHashSet<St>,
+
+    heuristic: MixedHeuristic<OH, CH>,
+
+    problem: MP,
+
+    _phantom_space: PhantomData<Sp>,
+    _phantom_action: PhantomData<A>,
+}
+
+impl<OH, CH, MP, Sp, St, A, C> MixedAStarSearch<OH, CH, MP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    CH: ConditionHeuristic<Sp, St, A, C>,
+    MP: MixedProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// Initializes the Search
+    #[must_use]
+    pub fn new(mp: MP, heuristic: MixedHeuristic<OH, CH>) -> Self {
+        let mut search = Self {
+            nodes: AStarNodes::with_capacity(DEFAULT_OPEN_CAPACITY, DEFAULT_NODE_MAP_CAPACITY),
+            objectives: mp.goals().iter().cloned().collect(),
+
+            heuristic,
+
+            problem: mp,
+
+            _phantom_space: PhantomData,
+            _phantom_action: PhantomData,
+        };
+
+        let mut heuristic = MixedNodeHeuristic {
+            heuristic: &search.heuristic,
+            objectives: search.problem.goals(),
+            _phantom_space: PhantomData,
+            _phantom_action: PhantomData,
+        };
+        for s in search.problem.starts() {
+            search
+                .nodes
+                .push_new(search.problem.space(), s, None, C::zero(), &mut heuristic);
+        }
+
+        search
+    }
+
+    /// Runs the search until the next goal is found.
+    #[must_use]
+    pub fn find_next_goal(&mut self) -> Option<Path<St, A, C>> {
+        #[cfg(feature = "coz_profile")]
+        coz::scope!("FindNextGoal");
+
+        let mut heuristic = MixedNodeHeuristic {
+            heuristic: &self.heuristic,
+            objectives: self.problem.goals(),
+            _phantom_space: PhantomData,
+            _phantom_action: PhantomData,
+        };
+
+        self.nodes.start_call();
+        while let Some(node_index) = self.nodes.next_node() {
+            let state = self
+                .nodes
+                .expand(self.problem.space(), node_index, &mut heuristic);
+
+            // NOTE: The goal is closed already, so the next call won't find it
+            // again.
+            if unlikely(self.objectives.contains(&state) || self.problem.is_goal(&state)) {
+                #[cfg(feature = "coz_profile")]
+                coz::progress!("GoalFound");
+                return Some(self.nodes.path(self.problem.space(), node_index));
+            }
+        }
+
+        None
+    }
+
+    /// Caps the number of expansions of each `find_next_goal` call.
+    ///
+    /// Calls running out of budget return `None`, and the next call resumes
+    /// the search.
+    pub fn set_expansion_budget(&mut self, max_expansions: usize) {
+        self.nodes.max_expansions = Some(max_expansions);
+    }
+
+    /// Whether the last `find_next_goal` call ran out of expansions.
+    #[inline(always)]
+    #[must_use]
+    pub fn budget_exhausted(&self) -> bool {
+        self.nodes.budget_exhausted
+    }
+
+    /// Makes `find_next_goal` calls give up after a deadline.
+    ///
+    /// Calls running out of time return `None`, and the next call resumes the
+    /// search.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.nodes.deadline = Some(deadline);
+    }
+
+    /// Whether the last `find_next_goal` call ran out of time.
+    #[inline(always)]
+    #[must_use]
+    pub fn timed_out(&self) -> bool {
+        self.nodes.timed_out
+    }
+
+    /// The number of nodes expanded so far.
+    #[inline(always)]
+    #[must_use]
+    pub fn expanded_count(&self) -> usize {
+        self.nodes.expanded
+    }
+
+    /// The number of nodes generated so far.
+    ///
+    /// Nodes reached again through a better path count again.
+    #[inline(always)]
+    #[must_use]
+    pub fn generated_count(&self) -> usize {
+        self.nodes.generated
+    }
+
+    /// Reconstructs the optimal path to an expanded state.
+    ///
+    /// Returns `None` if the state wasn't reached or is still open.
+    #[must_use]
+    pub fn path_to(&self, s: &St) -> Option<Path<St, A, C>> {
+        self.nodes.path_to(self.problem.space(), s)
+    }
+}
+
+impl<OH, CH, MP, Sp, St, A, C> Iterator for MixedAStarSearch<OH, CH, MP, Sp, St, A, C>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    CH: ConditionHeuristic<Sp, St, A, C>,
+    MP: MixedProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    type Item = Path<St, A, C>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.find_next_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::problem::BaseProblem;
+    use crate::problems::maze_2d::Maze2DAction;
+    use crate::problems::maze_2d::Maze2DCell;
+    use crate::problems::maze_2d::Maze2DCost;
+    use crate::problems::maze_2d::Maze2DEscapeProblem;
+    use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+    use crate::problems::maze_2d::Maze2DHeuristicRegionDistance;
+    use crate::problems::maze_2d::Maze2DProblem;
+    use crate::problems::maze_2d::Maze2DSpace;
+    use crate::problems::maze_2d::Maze2DState;
+    use crate::space::NoHeuristic;
+
+    fn goal_or_escape_problem() -> Maze2DEscapeProblem {
+        Maze2DEscapeProblem(
+            Maze2DProblem::try_from(indoc! {"
+                #########
+                #.......#
+                #.S...G.#
+                #.......#
+                #...G....
+                #########
+            "})
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn goal_or_escape() {
+        let problem = goal_or_escape_problem();
+        let space = problem.space().clone();
+        let heuristic = MixedHeuristic::<Maze2DHeuristicDiagonalDistance, NoHeuristic>::new(false);
+        let search = MixedAStarSearch::new(problem, heuristic);
+
+        let paths: Vec<_> = search.collect();
+        assert!(paths.iter().all(|p| space.valid_path(p)));
+        assert_eq!(
+            paths.iter().map(|p| p.end().unwrap()).collect::<Vec<_>>(),
+            [(4, 4), (6, 2), (8, 4)].map(|(x, y)| Maze2DState::new_from_usize(x, y).unwrap())
+        );
+        assert_eq!(
+            paths.iter().map(|p| p.cost()).collect::<Vec<_>>(),
+            vec![2 * 141, 4 * 100, 2 * 141 + 4 * 100]
+        );
+    }
+
+    #[test]
+    fn expansion_budget() {
+        let heuristic =
+            || MixedHeuristic::<Maze2DHeuristicDiagonalDistance, NoHeuristic>::new(false);
+        let expected: Vec<_> =
+            MixedAStarSearch::new(goal_or_escape_problem(), heuristic()).collect();
+
+        let mut search = MixedAStarSearch::new(goal_or_escape_problem(), heuristic());
+        search.set_expansion_budget(1);
+        let mut paths = vec![];
+        let mut calls = 0;
+        while paths.len() < expected.len() {
+            calls += 1;
+            match search.find_next_goal() {
+                Some(path) => paths.push(path),
+                None => assert!(search.budget_exhausted()),
+            }
+        }
+        assert_eq!(paths, expected);
+        assert_eq!(search.expanded_count(), calls);
+        assert!(search.generated_count() >= search.expanded_count());
+        assert!(!search.timed_out());
+    }
+
+    #[test]
+    fn mixed_heuristic() {
+        let s = Maze2DState::new_from_usize(0, 0).unwrap();
+        let goals = [
+            Maze2DState::new_from_usize(3, 0).unwrap(),
+            Maze2DState::new_from_usize(1, 1).unwrap(),
+        ];
+
+        let space = Maze2DSpace::new_from_map(vec![vec![Maze2DCell::Empty; 4]; 2]);
+        let min = MixedHeuristic::<Maze2DHeuristicDiagonalDistance, NoHeuristic>::new(false);
+        let max = MixedHeuristic::<Maze2DHeuristicDiagonalDistance, NoHeuristic>::new(true);
+        assert_eq!(
+            min.h::<Maze2DSpace, _, Maze2DAction, Maze2DCost>(&space, &s, &goals),
+            0
+        );
+        assert_eq!(
+            max.h::<Maze2DSpace, _, Maze2DAction, Maze2DCost>(&space, &s, &goals),
+            141
+        );
+        assert_eq!(
+            min.h::<Maze2DSpace, _, Maze2DAction, Maze2DCost>(&space, &s, &[]),
+            0
+        );
+        assert_eq!(
+            max.h::<Maze2DSpace, _, Maze2DAction, Maze2DCost>(&space, &s, &[]),
+            0
+        );
+
+        // Without objectives, the condition heuristic is used as is.
+        type ToCorner = Maze2DHeuristicRegionDistance<3, 1, 3, 1>;
+        for matching in [false, true] {
+            let heuristic =
+                MixedHeuristic::<Maze2DHeuristicDiagonalDistance, ToCorner>::new(matching);
+            assert_eq!(
+                heuristic.h::<Maze2DSpace, _, Maze2DAction, Maze2DCost>(&space, &s, &[]),
+                141 + 2 * 100
+            );
+        }
+    }
+
+    #[test]
+    fn escape_without_objectives() {
+        let problem = Maze2DEscapeProblem(
+            Maze2DProblem::try_from(indoc! {"
+                #######
+                #.....#
+                #.#.#.#
+                #.#S#.#
+                #.###..
+                #######
+            "})
+            .unwrap(),
+        );
+        let space = problem.space().clone();
+        let heuristic = MixedHeuristic::<Maze2DHeuristicDiagonalDistance, NoHeuristic>::new(true);
+        let mut search = MixedAStarSearch::new(problem, heuristic);
+
+        // Out through the only gap on the border.
+        let path = search.find_next_goal().unwrap();
+        assert!(space.valid_path(&path));
+        assert_eq!(path.end(), Maze2DState::new_from_usize(6, 4));
+        assert_eq!(path.cost(), 2 * 100 + 3 * 141);
+    }
+}
@@ -2,5 +2,17 @@
 //!
 //! These algorithms can do path-finding on generic search problems.
 
+pub mod alt;
+pub mod ara;
 pub mod astar;
+pub mod beam;
+pub mod bidirectional;
+pub mod bucket_dijkstra;
+pub mod condition_astar;
 pub mod dijkstra;
+pub mod focal;
+pub mod greedy;
+pub mod ida;
+pub mod mixed_astar;
+pub mod waypoints;
+pub mod yen;
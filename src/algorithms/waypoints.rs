@@ -0,0 +1,197 @@
+//! Ordered multi-goal search through waypoints.
+//!
+//! `ObjectiveProblem` goals are an unordered set, so visiting waypoints in
+//! order is solved leg-by-leg, searching from the end of the previous leg to
+//! the next waypoint, and joining the legs.
+
+use std::marker::PhantomData;
+
+use crate::algorithms::astar::AStarSearch;
+use crate::problem::BaseProblem;
+use crate::problem::ObjectiveProblem;
+use crate::space::Action;
+use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::Space;
+use crate::space::State;
+
+/// A single leg of a waypoint route, reaching one waypoint from the starts.
+///
+/// It borrows the Space, so legs don't copy it.
+#[derive(Debug)]
+pub struct WaypointProblem<'a, Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    space: &'a Sp,
+    starts: Vec<St>,
+    /// The waypoint, as a single goal.
+    goals: [St; 1],
+
+    _phantom_action: PhantomData<A>,
+    _phantom_cost: PhantomData<C>,
+}
+
+impl<'a, Sp, St, A, C> WaypointProblem<'a, Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[must_use]
+    pub fn new(space: &'a Sp, starts: Vec<St>, waypoint: St) -> Self {
+        Self {
+            space,
+            starts,
+            goals: [waypoint],
+            _phantom_action: PhantomData,
+            _phantom_cost: PhantomData,
+        }
+    }
+}
+
+impl<Sp, St, A, C> BaseProblem<Sp, St, A, C> for WaypointProblem<'_, Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    fn space(&self) -> &Sp {
+        self.space
+    }
+    fn starts(&self) -> &[St] {
+        &self.starts
+    }
+}
+
+impl<Sp, St, A, C> ObjectiveProblem<Sp, St, A, C> for WaypointProblem<'_, Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    fn goals(&self) -> &[St] {
+        &self.goals
+    }
+
+    /// Legs are defined by their route, so they can't be randomized.
+    fn randomize<R: rand::Rng>(
+        &mut self,
+        _r: &mut R,
+        _num_starts: u16,
+        _num_goals: u16,
+    ) -> Option<Self> {
+        None
+    }
+}
+
+/// Finds a path from the starts visiting the waypoints in order.
+///
+/// Each leg runs a fresh A* from the end of the previous one, so the route is
+/// optimal leg-by-leg. Returns `None` without waypoints or when a waypoint
+/// can't be reached.
+#[must_use]
+pub fn solve_waypoints<OH, Sp, St, A, C>(
+    space: &Sp,
+    starts: &[St],
+    waypoints: &[St],
+) -> Option<Path<St, A, C>>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    let (first, rest) = waypoints.split_first()?;
+
+    let leg = WaypointProblem::new(space, starts.to_vec(), *first);
+    let mut path = AStarSearch::<OH, _, _, _, _, _>::new(leg).find_next_goal()?;
+    for waypoint in rest {
+        let leg = WaypointProblem::new(space, vec![path.end()?], *waypoint);
+        let next = AStarSearch::<OH, _, _, _, _, _>::new(leg).find_next_goal()?;
+        path.concat(&next).ok()?;
+    }
+
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+    use crate::problems::maze_2d::Maze2DProblem;
+    use crate::problems::maze_2d::Maze2DState;
+
+    #[test]
+    fn waypoints() {
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S......
+            .#####.
+            .#...#.
+            .#.#.#.
+            ...#..G
+        "})
+        .unwrap();
+        let space = problem.space().clone();
+        let starts = problem.starts().to_vec();
+        let goal = problem.goals()[0];
+
+        let direct = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem)
+            .find_next_goal()
+            .unwrap();
+
+        let waypoints = [
+            Maze2DState::new(2, 2).unwrap(),
+            Maze2DState::new(4, 3).unwrap(),
+            goal,
+        ];
+        let path = solve_waypoints::<Maze2DHeuristicDiagonalDistance, _, _, _, _>(
+            &space, &starts, &waypoints,
+        )
+        .unwrap();
+
+        assert!(space.valid_path(&path));
+        assert!(path.cost() >= direct.cost());
+        assert_eq!(path.end(), Some(goal));
+
+        // Waypoints are visited in order.
+        let states = path.into_state_vec(&space).unwrap();
+        let visits: Vec<usize> = waypoints
+            .iter()
+            .map(|w| states.iter().position(|s| s == w).unwrap())
+            .collect();
+        assert!(visits.is_sorted());
+    }
+
+    #[test]
+    fn unreachable_waypoint() {
+        let problem = Maze2DProblem::try_from("S#.G\n").unwrap();
+        let waypoint = Maze2DState::new(2, 0).unwrap();
+
+        let path = solve_waypoints::<Maze2DHeuristicDiagonalDistance, _, _, _, _>(
+            problem.space(),
+            problem.starts(),
+            &[waypoint],
+        );
+        assert_eq!(path, None);
+        assert_eq!(
+            solve_waypoints::<Maze2DHeuristicDiagonalDistance, _, _, _, _>(
+                problem.space(),
+                problem.starts(),
+                &[],
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! K shortest loopless paths (Yen's algorithm).
+//!
+//! Searches stop at the first path to each goal, so alternative routes to the
+//! same goal are found by deviating from the known paths. Each deviation, or
+//! spur, is a shortest path query on a Space masking the transitions that
+//! would repeat a known path or loop back into its root.
+
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use rustc_hash::FxHashSet;
+
+use crate::algorithms::astar::AStarSearch;
+use crate::problem::FloodProblem;
+use crate::problem::ObjectiveProblem;
+use crate::space::Action;
+use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::Space;
+use crate::space::State;
+
+/// Decides whether a transition `(s, a) -> t` can be taken.
+type TransitionFilter<'a, St, A> = dyn Fn(&St, &A, &St) -> bool + 'a;
+
+/// A Space only allowing the transitions `(s, a) -> t` accepted by a filter.
+///
+/// It borrows the underlying Space, so masking it doesn't copy it.
+#[derive(Clone)]
+pub struct MaskedSpace<'a, Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    space: &'a Sp,
+    allowed: Rc<TransitionFilter<'a, St, A>>,
+
+    _phantom_cost: PhantomData<C>,
+}
+
+impl<'a, Sp, St, A, C> MaskedSpace<'a, Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[must_use]
+    pub fn new(space: &'a Sp, allowed: impl Fn(&St, &A, &St) -> bool + 'a) -> Self {
+        Self {
+            space,
+            allowed: Rc::new(allowed),
+            _phantom_cost: PhantomData,
+        }
+    }
+}
+
+impl<Sp, St, A, C> std::fmt::Debug for MaskedSpace<'_, Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MaskedSpace")
+            .field("space", &self.space)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Sp, St, A, C> Space<St, A, C> for MaskedSpace<'_, Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn apply(&self, s: &St, a: &A) -> Option<St> {
+        let t = self.space.apply(s, a)?;
+        (self.allowed)(s, a, &t).then_some(t)
+    }
+
+    #[inline(always)]
+    fn cost(&self, s: &St, a: &A) -> C {
+        self.space.cost(s, a)
+    }
+
+    fn neighbours(&self, s: &St) -> Vec<(St, A)> {
+        let mut neighbours = self.space.neighbours(s);
+        neighbours.retain(|(t, a)| (self.allowed)(s, a, t));
+        neighbours
+    }
+
+    #[inline(always)]
+    fn valid(&self, s: &St) -> bool {
+        self.space.valid(s)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> Option<usize> {
+        self.space.size()
+    }
+}
+
+/// An heuristic on a Space, used on a `MaskedSpace` over it.
+///
+/// Masking transitions only makes distances longer, so heuristics stay
+/// admissible.
+#[derive(Debug)]
+pub struct MaskedHeuristic<OH>(PhantomData<OH>);
+
+impl<OH, Sp, St, A, C> ObjectiveHeuristic<MaskedSpace<'_, Sp, St, A, C>, St, A, C>
+    for MaskedHeuristic<OH>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn h(a: &St, b: &St) -> C {
+        <OH as ObjectiveHeuristic<Sp, St, A, C>>::h(a, b)
+    }
+
+    #[inline(always)]
+    fn h_in(space: &MaskedSpace<'_, Sp, St, A, C>, a: &St, b: &St) -> C {
+        <OH as ObjectiveHeuristic<Sp, St, A, C>>::h_in(space.space, a, b)
+    }
+}
+
+/// Finds a shortest path only taking the transitions `(s, a) -> t` accepted
+/// by `allowed`.
+#[must_use]
+pub fn shortest_path_with<OH, Sp, St, A, C>(
+    space: &Sp,
+    start: St,
+    goal: St,
+    allowed: impl Fn(&St, &A, &St) -> bool,
+) -> Option<Path<St, A, C>>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    let space = MaskedSpace::new(space, allowed);
+    let problem = FloodProblem::new(space, start).with_goal(goal);
+    AStarSearch::<MaskedHeuristic<OH>, _, _, _, _, _>::new(problem).find_next_goal()
+}
+
+/// Finds up to `k` loopless paths from the first start to the first goal, in
+/// non-decreasing cost order.
+///
+/// Paths are distinct, but may only differ slightly, like taking two detours
+/// in a different order.
+#[must_use]
+pub fn k_shortest_paths<OH, OP, Sp, St, A, C>(problem: &OP, k: usize) -> Vec<Path<St, A, C>>
+where
+    OH: ObjectiveHeuristic<Sp, St, A, C>,
+    OP: ObjectiveProblem<Sp, St, A, C>,
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    let space = problem.space();
+    let mut paths: Vec<Path<St, A, C>> = vec![];
+    let (Some(start), Some(goal)) = (problem.starts().first(), problem.goals().first()) else {
+        return paths;
+    };
+    if k == 0 {
+        return paths;
+    }
+    let Some(first) = shortest_path_with::<OH, _, _, _, _>(space, *start, *goal, |_s, _a, _t| true)
+    else {
+        return paths;
+    };
+    paths.push(first);
+
+    let mut candidates: Vec<Path<St, A, C>> = vec![];
+    while paths.len() < k {
+        let last = &paths[paths.len() - 1];
+        let Some(states) = last.into_state_vec(space) else {
+            break;
+        };
+        let actions = last.actions();
+
+        for (i, spur) in states.iter().enumerate().take(actions.len()) {
+            let root = &actions[..i];
+            // The root can't be re-visited, and known paths sharing it can't
+            // be followed again from the spur.
+            let root_states: FxHashSet<St> = states[..i].iter().copied().collect();
+            let taken: Vec<A> = paths
+                .iter()
+                .filter(|p| p.actions().len() > i && p.actions()[..i] == *root)
+                .map(|p| p.actions()[i])
+                .collect();
+
+            let Some(spur_path) =
+                shortest_path_with::<OH, _, _, _, _>(space, *spur, *goal, |s, a, t| {
+                    !root_states.contains(t) && !(s == spur && taken.contains(a))
+                })
+            else {
+                continue;
+            };
+
+            let mut path = Path::with_capacity(*start, i + spur_path.len());
+            for (j, a) in root.iter().enumerate() {
+                path.append((states[j + 1], *a), space.cost(&states[j], a));
+            }
+            path.concat(&spur_path)
+                .expect("Spur paths start where their root ends");
+            if !paths.contains(&path) && !candidates.contains(&path) {
+                candidates.push(path);
+            }
+        }
+
+        let Some((best, _path)) = candidates.iter().enumerate().min_by_key(|(_i, p)| p.cost())
+        else {
+            break;
+        };
+        paths.push(candidates.swap_remove(best));
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::problem::BaseProblem;
+    use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+    use crate::problems::maze_2d::Maze2DProblem;
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn two_paths() {
+        use std::path::PathBuf;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let paths = k_shortest_paths::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>(&problem, 2);
+
+        assert_eq!(paths.len(), 2);
+        assert_ne!(paths[0], paths[1]);
+        assert!(paths[0].cost() <= paths[1].cost());
+        for path in &paths {
+            assert!(problem.space().valid_path(path));
+            assert_eq!(path.start(), Some(problem.starts()[0]));
+            assert_eq!(path.end(), Some(problem.goals()[0]));
+        }
+    }
+
+    #[test]
+    fn loopless() {
+        // Only two routes around the wall, plus detours on each of them.
+        let problem = Maze2DProblem::try_from(indoc! {"
+            .....
+            S.#.G
+            .....
+        "})
+        .unwrap();
+        let paths = k_shortest_paths::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>(&problem, 5);
+
+        assert_eq!(paths.len(), 5);
+        assert!(paths.is_sorted_by_key(|p| p.cost()));
+        for (i, path) in paths.iter().enumerate() {
+            assert!(!paths[..i].contains(path));
+            let states = path.into_state_vec(problem.space()).unwrap();
+            let unique: FxHashSet<_> = states.iter().collect();
+            assert_eq!(unique.len(), states.len());
+        }
+    }
+
+    #[test]
+    fn masked() {
+        let problem = Maze2DProblem::try_from("S...G\n").unwrap();
+        let space = problem.space();
+        let (start, goal) = (problem.starts()[0], problem.goals()[0]);
+
+        let path = shortest_path_with::<Maze2DHeuristicDiagonalDistance, _, _, _, _>(
+            space,
+            start,
+            goal,
+            |_s, _a, _t| true,
+        );
+        assert!(path.is_some());
+
+        let path = shortest_path_with::<Maze2DHeuristicDiagonalDistance, _, _, _, _>(
+            space,
+            start,
+            goal,
+            |_s, _a, t| t.x.get() != 2,
+        );
+        assert_eq!(path, None);
+        assert_eq!(
+            k_shortest_paths::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>(&problem, 3).len(),
+            1
+        );
+        assert!(
+            k_shortest_paths::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>(&problem, 0)
+                .is_empty()
+        );
+    }
+}
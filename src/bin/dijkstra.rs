@@ -15,11 +15,14 @@ use search::problem::BaseProblem;
 use search::problem::ObjectiveProblem;
 use search::problems::maze_2d::Maze2DProblem;
 use search::problems::maze_2d::Maze2DSpace;
+use search::search::DEFAULT_OPEN_CAPACITY;
+use search::search::SearchOutcome;
+use search::search::node_capacity_hint;
 
 #[cfg(feature = "mem_profile")]
 #[global_allocator]
 static ALLOC: dhat::Alloc = dhat::Alloc;
-#[cfg(not(feature = "mem_profile"))]
+#[cfg(all(not(feature = "mem_profile"), not(target_arch = "wasm32")))]
 #[global_allocator]
 static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
@@ -66,7 +69,13 @@ fn main() -> std::io::Result<()> {
 
     writeln!(out, "* Runs")?;
     for p in &args.problems {
-        let space = Maze2DSpace::try_from(p.as_path()).unwrap();
+        let space = match Maze2DSpace::try_from(p.as_path()) {
+            Ok(space) => space,
+            Err(e) => {
+                writeln!(out, "** Space {p:?}\nFailed to load: {e}")?;
+                continue;
+            }
+        };
         writeln!(out, "** Space {:?} ({:?})", p, space.dimensions())?;
         writeln!(out, "*** Map")?;
         writeln!(out, "#+begin_quote\n{space}\n#+end_quote")?;
@@ -94,7 +103,12 @@ fn main() -> std::io::Result<()> {
                     writeln!(out, "  - {goal:?}")?;
                 }
                 writeln!(out, "***** Solution")?;
-                let mut search = DijkstraSearch::new(random_problem);
+                let map_capacity = node_capacity_hint(random_problem.space());
+                let mut search = DijkstraSearch::with_capacity(
+                    random_problem,
+                    DEFAULT_OPEN_CAPACITY,
+                    map_capacity,
+                );
                 writeln!(
                     out,
                     "****** Dijkstra run\n#+begin_src ron\n{search:?}\n#+end_src"
@@ -102,16 +116,21 @@ fn main() -> std::io::Result<()> {
 
                 let mut stopwatch = Stopwatch::new_started();
                 for i in 0..args.num_solutions {
-                    if let Some(path) = search.find_next_goal() {
-                        let elapsed = stopwatch.elapsed();
-                        debug_assert!(starts.contains(&path.start.unwrap()));
-                        debug_assert!(goals.contains(&path.end.unwrap()));
-                        writeln!(out, "******* Path {i} {path}",)?;
-                        writeln!(out, "Length: {}", path.len())?;
-                        writeln!(out, "Elapsed time: {}", human_duration(&elapsed))?;
-                        search.write_memory_stats(&mut out)?;
-                    } else {
-                        break;
+                    match search.solve_reporting() {
+                        SearchOutcome::Found(path) => {
+                            let elapsed = stopwatch.elapsed();
+                            debug_assert!(starts.contains(&path.start().unwrap()));
+                            debug_assert!(goals.contains(&path.end().unwrap()));
+                            writeln!(out, "******* Path {i} {path}",)?;
+                            writeln!(out, "Length: {}", path.len())?;
+                            writeln!(out, "Elapsed time: {}", human_duration(&elapsed))?;
+                            search.write_memory_stats(&mut out)?;
+                        }
+                        SearchOutcome::NoGoals => break,
+                        outcome => {
+                            writeln!(out, "******* Path {i} {outcome:?}")?;
+                            break;
+                        }
                     }
                 }
                 stopwatch.stop();
@@ -122,9 +141,8 @@ fn main() -> std::io::Result<()> {
             } else {
                 writeln!(
                     out,
-                    "FIXME Failed to generate random problem with seed
-            {} with {} starts and {} goals",
-                    instance, args.instance_starts, args.instance_goals,
+                    "Failed to generate random problem with seed {instance} with {} starts and {} goals",
+                    args.instance_starts, args.instance_goals,
                 )?;
             }
         }
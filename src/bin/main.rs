@@ -14,11 +14,14 @@ use search::problem::ObjectiveProblem;
 use search::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
 use search::problems::maze_2d::Maze2DProblem;
 use search::problems::maze_2d::Maze2DSpace;
+use search::search::DEFAULT_OPEN_CAPACITY;
+use search::search::SearchOutcome;
+use search::search::node_capacity_hint;
 
 #[cfg(feature = "mem_profile")]
 #[global_allocator]
 static ALLOC: dhat::Alloc = dhat::Alloc;
-#[cfg(not(feature = "mem_profile"))]
+#[cfg(all(not(feature = "mem_profile"), not(target_arch = "wasm32")))]
 #[global_allocator]
 static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
@@ -50,12 +53,24 @@ fn main() -> std::io::Result<()> {
 
     writeln!(out, "* Runs")?;
     for p in &args.problems {
-        let space = Maze2DSpace::try_from(p.as_path()).unwrap();
+        let space = match Maze2DSpace::try_from(p.as_path()) {
+            Ok(space) => space,
+            Err(e) => {
+                writeln!(out, "** Space {p:?}\nFailed to load: {e}")?;
+                continue;
+            }
+        };
         writeln!(out, "** Space {:?} ({:?})", p, space.dimensions())?;
         writeln!(out, "*** Map")?;
         writeln!(out, "#+begin_quote\n{space}\n#+end_quote")?;
         writeln!(out, "*** Problems")?;
-        let mut p = Maze2DProblem::try_from(p.as_path()).unwrap();
+        let mut p = match Maze2DProblem::try_from(p.as_path()) {
+            Ok(p) => p,
+            Err(e) => {
+                writeln!(out, "Failed to load problem: {e}")?;
+                continue;
+            }
+        };
 
         for instance in 0..10 {
             writeln!(out, "**** Problem {instance}")?;
@@ -75,24 +90,36 @@ fn main() -> std::io::Result<()> {
                     writeln!(out, "  - {goal:?}")?;
                 }
                 writeln!(out, "***** Solution")?;
-                let search = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(
-                    random_problem,
-                );
+                let map_capacity = node_capacity_hint(random_problem.space());
+                let mut search =
+                    AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::with_capacity(
+                        random_problem,
+                        DEFAULT_OPEN_CAPACITY,
+                        map_capacity,
+                    );
                 writeln!(out, "****** A* run\n#+begin_src ron\n{search:?}\n#+end_src")?;
 
-                for (i, path) in search.take(3).enumerate() {
-                    writeln!(
-                        out,
-                        "******* Path {i} {path}\n#+begin_src ron\n{path:?}\n#+end_src",
-                    )?;
-                    debug_assert!(starts.contains(&path.start.unwrap()));
-                    debug_assert!(goals.contains(&path.end.unwrap()));
+                for i in 0..3 {
+                    match search.solve_reporting() {
+                        SearchOutcome::Found(path) => {
+                            writeln!(
+                                out,
+                                "******* Path {i} {path}\n#+begin_src ron\n{path:?}\n#+end_src",
+                            )?;
+                            debug_assert!(starts.contains(&path.start().unwrap()));
+                            debug_assert!(goals.contains(&path.end().unwrap()));
+                        }
+                        SearchOutcome::NoGoals => break,
+                        outcome => {
+                            writeln!(out, "******* Path {i} {outcome:?}")?;
+                            break;
+                        }
+                    }
                 }
             } else {
                 writeln!(
                     out,
-                    "FIXME Failed to generate random problem with seed
-            {instance} with {num_starts} starts and {num_goals} goals",
+                    "Failed to generate random problem with seed {instance} with {num_starts} starts and {num_goals} goals",
                 )?;
             }
         }
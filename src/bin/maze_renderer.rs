@@ -161,16 +161,16 @@ fn setup(mut commands: Commands, args: Res<Args>) {
         if path.is_empty() {
             log::info!("Empty path, the problem is trivial");
         } else {
-            log::info!("Path: (cost={})", path.cost);
-            let mut s = path.start.unwrap();
-            log::info!("- {}..{}", s, path.end.unwrap());
+            log::info!("Path: (cost={})", path.cost());
+            let mut s = path.start().unwrap();
+            log::info!("- {}..{}", s, path.end().unwrap());
 
             log::info!("Rendering path...");
             let x = s.x.get() as f32 * spacing - offset;
             let y = (last_y - s.y.get()) as f32 * spacing - offset;
             commands.spawn((path_sprite.clone(), Transform::from_xyz(x, y, ZLEVEL_PATH)));
 
-            for a in &path.actions {
+            for a in path.actions() {
                 if let Some(new_state) = problem.space().apply(&s, a) {
                     s = new_state;
                     log::trace!("- {a} => {s}");
@@ -180,7 +180,7 @@ fn setup(mut commands: Commands, args: Res<Args>) {
                     commands.spawn((path_sprite.clone(), Transform::from_xyz(x, y, ZLEVEL_PATH)));
                 }
             }
-            debug_assert_eq!(s, path.end.unwrap());
+            debug_assert_eq!(s, path.end().unwrap());
         }
     }
 
@@ -208,7 +208,7 @@ fn maze_renderer() {
 #[cfg(feature = "mem_profile")]
 #[global_allocator]
 static ALLOC: dhat::Alloc = dhat::Alloc;
-#[cfg(not(feature = "mem_profile"))]
+#[cfg(all(not(feature = "mem_profile"), not(target_arch = "wasm32")))]
 #[global_allocator]
 static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
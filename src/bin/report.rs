@@ -13,6 +13,7 @@ use owo_colors::OwoColorize;
 use search::algorithms::astar::AStarHeapNode;
 use search::algorithms::astar::AStarRank;
 use search::algorithms::astar::AStarSearch;
+use search::algorithms::astar::Rank;
 use search::algorithms::dijkstra::DijkstraHeapNode;
 use search::algorithms::dijkstra::DijkstraRank;
 use search::algorithms::dijkstra::DijkstraSearch;
@@ -142,7 +143,7 @@ fn write_report<W: std::io::Write>(out: &mut BufWriter<W>) -> std::io::Result<()
     print_size(
         out,
         AStarHeapNode {
-            rank: AStarRank::new(0, 0),
+            rank: AStarRank::<Maze2DCost>::new(0, 0),
             node_index: SearchTreeIndex::fake_new(),
         },
     )?;
@@ -0,0 +1,166 @@
+//! A monotone bucket queue (Dial's queue).
+//!
+//! Items are stored in one bucket per `u32` rank, which gives O(1) pushes and
+//! amortized O(1) pops instead of a heap's O(log n), as long as items are
+//! rarely pushed below the last popped rank.
+//!
+//! Dijkstra satisfies this on non-negative integer costs, as expanding a node
+//! never reaches nodes cheaper than it. Only the ranks between the minimum and
+//! the maximum queued rank hold buckets, so with bounded edge costs (like
+//! `Maze2DSpace`) the queue stays small regardless of the path costs.
+
+use std::collections::VecDeque;
+
+/// A min-queue of items ranked by `u32` meant to pop increasing ranks.
+#[derive(Debug, Clone)]
+pub struct BucketQueue<T> {
+    /// The buckets for the ranks `min..min + buckets.len()`.
+    buckets: VecDeque<Vec<T>>,
+    /// The rank of the first bucket.
+    ///
+    /// It only moves back when pushing items better than every queued item.
+    min: u32,
+    /// The number of queued items.
+    len: usize,
+}
+
+impl<T> BucketQueue<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            buckets: VecDeque::new(),
+            min: 0,
+            len: 0,
+        }
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The rank of the best item.
+    #[must_use]
+    pub fn min_rank(&self) -> Option<u32> {
+        let offset = self.buckets.iter().position(|b| !b.is_empty())?;
+        Some(self.min + offset as u32)
+    }
+
+    /// Queues an item.
+    ///
+    /// Ranks better than the first bucket need new buckets in front of it,
+    /// which is only cheap while they are close to it.
+    pub fn push(&mut self, rank: u32, item: T) {
+        if self.len == 0 {
+            self.min = rank;
+        }
+        if rank < self.min {
+            for _rank in rank..self.min {
+                self.buckets.push_front(Vec::new());
+            }
+            self.min = rank;
+        }
+
+        let offset = (rank - self.min) as usize;
+        if offset >= self.buckets.len() {
+            self.buckets.resize_with(offset + 1, Vec::new);
+        }
+        self.buckets[offset].push(item);
+        self.len += 1;
+    }
+
+    /// Removes an item with the best rank.
+    pub fn pop(&mut self) -> Option<(u32, T)> {
+        if self.len == 0 {
+            return None;
+        }
+
+        loop {
+            if let Some(item) = self.buckets[0].pop() {
+                self.len -= 1;
+                return Some((self.min, item));
+            }
+            // Recycle the empty bucket for the rank after the last one.
+            self.buckets.rotate_left(1);
+            self.min += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        self.len = 0;
+    }
+}
+
+impl<T> Default for BucketQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_sorted() {
+        let mut queue = BucketQueue::new();
+        for rank in [7, 3, 141, 3, 100, 0] {
+            queue.push(rank, rank * 10);
+        }
+        assert_eq!(queue.len(), 6);
+        assert_eq!(queue.min_rank(), Some(0));
+
+        let mut popped = vec![];
+        while let Some((rank, item)) = queue.pop() {
+            assert_eq!(item, rank * 10);
+            popped.push(rank);
+        }
+        assert_eq!(popped, vec![0, 3, 3, 7, 100, 141]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.min_rank(), None);
+    }
+
+    #[test]
+    fn monotone() {
+        let mut queue = BucketQueue::new();
+        queue.push(100, ());
+
+        // Pushing while popping, like Dijkstra does.
+        let mut last = 0;
+        for step in 0..1000 {
+            let (rank, ()) = queue.pop().unwrap();
+            assert!(last <= rank);
+            last = rank;
+            queue.push(rank + 100, ());
+            if step % 3 == 0 {
+                queue.push(rank + 141, ());
+            }
+        }
+
+        // Only the ranks being queued need buckets.
+        assert!(queue.buckets.len() <= 142);
+    }
+
+    #[test]
+    fn restarts_when_empty() {
+        let mut queue = BucketQueue::new();
+        queue.push(1000, 'a');
+        assert_eq!(queue.pop(), Some((1000, 'a')));
+
+        queue.push(10, 'b');
+        queue.push(20, 'c');
+        assert_eq!(queue.pop(), Some((10, 'b')));
+        assert_eq!(queue.pop(), Some((20, 'c')));
+        assert_eq!(queue.pop(), None);
+    }
+}
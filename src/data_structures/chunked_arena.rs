@@ -0,0 +1,122 @@
+//! An arena that keeps its memory when cleared.
+//!
+//! Search Trees address their nodes by pointer, so nodes can't move once
+//! allocated. `typed_arena::Arena` guarantees that, but can't be cleared to be
+//! reused by a new search.
+
+/// Smallest chunk allocated after the initial one.
+const MIN_CHUNK_CAPACITY: usize = 64;
+
+/// An arena of `T`s that never moves its elements.
+///
+/// Elements are kept in chunks that are never pushed past their capacity.
+/// Clearing drops the elements but keeps the chunks, so refilling the arena
+/// doesn't allocate until it outgrows them.
+#[derive(Debug, Default)]
+pub struct ChunkedArena<T> {
+    chunks: Vec<Vec<T>>,
+    /// The chunk new elements go into.
+    current: usize,
+    len: usize,
+}
+
+impl<T> ChunkedArena<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            chunks: Vec::new(),
+            current: 0,
+            len: 0,
+        }
+    }
+
+    /// Initializes an arena fitting `capacity` elements in a single chunk.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            chunks: vec![Vec::with_capacity(capacity)],
+            current: 0,
+            len: 0,
+        }
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of elements that fit without allocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.chunks.iter().map(Vec::capacity).sum()
+    }
+
+    /// Moves a value into the arena.
+    ///
+    /// The value won't move until the arena is cleared or dropped.
+    #[inline(always)]
+    pub fn alloc(&mut self, value: T) -> &mut T {
+        while self.current < self.chunks.len()
+            && self.chunks[self.current].len() == self.chunks[self.current].capacity()
+        {
+            self.current += 1;
+        }
+        if self.current == self.chunks.len() {
+            let capacity = self
+                .chunks
+                .last()
+                .map_or(0, |chunk| 2 * chunk.capacity())
+                .max(MIN_CHUNK_CAPACITY);
+            self.chunks.push(Vec::with_capacity(capacity));
+        }
+
+        // NOTE: Pushing within capacity never reallocates, so elements
+        // already in the chunk stay in place.
+        let chunk = &mut self.chunks[self.current];
+        debug_assert!(chunk.len() < chunk.capacity());
+        chunk.push(value);
+        self.len += 1;
+        chunk.last_mut().unwrap()
+    }
+
+    /// Drops all the elements, keeping the chunks for reuse.
+    pub fn clear(&mut self) {
+        for chunk in &mut self.chunks {
+            chunk.clear();
+        }
+        self.current = 0;
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_and_reused() {
+        let mut arena = ChunkedArena::<usize>::with_capacity(3);
+        let pointers: Vec<*const usize> =
+            (0..100).map(|i| arena.alloc(i) as *const usize).collect();
+        assert_eq!(arena.len(), 100);
+        for (i, p) in pointers.iter().enumerate() {
+            assert_eq!(unsafe { **p }, i);
+        }
+
+        let capacity = arena.capacity();
+        arena.clear();
+        assert!(arena.is_empty());
+        for i in 0..100 {
+            arena.alloc(i);
+        }
+        assert_eq!(arena.len(), 100);
+        assert_eq!(arena.capacity(), capacity);
+    }
+}
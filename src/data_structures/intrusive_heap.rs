@@ -0,0 +1,452 @@
+//! An intrusive d-ary heap.
+//!
+//! Heap nodes are usually small `(rank, index)` pairs referring to larger
+//! nodes stored elsewhere (like `SearchTreeNode`s). Keeping those remote nodes
+//! updated with their position in the heap allows re-ranking them without a
+//! linear search for their heap node.
+//!
+//! Positions are reported through a `HeapIndexTracker`, which is given to
+//! every operation moving nodes around so the heap doesn't need to borrow the
+//! remote storage. Collections implementing `IndexMut` (like `SearchTree`)
+//! make natural trackers.
+
+use std::cmp::min;
+
+use crate::derank::derank;
+use crate::heap_primitives::index_first_children;
+use crate::heap_primitives::index_parent;
+
+/// A position in an `IntrusiveHeap`.
+pub type HeapIndex = usize;
+
+/// Keeps track of where heap nodes are.
+pub trait HeapIndexTracker<N> {
+    /// Records that `node` is now at `heap_index`.
+    fn set_heap_index(&mut self, node: &N, heap_index: HeapIndex);
+}
+
+/// Ignores positions, for heaps that never need to find their nodes again.
+impl<N> HeapIndexTracker<N> for () {
+    #[inline(always)]
+    fn set_heap_index(&mut self, _node: &N, _heap_index: HeapIndex) {}
+}
+
+/// A min-heap with `ARITY` children per node that reports node movements.
+///
+/// `ARITY` must be at least 2. Arities `derank` has no network for are
+/// slower, as they find the best child with a linear scan.
+#[derive(Debug, Clone)]
+pub struct IntrusiveHeap<N, const ARITY: usize = 8>
+where
+    N: Ord,
+{
+    nodes: Vec<N>,
+}
+
+impl<N, const ARITY: usize> IntrusiveHeap<N, ARITY>
+where
+    N: Ord,
+{
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        const { assert!(ARITY >= 2, "ARITY must be at least 2") };
+        Self {
+            nodes: Vec::with_capacity(capacity),
+        }
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// The best node.
+    #[inline(always)]
+    #[must_use]
+    pub fn peek(&self) -> Option<&N> {
+        self.nodes.first()
+    }
+
+    /// The nodes, in heap order.
+    pub fn iter(&self) -> std::slice::Iter<'_, N> {
+        self.nodes.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    /// Adds a node, returning where it ended up.
+    pub fn push<T: HeapIndexTracker<N>>(&mut self, node: N, tracker: &mut T) -> HeapIndex {
+        let heap_index = self.nodes.len();
+        self.nodes.push(node);
+        tracker.set_heap_index(&self.nodes[heap_index], heap_index);
+        let heap_index = self._unsafe_sift_up(heap_index, tracker);
+        self.verify_heap();
+        heap_index
+    }
+
+    /// Removes the best node.
+    pub fn pop<T: HeapIndexTracker<N>>(&mut self, tracker: &mut T) -> Option<N> {
+        match self.nodes.len() {
+            0 | 1 => self.nodes.pop(),
+            _ => {
+                self.verify_heap();
+                let node = self._unsafe_pop_non_trivial_heap(tracker);
+                self.verify_heap();
+                Some(node)
+            }
+        }
+    }
+
+    /// Removes the node at `heap_index`.
+    ///
+    /// The last node takes its place and gets sifted to restore the heap.
+    pub fn remove<T: HeapIndexTracker<N>>(&mut self, heap_index: HeapIndex, tracker: &mut T) -> N {
+        let node = self.nodes.swap_remove(heap_index);
+        if heap_index < self.nodes.len() {
+            tracker.set_heap_index(&self.nodes[heap_index], heap_index);
+            let moved_index = self._unsafe_sift_up(heap_index, tracker);
+            if moved_index == heap_index {
+                self._unsafe_sift_down(heap_index, tracker);
+            }
+        }
+        self.verify_heap();
+        node
+    }
+
+    /// Restores the heap after improving the node at `heap_index` through
+    /// `IndexMut`.
+    ///
+    /// Returns the new index of the node.
+    pub fn decrease_key<T: HeapIndexTracker<N>>(
+        &mut self,
+        heap_index: HeapIndex,
+        tracker: &mut T,
+    ) -> HeapIndex {
+        let heap_index = self._unsafe_sift_up(heap_index, tracker);
+        self.verify_heap();
+        heap_index
+    }
+
+    /// Restores the heap after worsening the node at `heap_index` through
+    /// `IndexMut`.
+    ///
+    /// Returns the new index of the node.
+    pub fn increase_key<T: HeapIndexTracker<N>>(
+        &mut self,
+        heap_index: HeapIndex,
+        tracker: &mut T,
+    ) -> HeapIndex {
+        let heap_index = self._unsafe_sift_down(heap_index, tracker);
+        self.verify_heap();
+        heap_index
+    }
+
+    #[inline(always)]
+    #[cfg(not(feature = "verify"))]
+    pub(crate) fn verify_heap(&self) {
+        // All good... (hopefully)
+    }
+    #[inline(always)]
+    #[cfg(feature = "verify")]
+    pub(crate) fn verify_heap(&self) {
+        for i in 1..self.nodes.len() {
+            let p = index_parent::<ARITY>(i);
+            debug_assert!(
+                self.nodes[p] <= self.nodes[i],
+                "Node[{p}] !<= child[{i}]. Out of heap of len={}",
+                self.nodes.len(),
+            );
+        }
+    }
+
+    /// Finds the best node among the children starting at `first`.
+    #[inline(always)]
+    #[must_use]
+    fn best_child(&self, first: HeapIndex) -> HeapIndex {
+        let children = &self.nodes[first..min(first + ARITY, self.nodes.len())];
+        first + derank(children)
+    }
+
+    /// Pops the top node from a Heap with at least 2 elements.
+    ///
+    /// Works by unfairly sifting down the top-node to the last level, where it
+    /// can be swapped with the very last element of the array and popped.
+    /// Temporarily breaks invariants around the node sifting down unfairly.
+    fn _unsafe_pop_non_trivial_heap<T: HeapIndexTracker<N>>(&mut self, tracker: &mut T) -> N {
+        debug_assert!(self.nodes.len() >= 2);
+
+        let len = self.nodes.len();
+        let last = len - 1;
+
+        let mut hole = 0;
+        let mut child = index_first_children::<ARITY>(hole);
+        while child < len {
+            child = self.best_child(child);
+            self.nodes.swap(hole, child);
+            tracker.set_heap_index(&self.nodes[hole], hole);
+
+            hole = child;
+            child = index_first_children::<ARITY>(hole);
+        }
+        // NOTE: The hole made it to the last level, but it may not be at the
+        // end of the array.
+        if hole != last {
+            self.nodes.swap(hole, last);
+            tracker.set_heap_index(&self.nodes[hole], hole);
+            self._unsafe_sift_up(hole, tracker);
+        }
+
+        self.nodes.pop().unwrap()
+    }
+
+    /// Raises a node.
+    /// Returns its new index.
+    #[inline(always)]
+    fn _unsafe_sift_up<T: HeapIndexTracker<N>>(
+        &mut self,
+        mut index: HeapIndex,
+        tracker: &mut T,
+    ) -> HeapIndex {
+        debug_assert!(index < self.nodes.len());
+
+        while index > 0 {
+            let parent = index_parent::<ARITY>(index);
+            if self.nodes[parent] <= self.nodes[index] {
+                break;
+            }
+            self._unsafe_swap(parent, index, tracker);
+            index = parent;
+        }
+        index
+    }
+
+    /// Lowers a node.
+    /// Returns its new index.
+    #[inline(always)]
+    fn _unsafe_sift_down<T: HeapIndexTracker<N>>(
+        &mut self,
+        mut index: HeapIndex,
+        tracker: &mut T,
+    ) -> HeapIndex {
+        debug_assert!(index < self.nodes.len());
+
+        loop {
+            let first = index_first_children::<ARITY>(index);
+            if first >= self.nodes.len() {
+                return index;
+            }
+            let child = self.best_child(first);
+            if self.nodes[index] <= self.nodes[child] {
+                return index;
+            }
+            self._unsafe_swap(index, child, tracker);
+            index = child;
+        }
+    }
+
+    /// Swaps two elements in the heap.
+    ///
+    /// For consistency in calling code `l < r` is checked.
+    ///
+    /// Keeps the intrusive indices in sync.
+    #[inline(always)]
+    fn _unsafe_swap<T: HeapIndexTracker<N>>(
+        &mut self,
+        l: HeapIndex,
+        r: HeapIndex,
+        tracker: &mut T,
+    ) {
+        debug_assert!(l < r, "Swap({l}, {r}) uses wrong argument order");
+        self.nodes.swap(l, r);
+        tracker.set_heap_index(&self.nodes[l], l);
+        tracker.set_heap_index(&self.nodes[r], r);
+    }
+}
+
+impl<N, const ARITY: usize> Default for IntrusiveHeap<N, ARITY>
+where
+    N: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, const ARITY: usize> std::ops::Index<HeapIndex> for IntrusiveHeap<N, ARITY>
+where
+    N: Ord,
+{
+    type Output = N;
+
+    #[inline(always)]
+    fn index(&self, index: HeapIndex) -> &N {
+        &self.nodes[index]
+    }
+}
+
+/// Changing the rank of a node breaks the heap until it's restored.
+impl<N, const ARITY: usize> std::ops::IndexMut<HeapIndex> for IntrusiveHeap<N, ARITY>
+where
+    N: Ord,
+{
+    #[inline(always)]
+    fn index_mut(&mut self, index: HeapIndex) -> &mut N {
+        &mut self.nodes[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// A `(rank, id)` heap node.
+    type Node = (u32, usize);
+
+    /// Tracks the heap index of every node id.
+    impl HeapIndexTracker<Node> for Vec<HeapIndex> {
+        fn set_heap_index(&mut self, node: &Node, heap_index: HeapIndex) {
+            self[node.1] = heap_index;
+        }
+    }
+
+    fn verify_tracker<const ARITY: usize>(
+        heap: &IntrusiveHeap<Node, ARITY>,
+        tracker: &[HeapIndex],
+    ) {
+        for (i, node) in heap.iter().enumerate() {
+            assert_eq!(tracker[node.1], i);
+        }
+    }
+
+    /// Builds a heap with ranks `10, 20, 30, ...`.
+    fn ten_steps(n: usize) -> (IntrusiveHeap<Node>, Vec<HeapIndex>) {
+        let mut tracker = vec![0; n];
+        let mut heap = IntrusiveHeap::new();
+        for id in 0..n {
+            heap.push((10 * (id as u32 + 1), id), &mut tracker);
+        }
+        (heap, tracker)
+    }
+
+    #[test]
+    fn decrease_key() {
+        let (mut heap, mut tracker) = ten_steps(100);
+
+        let id = 42;
+        let heap_index = tracker[id];
+        assert_ne!(heap_index, 0);
+        heap[heap_index].0 = 5;
+        assert_eq!(heap.decrease_key(heap_index, &mut tracker), 0);
+        verify_tracker(&heap, &tracker);
+
+        assert_eq!(heap.pop(&mut tracker), Some((5, id)));
+        assert_eq!(heap.pop(&mut tracker), Some((10, 0)));
+    }
+
+    #[test]
+    fn increase_key() {
+        let (mut heap, mut tracker) = ten_steps(100);
+
+        heap[0].0 = 5000;
+        let heap_index = heap.increase_key(0, &mut tracker);
+        assert_eq!(tracker[0], heap_index);
+        verify_tracker(&heap, &tracker);
+
+        let mut popped = vec![];
+        while let Some((_rank, id)) = heap.pop(&mut tracker) {
+            popped.push(id);
+        }
+        assert_eq!(popped.last(), Some(&0));
+        assert_eq!(popped.len(), 100);
+    }
+
+    #[test]
+    fn remove() {
+        let (mut heap, mut tracker) = ten_steps(100);
+
+        for id in [42, 7, 99, 0] {
+            let heap_index = tracker[id];
+            assert_eq!(heap.remove(heap_index, &mut tracker).1, id);
+            verify_tracker(&heap, &tracker);
+        }
+        assert_eq!(heap.len(), 96);
+
+        let mut prev = 0;
+        while let Some((rank, id)) = heap.pop(&mut tracker) {
+            assert!(prev <= rank);
+            assert!(![42, 7, 99, 0].contains(&id));
+            verify_tracker(&heap, &tracker);
+            prev = rank;
+        }
+    }
+
+    fn heap_sorts<const ARITY: usize>() {
+        let mut r = ChaCha8Rng::seed_from_u64(ARITY as u64);
+        let ranks: Vec<u32> = (0..1000).map(|_| r.random_range(0..100)).collect();
+
+        let mut tracker = vec![0; ranks.len()];
+        let mut heap = IntrusiveHeap::<Node, ARITY>::new();
+        for (id, rank) in ranks.iter().enumerate() {
+            heap.push((*rank, id), &mut tracker);
+        }
+        verify_tracker(&heap, &tracker);
+
+        let mut sorted = ranks.clone();
+        sorted.sort();
+        let mut popped = vec![];
+        while let Some((rank, _id)) = heap.pop(&mut tracker) {
+            verify_tracker(&heap, &tracker);
+            popped.push(rank);
+        }
+        assert_eq!(popped, sorted);
+    }
+
+    #[test]
+    fn heap_sorts_2() {
+        heap_sorts::<2>();
+    }
+
+    #[test]
+    fn heap_sorts_4() {
+        heap_sorts::<4>();
+    }
+
+    #[test]
+    fn heap_sorts_8() {
+        heap_sorts::<8>();
+    }
+
+    #[test]
+    fn heap_sorts_12() {
+        heap_sorts::<12>();
+    }
+
+    #[test]
+    fn heap_sorts_16() {
+        heap_sorts::<16>();
+    }
+}
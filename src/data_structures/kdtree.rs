@@ -0,0 +1,230 @@
+//! A static 2D k-d tree.
+//!
+//! Points are laid out in a single `Vec` where every sub-slice is a sub-tree
+//! rooted at its middle element, so there's no pointer chasing and no
+//! rebalancing. This is meant for point sets that are fixed after loading.
+
+use std::cmp::Ordering;
+
+use num_traits::Signed;
+
+/// A 2D point.
+pub type Point<P> = [P; 2];
+
+/// A static k-d tree over 2D points carrying some data.
+#[derive(Clone, Debug)]
+pub struct KdTree<P, T>
+where
+    P: Copy + PartialOrd + Signed,
+{
+    items: Vec<(Point<P>, T)>,
+}
+
+#[inline(always)]
+#[must_use]
+fn squared_distance<P>(a: &Point<P>, b: &Point<P>) -> P
+where
+    P: Copy + PartialOrd + Signed,
+{
+    let dx = a[0] - b[0];
+    let dy = a[1] - b[1];
+    dx * dx + dy * dy
+}
+
+impl<P, T> KdTree<P, T>
+where
+    P: Copy + PartialOrd + Signed,
+{
+    #[must_use]
+    pub fn new(mut items: Vec<(Point<P>, T)>) -> Self {
+        Self::build(&mut items, 0);
+        Self { items }
+    }
+
+    fn build(items: &mut [(Point<P>, T)], depth: usize) {
+        if items.len() <= 1 {
+            return;
+        }
+        let axis = depth % 2;
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |(a, _), (b, _)| {
+            a[axis].partial_cmp(&b[axis]).unwrap_or(Ordering::Equal)
+        });
+        let (left, right) = items.split_at_mut(mid);
+        Self::build(left, depth + 1);
+        Self::build(&mut right[1..], depth + 1);
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Finds the closest point, returning it along with its squared distance.
+    #[must_use]
+    pub fn nearest(&self, p: &Point<P>) -> Option<(&Point<P>, &T, P)> {
+        let mut best: Option<(usize, P)> = None;
+        Self::nearest_in(&self.items, 0, 0, p, &mut best);
+        best.map(|(i, d)| {
+            let (q, t) = &self.items[i];
+            (q, t, d)
+        })
+    }
+
+    fn nearest_in(
+        items: &[(Point<P>, T)],
+        offset: usize,
+        depth: usize,
+        p: &Point<P>,
+        best: &mut Option<(usize, P)>,
+    ) {
+        if items.is_empty() {
+            return;
+        }
+        let axis = depth % 2;
+        let mid = items.len() / 2;
+        let (q, _) = &items[mid];
+
+        let d = squared_distance(p, q);
+        if best.is_none_or(|(_, best_d)| d < best_d) {
+            *best = Some((offset + mid, d));
+        }
+
+        let (left, right) = (&items[..mid], &items[mid + 1..]);
+        let (left_offset, right_offset) = (offset, offset + mid + 1);
+        let ((near, near_offset), (far, far_offset)) = if p[axis] < q[axis] {
+            ((left, left_offset), (right, right_offset))
+        } else {
+            ((right, right_offset), (left, left_offset))
+        };
+
+        Self::nearest_in(near, near_offset, depth + 1, p, best);
+        let split = p[axis] - q[axis];
+        if best.is_none_or(|(_, best_d)| split * split < best_d) {
+            Self::nearest_in(far, far_offset, depth + 1, p, best);
+        }
+    }
+
+    /// Finds all the points within distance `r` (inclusive).
+    #[must_use]
+    pub fn within_radius(&self, p: &Point<P>, r: P) -> Vec<(&Point<P>, &T)> {
+        let mut found = vec![];
+        Self::within_radius_in(&self.items, 0, p, r * r, &mut found);
+        found
+    }
+
+    fn within_radius_in<'a>(
+        items: &'a [(Point<P>, T)],
+        depth: usize,
+        p: &Point<P>,
+        r2: P,
+        found: &mut Vec<(&'a Point<P>, &'a T)>,
+    ) {
+        if items.is_empty() {
+            return;
+        }
+        let axis = depth % 2;
+        let mid = items.len() / 2;
+        let (q, t) = &items[mid];
+
+        if squared_distance(p, q) <= r2 {
+            found.push((q, t));
+        }
+
+        let split = p[axis] - q[axis];
+        let crosses = split * split <= r2;
+        if p[axis] < q[axis] || crosses {
+            Self::within_radius_in(&items[..mid], depth + 1, p, r2, found);
+        }
+        if p[axis] >= q[axis] || crosses {
+            Self::within_radius_in(&items[mid + 1..], depth + 1, p, r2, found);
+        }
+    }
+}
+
+impl<P, T> FromIterator<(Point<P>, T)> for KdTree<P, T>
+where
+    P: Copy + PartialOrd + Signed,
+{
+    fn from_iter<I: IntoIterator<Item = (Point<P>, T)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn random_points(rng: &mut ChaCha8Rng, n: usize) -> Vec<(Point<i64>, usize)> {
+        (0..n)
+            .map(|i| {
+                (
+                    [rng.random_range(-1000..1000), rng.random_range(-1000..1000)],
+                    i,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn empty() {
+        let tree = KdTree::<i64, ()>::new(vec![]);
+        assert!(tree.is_empty());
+        assert!(tree.nearest(&[0, 0]).is_none());
+        assert!(tree.within_radius(&[0, 0], 10).is_empty());
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let points = random_points(&mut rng, 500);
+        let tree: KdTree<i64, usize> = points.iter().copied().collect();
+        assert_eq!(tree.len(), points.len());
+
+        for _ in 0..200 {
+            let p = [rng.random_range(-1100..1100), rng.random_range(-1100..1100)];
+            let brute = points
+                .iter()
+                .map(|(q, _)| squared_distance(&p, q))
+                .min()
+                .unwrap();
+            let (q, i, d) = tree.nearest(&p).unwrap();
+            assert_eq!(d, brute);
+            assert_eq!(squared_distance(&p, q), d);
+            assert_eq!(points[*i].0, *q);
+        }
+    }
+
+    #[test]
+    fn within_radius_matches_brute_force() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let points = random_points(&mut rng, 500);
+        let tree: KdTree<i64, usize> = points.iter().copied().collect();
+
+        for _ in 0..100 {
+            let p = [rng.random_range(-1100..1100), rng.random_range(-1100..1100)];
+            let r = rng.random_range(0..300);
+            let mut brute: Vec<usize> = points
+                .iter()
+                .filter(|(q, _)| squared_distance(&p, q) <= r * r)
+                .map(|(_, i)| *i)
+                .collect();
+            let mut found: Vec<usize> =
+                tree.within_radius(&p, r).iter().map(|(_, i)| **i).collect();
+            brute.sort_unstable();
+            found.sort_unstable();
+            assert_eq!(found, brute);
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Data structures that support search spaces and algorithms.
+
+pub mod bucket_queue;
+pub mod chunked_arena;
+pub mod intrusive_heap;
+pub mod kdtree;
+pub mod state_set;
@@ -0,0 +1,103 @@
+//! Collections of States.
+//!
+//! States only need to be `Hash + Eq`, which is usually derived,
+//!
+//! ```rust
+//! #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+//! struct GridState {
+//!     x: u16,
+//!     y: u16,
+//! }
+//! ```
+//!
+//! so these collections use `FxHash`, which is fast for small keys like these.
+
+use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
+
+use crate::space::State;
+
+/// A set of States (visited sets, goal sets, ...).
+pub type StateSet<St> = FxHashSet<St>;
+/// A map from States to some value (costs, parents, ...).
+pub type StateMap<St, V> = FxHashMap<St, V>;
+
+/// The States that were already expanded.
+#[derive(Clone, Debug)]
+pub struct ClosedSet<St>
+where
+    St: State,
+{
+    closed: StateSet<St>,
+}
+
+impl<St> ClosedSet<St>
+where
+    St: State,
+{
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            closed: StateSet::default(),
+        }
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn is_closed(&self, s: &St) -> bool {
+        self.closed.contains(s)
+    }
+
+    /// Marks a State as closed, returning whether it was open.
+    #[inline(always)]
+    pub fn mark_closed(&mut self, s: &St) -> bool {
+        self.closed.insert(*s)
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.closed.len()
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.closed.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.closed.clear();
+    }
+}
+
+impl<St> Default for ClosedSet<St>
+where
+    St: State,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::problems::maze_2d::Maze2DState;
+
+    #[test]
+    fn closed_set() {
+        let s = Maze2DState::default();
+        let mut closed = ClosedSet::new();
+        assert!(!closed.is_closed(&s));
+
+        assert!(closed.mark_closed(&s));
+        assert!(!closed.mark_closed(&s));
+        assert!(closed.is_closed(&s));
+        assert_eq!(closed.len(), 1);
+
+        closed.clear();
+        assert!(closed.is_empty());
+    }
+}
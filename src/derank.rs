@@ -277,6 +277,10 @@ fn derank_16<T: PartialOrd>(a: &[T]) -> usize {
     )
 }
 
+/// Finds the index of the minimum of a non-empty slice, preferring the first
+/// one on ties.
+///
+/// Lengths without a tournament network fall back to a linear scan.
 #[inline(always)]
 #[must_use]
 pub fn derank<T: PartialOrd>(a: &[T]) -> usize {
@@ -293,7 +297,7 @@ pub fn derank<T: PartialOrd>(a: &[T]) -> usize {
         7 => derank_7(a),
         8 => derank_8(a),
         16 => derank_16(a),
-        _ => unreachable!(),
+        _ => linear_min_index(a),
     }
 }
 
@@ -355,6 +359,18 @@ mod tests {
         assert_eq!(derank(&a), linear_min_index(&a));
     }
 
+    #[test]
+    fn verify_fallback() {
+        for len in (9..16).chain([17]) {
+            for min_i in 0..len {
+                let mut a: Vec<u8> = (0..len).map(|i| (i * 7 % len) as u8 + 1).collect();
+                a[min_i] = 0;
+                assert_eq!(derank(&a), min_i);
+                assert_eq!(derank(&a), linear_min_index(&a));
+            }
+        }
+    }
+
     #[test]
     fn verify_16() {
         let a = vec![
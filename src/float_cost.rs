@@ -0,0 +1,281 @@
+//! Floating point costs.
+//!
+//! Floats are not `Ord` because of NaN, so `FloatCost` wraps them rejecting NaN
+//! and negative values. What's left is totally ordered, and non-negative, so it
+//! can be a `Cost`.
+//!
+//! `Cost` requires `Unsigned`, which floats can't implement. `FloatCost` only
+//! gets it because `new` and parsing reject negative values, and subtraction
+//! clamps at 0. Both `f32` and `f64` work, see `FloatCost32` and `FloatCost64`.
+
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use num_traits::Num;
+use num_traits::One;
+use num_traits::SaturatingAdd;
+use num_traits::Zero;
+use num_traits::bounds::UpperBounded;
+use num_traits::float::FloatCore;
+use num_traits::sign::Unsigned;
+use thiserror::Error;
+
+use crate::space::Cost;
+use crate::space::OrdCost;
+
+/// A non-negative, non-NaN float.
+///
+/// Infinity is the `max_value`, so it also marks invalid costs.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FloatCost<F: FloatCore>(F);
+
+/// Single precision costs, enough for grid distances.
+pub type FloatCost32 = FloatCost<f32>;
+/// Double precision costs, for long accumulated distances.
+pub type FloatCost64 = FloatCost<f64>;
+
+impl<F: FloatCore> FloatCost<F> {
+    /// Wraps a float, unless it's NaN or negative.
+    #[must_use]
+    pub fn new(f: F) -> Option<Self> {
+        (!f.is_nan() && f >= F::zero()).then_some(Self(f))
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn get(&self) -> F {
+        self.0
+    }
+
+    /// Wraps the result of an operation on valid costs.
+    #[inline(always)]
+    #[must_use]
+    fn from_op(f: F) -> Self {
+        debug_assert!(!f.is_nan(), "Cost operation resulted in NaN");
+        Self(f)
+    }
+}
+
+/// NaN is never wrapped, so `PartialEq` is reflexive.
+impl<F: FloatCore> Eq for FloatCost<F> {}
+
+/// `PartialOrd` is forwarded to `Ord::cmp`
+impl<F: FloatCore> PartialOrd for FloatCost<F> {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+/// NaN is never wrapped, so every pair of values is comparable.
+impl<F: FloatCore> Ord for FloatCost<F> {
+    #[inline(always)]
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0
+            .partial_cmp(&other.0)
+            .expect("FloatCost can't be NaN")
+    }
+}
+
+impl<F: FloatCore + Display> Display for FloatCost<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl<F: FloatCore> std::ops::Add for FloatCost<F> {
+    type Output = Self;
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self::from_op(self.0 + rhs.0)
+    }
+}
+impl<F: FloatCore> std::ops::AddAssign for FloatCost<F> {
+    #[inline(always)]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+/// Rounding errors could make differences negative, so they are clamped at 0.
+impl<F: FloatCore> std::ops::Sub for FloatCost<F> {
+    type Output = Self;
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        Self::from_op((self.0 - rhs.0).max(F::zero()))
+    }
+}
+impl<F: FloatCore> std::ops::Mul for FloatCost<F> {
+    type Output = Self;
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self {
+        Self::from_op(self.0 * rhs.0)
+    }
+}
+impl<F: FloatCore> std::ops::Div for FloatCost<F> {
+    type Output = Self;
+    #[inline(always)]
+    fn div(self, rhs: Self) -> Self {
+        Self::from_op(self.0 / rhs.0)
+    }
+}
+impl<F: FloatCore> std::ops::Rem for FloatCost<F> {
+    type Output = Self;
+    #[inline(always)]
+    fn rem(self, rhs: Self) -> Self {
+        Self::from_op(self.0 % rhs.0)
+    }
+}
+
+/// Floats saturate at infinity on their own.
+impl<F: FloatCore> SaturatingAdd for FloatCost<F> {
+    #[inline(always)]
+    fn saturating_add(&self, v: &Self) -> Self {
+        *self + *v
+    }
+}
+
+impl<F: FloatCore> Zero for FloatCost<F> {
+    #[inline(always)]
+    fn zero() -> Self {
+        Self(F::zero())
+    }
+    #[inline(always)]
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+impl<F: FloatCore> One for FloatCost<F> {
+    #[inline(always)]
+    fn one() -> Self {
+        Self(F::one())
+    }
+}
+
+impl<F: FloatCore> UpperBounded for FloatCost<F> {
+    #[inline(always)]
+    fn max_value() -> Self {
+        Self(F::infinity())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FloatCostParseError {
+    #[error("Invalid float")]
+    InvalidFloat,
+    #[error("Costs can't be NaN or negative")]
+    OutOfRange,
+}
+
+impl<F: FloatCore> Num for FloatCost<F> {
+    type FromStrRadixErr = FloatCostParseError;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        let f = F::from_str_radix(s, radix).map_err(|_e| FloatCostParseError::InvalidFloat)?;
+        Self::new(f).ok_or(FloatCostParseError::OutOfRange)
+    }
+}
+
+/// Negative values are rejected by `FloatCost::new`.
+impl<F: FloatCore> Unsigned for FloatCost<F> {}
+
+/// Sums of `n` costs can be off by about `n` ulps, so costs are compared with
+/// a relative tolerance that allows for long paths.
+impl<F: FloatCore + Debug + Display> OrdCost for FloatCost<F> {
+    #[inline(always)]
+    fn approx_eq(&self, other: &Self) -> bool {
+        if self.0 == other.0 {
+            return true;
+        }
+        if self.0.is_infinite() || other.0.is_infinite() {
+            return false;
+        }
+        let tolerance = F::epsilon() * <F as num_traits::NumCast>::from(1 << 12).unwrap();
+        (self.0 - other.0).abs() <= tolerance * self.0.max(other.0)
+    }
+}
+impl<F: FloatCore + Debug + Display> Cost for FloatCost<F> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero() {
+        let zero = FloatCost::<f32>::zero();
+        assert!(zero.is_zero());
+        assert_eq!(zero, FloatCost::new(0.0).unwrap());
+        assert_eq!(zero, FloatCost::new(-0.0).unwrap());
+        assert!(zero.valid());
+    }
+
+    #[test]
+    fn order() {
+        let one = FloatCost::new(1.0f32).unwrap();
+        let two = FloatCost::new(2.0f32).unwrap();
+        assert!(one < two);
+        assert!(two < FloatCost::max_value());
+        assert_eq!(one.max(two), two);
+
+        assert_eq!(FloatCost::new(f32::NAN), None);
+        assert_eq!(FloatCost::new(-1.0f32), None);
+    }
+
+    #[test]
+    fn sum() {
+        let a = FloatCost::new(1.5f32).unwrap();
+        let b = FloatCost::new(2.25f32).unwrap();
+        assert_eq!((a + b).get(), 3.75);
+        assert_eq!((b - a).get(), 0.75);
+        assert_eq!((a - b).get(), 0.0);
+
+        let max = FloatCost::<f32>::max_value();
+        assert_eq!(a.saturating_add(&max), max);
+        assert!(!max.valid());
+    }
+
+    #[test]
+    fn is_cost() {
+        fn cost<C: Cost>() -> C {
+            C::zero()
+        }
+        assert!(cost::<FloatCost32>().is_zero());
+        assert!(cost::<FloatCost64>().is_zero());
+    }
+
+    #[test]
+    fn order_f64() {
+        let one = FloatCost64::new(1.0).unwrap();
+        let next = FloatCost64::new(1.0 + f64::EPSILON).unwrap();
+        assert!(one < next);
+        assert!(next < FloatCost64::max_value());
+
+        assert_eq!(FloatCost64::new(f64::NAN), None);
+        assert_eq!(FloatCost64::new(-f64::MIN_POSITIVE), None);
+    }
+
+    #[test]
+    fn sum_f64() {
+        let a = FloatCost64::new(0.1).unwrap();
+        let b = FloatCost64::new(0.2).unwrap();
+        assert_eq!((a + b).get(), 0.1 + 0.2);
+        assert_eq!((a - b).get(), 0.0);
+        assert_eq!(FloatCost64::from_str_radix("2.5", 10).unwrap().get(), 2.5);
+        assert!(FloatCost64::from_str_radix("-2.5", 10).is_err());
+
+        // Adding in a different order rounds differently.
+        let tenth = FloatCost64::new(0.1).unwrap();
+        let sum = (0..10).fold(FloatCost64::zero(), |sum, _i| sum + tenth);
+        let one = FloatCost64::new(1.0).unwrap();
+        assert_ne!(sum, one);
+        assert!(sum.approx_eq(&one));
+        assert!(!sum.approx_eq(&FloatCost64::new(1.001).unwrap()));
+        assert!(!one.approx_eq(&FloatCost64::max_value()));
+
+        // Increments too small for f32 still add up.
+        let tiny = FloatCost64::new(1e-9).unwrap();
+        let mut sum = FloatCost64::new(1.0).unwrap();
+        for _ in 0..1000 {
+            sum += tiny;
+        }
+        assert!(sum.get() > 1.0);
+    }
+}
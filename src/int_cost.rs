@@ -0,0 +1,149 @@
+//! Signed integer costs.
+//!
+//! `Cost` requires `Unsigned` costs. `IntCost` wraps signed integers as an
+//! `OrdCost`, which is all Dijkstra and A* need to rank nodes, so `g` values
+//! and heuristics can go below zero (like when starting from a negative
+//! offset).
+//!
+//! NOTE: The searches don't support negative edges. They close nodes after
+//! expanding them, which is only sound when costs never decrease along paths.
+
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use num_traits::PrimInt;
+use num_traits::SaturatingAdd;
+use num_traits::Signed;
+use num_traits::Zero;
+use num_traits::bounds::UpperBounded;
+
+use crate::space::OrdCost;
+
+/// A signed integer cost.
+///
+/// Operations saturate, so `max_value` stays the marker for invalid costs and
+/// subtracting costs can't wrap around.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IntCost<I: PrimInt + Signed>(I);
+
+impl<I: PrimInt + Signed> IntCost<I> {
+    #[inline(always)]
+    #[must_use]
+    pub fn new(i: I) -> Self {
+        Self(i)
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn get(&self) -> I {
+        self.0
+    }
+}
+
+impl<I: PrimInt + Signed + Display> Display for IntCost<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl<I: PrimInt + Signed> std::ops::Add for IntCost<I> {
+    type Output = Self;
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+impl<I: PrimInt + Signed> std::ops::AddAssign for IntCost<I> {
+    #[inline(always)]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+/// A* recovers `g` as `f - h`, which is exact unless `f` saturated.
+impl<I: PrimInt + Signed> std::ops::Sub for IntCost<I> {
+    type Output = Self;
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl<I: PrimInt + Signed> SaturatingAdd for IntCost<I> {
+    #[inline(always)]
+    fn saturating_add(&self, v: &Self) -> Self {
+        *self + *v
+    }
+}
+
+impl<I: PrimInt + Signed> Zero for IntCost<I> {
+    #[inline(always)]
+    fn zero() -> Self {
+        Self(I::zero())
+    }
+    #[inline(always)]
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+impl<I: PrimInt + Signed> UpperBounded for IntCost<I> {
+    #[inline(always)]
+    fn max_value() -> Self {
+        Self(I::max_value())
+    }
+}
+
+impl<I: PrimInt + Signed + Debug + Display> OrdCost for IntCost<I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::astar::AStarRank;
+    use crate::algorithms::astar::Rank;
+
+    #[test]
+    fn zero() {
+        let zero = IntCost::<i64>::zero();
+        assert!(zero.is_zero());
+        assert_eq!(zero, IntCost::new(0));
+        assert_eq!(zero, IntCost::default());
+        assert!(zero.valid());
+    }
+
+    #[test]
+    fn order() {
+        let minus_one = IntCost::new(-1i64);
+        let one = IntCost::new(1i64);
+        assert!(minus_one < IntCost::zero());
+        assert!(minus_one < one);
+        assert!(one < IntCost::max_value());
+        assert_eq!(minus_one.min(one), minus_one);
+    }
+
+    #[test]
+    fn sum() {
+        let a = IntCost::new(5i64);
+        let b = IntCost::new(-7i64);
+        assert_eq!((a + b).get(), -2);
+        assert_eq!((a - b).get(), 12);
+        assert_eq!((b - a).get(), -12);
+
+        let max = IntCost::<i64>::max_value();
+        assert_eq!(a.saturating_add(&max), max);
+        assert_eq!(max - b, max);
+        assert!(!max.valid());
+        assert_eq!(IntCost::new(i64::MIN) + b, IntCost::new(i64::MIN));
+    }
+
+    #[test]
+    fn worsen_h() {
+        let mut rank = AStarRank::<_>::new(IntCost::new(-5i64), IntCost::new(3));
+        assert!(rank.worsen_h(IntCost::new(10)));
+        assert_eq!(
+            rank,
+            AStarRank::<_>::new(IntCost::new(-5), IntCost::new(10))
+        );
+        assert!(!rank.worsen_h(IntCost::new(-20)));
+    }
+}
@@ -0,0 +1,258 @@
+//! Lexicographic costs.
+//!
+//! Optimizing a primary cost and breaking its ties with a secondary one (like
+//! distance, then turns) is the same as optimizing the pair compared
+//! lexicographically.
+
+use std::fmt::Display;
+
+use num_traits::Num;
+use num_traits::One;
+use num_traits::SaturatingAdd;
+use num_traits::Zero;
+use num_traits::bounds::UpperBounded;
+use num_traits::sign::Unsigned;
+use thiserror::Error;
+
+use crate::space::Cost;
+use crate::space::OrdCost;
+
+/// A pair of costs compared lexicographically.
+///
+/// Operations are component-wise, so the secondary cost accumulates along
+/// paths independently of the primary one.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LexCost<A: OrdCost, B: OrdCost>(A, B);
+
+impl<A: OrdCost, B: OrdCost> LexCost<A, B> {
+    #[inline(always)]
+    #[must_use]
+    pub fn new(primary: A, secondary: B) -> Self {
+        Self(primary, secondary)
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn primary(&self) -> A {
+        self.0
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn secondary(&self) -> B {
+        self.1
+    }
+}
+
+impl<A: OrdCost, B: OrdCost> Display for LexCost<A, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.0, self.1)
+    }
+}
+
+impl<A: OrdCost, B: OrdCost> std::ops::Add for LexCost<A, B> {
+    type Output = Self;
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+impl<A: OrdCost, B: OrdCost> std::ops::AddAssign for LexCost<A, B> {
+    #[inline(always)]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+impl<A: OrdCost, B: OrdCost> std::ops::Sub for LexCost<A, B> {
+    type Output = Self;
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+/// Component-wise.
+///
+/// NOTE: Multiplication, division and remainder don't mean much for pairs
+/// compared lexicographically. They are only here because `Cost` requires
+/// `Unsigned`, which requires `Num`, and searches don't use them.
+impl<A: Cost, B: Cost> std::ops::Mul for LexCost<A, B> {
+    type Output = Self;
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self {
+        Self(self.0 * rhs.0, self.1 * rhs.1)
+    }
+}
+/// Component-wise, see `Mul`.
+impl<A: Cost, B: Cost> std::ops::Div for LexCost<A, B> {
+    type Output = Self;
+    #[inline(always)]
+    fn div(self, rhs: Self) -> Self {
+        Self(self.0 / rhs.0, self.1 / rhs.1)
+    }
+}
+/// Component-wise, see `Mul`.
+impl<A: Cost, B: Cost> std::ops::Rem for LexCost<A, B> {
+    type Output = Self;
+    #[inline(always)]
+    fn rem(self, rhs: Self) -> Self {
+        Self(self.0 % rhs.0, self.1 % rhs.1)
+    }
+}
+
+impl<A: OrdCost, B: OrdCost> SaturatingAdd for LexCost<A, B> {
+    #[inline(always)]
+    fn saturating_add(&self, v: &Self) -> Self {
+        Self(self.0.saturating_add(&v.0), self.1.saturating_add(&v.1))
+    }
+}
+
+impl<A: OrdCost, B: OrdCost> Zero for LexCost<A, B> {
+    #[inline(always)]
+    fn zero() -> Self {
+        Self(A::zero(), B::zero())
+    }
+    #[inline(always)]
+    fn is_zero(&self) -> bool {
+        self.0.is_zero() && self.1.is_zero()
+    }
+}
+/// Only here for `Num`, see `Mul`.
+impl<A: Cost, B: Cost> One for LexCost<A, B> {
+    #[inline(always)]
+    fn one() -> Self {
+        Self(A::one(), B::one())
+    }
+}
+
+impl<A: OrdCost, B: OrdCost> UpperBounded for LexCost<A, B> {
+    #[inline(always)]
+    fn max_value() -> Self {
+        Self(A::max_value(), B::max_value())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LexCostParseError {
+    #[error("Expected a pair like '(a, b)'")]
+    InvalidPair,
+    #[error("Invalid cost in pair")]
+    InvalidCost,
+}
+
+/// Parses the `Display` format, `(a, b)`.
+///
+/// Only `from_str_radix` is needed from `Num`, see `Mul`.
+impl<A: Cost, B: Cost> Num for LexCost<A, B> {
+    type FromStrRadixErr = LexCostParseError;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        let (a, b) = s
+            .trim()
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .and_then(|s| s.split_once(','))
+            .ok_or(LexCostParseError::InvalidPair)?;
+        let a = A::from_str_radix(a.trim(), radix).map_err(|_e| LexCostParseError::InvalidCost)?;
+        let b = B::from_str_radix(b.trim(), radix).map_err(|_e| LexCostParseError::InvalidCost)?;
+        Ok(Self(a, b))
+    }
+}
+
+/// Pairs of non-negative costs are non-negative.
+impl<A: Cost, B: Cost> Unsigned for LexCost<A, B> {}
+
+/// A pair is invalid when either cost saturated, as adding to a saturated
+/// primary cost can still change the secondary one.
+impl<A: OrdCost, B: OrdCost> OrdCost for LexCost<A, B> {
+    #[inline(always)]
+    fn valid(&self) -> bool {
+        self.0.valid() && self.1.valid()
+    }
+}
+impl<A: Cost, B: Cost> Cost for LexCost<A, B> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::algorithms::dijkstra::DijkstraSearch;
+    use crate::problem::BaseProblem;
+    use crate::problem::FloodProblem;
+    use crate::problem::ObjectiveProblem;
+    use crate::problems::maze_2d::Maze2DAction;
+    use crate::problems::maze_2d::Maze2DCost;
+    use crate::problems::maze_2d::Maze2DProblem;
+    use crate::problems::maze_2d::Maze2DSpace;
+    use crate::problems::maze_2d::Maze2DState;
+    use crate::space::Space;
+
+    type Lex = LexCost<u32, u32>;
+
+    #[test]
+    fn order() {
+        assert!(Lex::new(1, 9) < Lex::new(2, 0));
+        assert!(Lex::new(1, 0) < Lex::new(1, 1));
+        assert!(Lex::new(1, 1) < Lex::max_value());
+        assert!(Lex::zero().is_zero());
+        assert!(!Lex::max_value().valid());
+        assert!(!Lex::new(u32::MAX, 0).valid());
+        assert!(!Lex::new(0, u32::MAX).valid());
+        assert!(Lex::new(1, 1).valid());
+    }
+
+    #[test]
+    fn sum() {
+        let a = Lex::new(1, 2);
+        let b = Lex::new(3, 4);
+        assert_eq!(a + b, Lex::new(4, 6));
+        assert_eq!(b - a, Lex::new(2, 2));
+        assert_eq!(a.saturating_add(&Lex::max_value()), Lex::max_value());
+        assert_eq!(Lex::from_str_radix(&b.to_string(), 10).unwrap(), b);
+    }
+
+    /// A maze where stepping into the top row is penalized.
+    #[derive(Clone, Debug)]
+    struct PenalizedMaze(Maze2DSpace);
+
+    impl Space<Maze2DState, Maze2DAction, Lex> for PenalizedMaze {
+        fn apply(&self, s: &Maze2DState, a: &Maze2DAction) -> Option<Maze2DState> {
+            self.0.apply(s, a)
+        }
+        fn cost(&self, s: &Maze2DState, a: &Maze2DAction) -> Lex {
+            let to = self.0.apply(s, a).unwrap();
+            let penalty = if to.y.get() == 0 { 1 } else { 0 };
+            Lex::new(self.0.cost(s, a), penalty)
+        }
+        fn neighbours(&self, s: &Maze2DState) -> Vec<(Maze2DState, Maze2DAction)> {
+            self.0.neighbours(s)
+        }
+        fn valid(&self, s: &Maze2DState) -> bool {
+            self.0.valid(s)
+        }
+    }
+
+    #[test]
+    fn tie_break() {
+        // Going around the wall over or under it costs the same distance.
+        let problem = Maze2DProblem::try_from(indoc! {"
+            .....
+            S.#.G
+            .....
+        "})
+        .unwrap();
+        let distance: Maze2DCost = DijkstraSearch::new(problem.clone())
+            .find_next_goal()
+            .unwrap()
+            .cost();
+
+        let space = PenalizedMaze(problem.space().clone());
+        let lex_problem =
+            FloodProblem::new(space.clone(), problem.starts()[0]).with_goal(problem.goals()[0]);
+        let path = DijkstraSearch::new(lex_problem).find_next_goal().unwrap();
+
+        assert_eq!(path.cost(), Lex::new(distance, 0));
+        assert!(path.states(&space).all(|s| s.y.get() != 0));
+    }
+}
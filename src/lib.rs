@@ -8,6 +8,7 @@ shadow!(build);
 
 // Internals
 // ---------
+pub mod data_structures;
 pub mod debug;
 pub mod derank;
 pub mod heap_primitives;
@@ -18,6 +19,9 @@ pub mod renderer;
 
 // Search space and problems
 // -------------------------
+pub mod float_cost;
+pub mod int_cost;
+pub mod lex_cost;
 pub mod problem;
 pub mod search;
 pub mod space;
@@ -1,5 +1,8 @@
+use std::marker::PhantomData;
+
 use crate::space::Action;
-use crate::space::Cost;
+use crate::space::OrdCost;
+use crate::space::SampleableSpace;
 use crate::space::Space;
 use crate::space::State;
 
@@ -9,7 +12,7 @@ where
     Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     #[must_use]
     fn space(&self) -> &Sp;
@@ -27,19 +30,22 @@ where
     Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     /// The goal states.
     #[must_use]
     fn goals(&self) -> &[St];
 
+    /// Creates a random instance on the same space.
     #[must_use]
     fn randomize<R: rand::Rng>(
         &mut self,
         r: &mut R,
         num_starts: u16,
         num_goals: u16,
-    ) -> Option<Self>;
+    ) -> Option<Self>
+    where
+        Sp: SampleableSpace<St, A, C>;
 }
 
 /// A problem where the goal is to reach states satisfying certain condition.
@@ -52,7 +58,7 @@ where
     Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     /// The goal condition.
     #[must_use]
@@ -64,16 +70,96 @@ where
 /// NOTE: An heuristic here mixes both, objective and condition heuristics. If
 /// the objectives match the goal condition the maximum of both heuristics can
 /// be used, otherwise the minimum is needed to avoid letting the condition
-/// heuristic to steer you away from objective states.
-///
-/// TODO: Think about this harder. Do we need to rank using both heuristics
-/// separately?
+/// heuristic to steer you away from objective states (see `MixedHeuristic`).
 pub trait MixedProblem<Sp, St, A, C>:
     ConditionProblem<Sp, St, A, C> + ObjectiveProblem<Sp, St, A, C>
 where
     Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
+{
+}
+
+/// A problem flooding a space from a single start.
+///
+/// It has no goals, so searches explore every state reachable from the
+/// start, unless a goal is added with `with_goal`.
+#[derive(Debug)]
+pub struct FloodProblem<Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    space: Sp,
+    starts: [St; 1],
+    goals: Vec<St>,
+
+    _phantom_action: PhantomData<A>,
+    _phantom_cost: PhantomData<C>,
+}
+
+impl<Sp, St, A, C> FloodProblem<Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[must_use]
+    pub fn new(space: Sp, start: St) -> Self {
+        Self {
+            space,
+            starts: [start],
+            goals: Vec::new(),
+            _phantom_action: PhantomData,
+            _phantom_cost: PhantomData,
+        }
+    }
+
+    /// Adds a goal to stop searches at.
+    #[must_use]
+    pub fn with_goal(mut self, goal: St) -> Self {
+        self.goals.push(goal);
+        self
+    }
+}
+
+impl<Sp, St, A, C> BaseProblem<Sp, St, A, C> for FloodProblem<Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
 {
+    fn space(&self) -> &Sp {
+        &self.space
+    }
+    fn starts(&self) -> &[St] {
+        &self.starts
+    }
+}
+
+impl<Sp, St, A, C> ObjectiveProblem<Sp, St, A, C> for FloodProblem<Sp, St, A, C>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    fn goals(&self) -> &[St] {
+        &self.goals
+    }
+
+    /// Floods are defined by their start, so they can't be randomized.
+    fn randomize<R: rand::Rng>(
+        &mut self,
+        _r: &mut R,
+        _num_starts: u16,
+        _num_goals: u16,
+    ) -> Option<Self> {
+        None
+    }
 }
@@ -4,32 +4,53 @@
 //! either free (`Empty`) or blocked (`Wall`).
 
 use std::hash::Hash;
+use std::num::NonZeroU8;
 
 use derive_more::Display;
 use nonmax::NonMaxU32;
 
 use crate::problem::BaseProblem;
+use crate::problem::ConditionProblem;
+use crate::problem::MixedProblem;
 use crate::problem::ObjectiveProblem;
 use crate::space::Action;
+use crate::space::ConditionHeuristic;
 use crate::space::Cost;
+use crate::space::DistanceHeuristic;
 use crate::space::ObjectiveHeuristic;
+use crate::space::OrdCost;
+use crate::space::Path;
+use crate::space::ReversibleSpace;
+use crate::space::SampleableSpace;
 use crate::space::Space;
 use crate::space::State;
 
 const MAX_ELEMENTS_DISPLAYED: usize = 20;
 const RANDOM_STATE_MAX_TRIES: usize = 10_000;
+/// Bounds the exploration used to find far away goals.
+const HARD_RANDOMIZE_MAX_EXPANSIONS: usize = 1_000_000;
+/// Goals are picked among this fraction of the farthest reachable cells.
+const HARD_RANDOMIZE_FARTHEST_FRACTION: usize = 10;
 
 // Simple colors
+#[cfg(feature = "image")]
 const WHITE: [u8; 3] = [u8::MAX, u8::MAX, u8::MAX];
+#[cfg(feature = "image")]
 const BLACK: [u8; 3] = [u8::MIN, u8::MIN, u8::MIN];
+#[cfg(feature = "image")]
 const RED: [u8; 3] = [u8::MAX, u8::MIN, u8::MIN];
+#[cfg(feature = "image")]
 const GREEN: [u8; 3] = [u8::MIN, u8::MAX, u8::MIN];
+#[cfg(feature = "image")]
 const BLUE: [u8; 3] = [u8::MIN, u8::MIN, u8::MAX];
+#[cfg(feature = "image")]
+const YELLOW: [u8; 3] = [u8::MAX, u8::MAX, u8::MIN];
 
 pub(crate) type CoordIntrinsic = u32;
 pub type Coord = NonMaxU32;
 
 #[derive(Copy, Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[display("({x},{y})")]
 pub struct Maze2DState {
     pub x: Coord,
@@ -79,6 +100,7 @@ impl Default for Maze2DState {
 }
 
 #[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Maze2DAction {
     #[display("↑")]
     Up = 0,
@@ -97,13 +119,37 @@ pub enum Maze2DAction {
     #[display("↘")]
     RightDown = 7,
 }
-impl Action for Maze2DAction {}
+impl Action for Maze2DAction {
+    #[inline(always)]
+    fn is_diagonal(&self) -> bool {
+        *self > Maze2DAction::Right
+    }
+}
+impl Maze2DAction {
+    /// The action undoing this one.
+    #[must_use]
+    pub fn opposite(&self) -> Self {
+        match self {
+            Maze2DAction::Up => Maze2DAction::Down,
+            Maze2DAction::Down => Maze2DAction::Up,
+            Maze2DAction::Left => Maze2DAction::Right,
+            Maze2DAction::Right => Maze2DAction::Left,
+            Maze2DAction::LeftUp => Maze2DAction::RightDown,
+            Maze2DAction::RightUp => Maze2DAction::LeftDown,
+            Maze2DAction::LeftDown => Maze2DAction::RightUp,
+            Maze2DAction::RightDown => Maze2DAction::LeftUp,
+        }
+    }
+}
 
 pub type Maze2DCost = CoordIntrinsic;
+impl OrdCost for Maze2DCost {}
 impl Cost for Maze2DCost {}
 
-const ORTHOGONAL_COST: Maze2DCost = 100u32;
-const DIAGONAL_COST: Maze2DCost = 141u32; // 1.414213562373095
+/// The default cost of moving `Up`, `Down`, `Left` or `Right`.
+pub(crate) const ORTHOGONAL_COST: Maze2DCost = 100u32;
+/// The default cost of moving diagonally.
+pub(crate) const DIAGONAL_COST: Maze2DCost = 141u32; // 1.414213562373095
 
 #[derive(Copy, Clone, Debug, Display, PartialEq)]
 pub enum Maze2DCell {
@@ -113,6 +159,47 @@ pub enum Maze2DCell {
     Wall,
 }
 
+/// How hard an empty cell is to walk into.
+///
+/// Moving into a cell costs the usual move cost times its weight. Weights are
+/// never below `PLAIN`, which keeps the distance heuristics admissible. When
+/// every cell is heavier, heuristics scaled by the minimum weight stay
+/// admissible while being more informed.
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord)]
+#[display("×{_0}")]
+pub struct Maze2DTerrain(NonZeroU8);
+
+impl Maze2DTerrain {
+    pub const PLAIN: Self = Self(NonZeroU8::MIN);
+
+    /// Creates a terrain from its weight, which can't be 0.
+    #[must_use]
+    pub fn new(weight: u8) -> Option<Self> {
+        NonZeroU8::new(weight).map(Self)
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn weight(&self) -> Maze2DCost {
+        self.0.get() as Maze2DCost
+    }
+
+    /// Maps grey levels to weights, from `PLAIN` (white) to 8 (almost black).
+    #[cfg(feature = "image")]
+    #[must_use]
+    fn from_grey(level: u8) -> Self {
+        Self::new(1 + (u8::MAX - level) / 32).unwrap()
+    }
+
+    /// Maps weights back to grey levels, inverting `from_grey` on its range.
+    #[cfg(feature = "image")]
+    #[must_use]
+    fn to_grey(self) -> u8 {
+        let darkness = (self.0.get() - 1).saturating_mul(32);
+        u8::MAX.saturating_sub(darkness).max(1)
+    }
+}
+
 use thiserror::Error;
 #[derive(Debug, Error)]
 pub enum Maze2DCellParseError {
@@ -132,19 +219,148 @@ impl std::convert::TryFrom<char> for Maze2DCell {
     }
 }
 
+/// The moves allowed between cells.
+///
+/// Heuristics need to match the connectivity to be admissible and informed,
+/// - `Four` pairs with `Maze2DHeuristicManhattanDistance`.
+/// - `Eight` pairs with `Maze2DHeuristicDiagonalDistance`. Manhattan distance
+///   overestimates diagonal moves.
+#[derive(Copy, Clone, Debug, Default, Display, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Only `Up`, `Down`, `Left` and `Right`.
+    #[display("4-connected")]
+    Four,
+    /// Orthogonal and diagonal moves.
+    #[default]
+    #[display("8-connected")]
+    Eight,
+}
+
 #[derive(Clone)]
 pub struct Maze2DSpace {
     pub map: Vec<Vec<Maze2DCell>>,
+    connectivity: Connectivity,
+    /// The terrain of each cell, only allocated once a cell isn't `PLAIN`.
+    terrain: Option<Vec<Vec<Maze2DTerrain>>>,
+    orthogonal_cost: Maze2DCost,
+    diagonal_cost: Maze2DCost,
 }
 
 impl Maze2DSpace {
     pub fn new_from_map(map: Vec<Vec<Maze2DCell>>) -> Self {
-        Self { map }
+        Self {
+            map,
+            connectivity: Connectivity::default(),
+            terrain: None,
+            orthogonal_cost: ORTHOGONAL_COST,
+            diagonal_cost: DIAGONAL_COST,
+        }
     }
     pub(crate) fn new_empty_with_dimensions(x: usize, y: usize) -> Self {
-        Self {
-            map: vec![vec![Maze2DCell::Empty; x]; y],
+        Self::new_from_map(vec![vec![Maze2DCell::Empty; x]; y])
+    }
+
+    /// Sets the moves allowed between cells.
+    #[must_use]
+    pub fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+    #[inline(always)]
+    pub fn connectivity(&self) -> Connectivity {
+        self.connectivity
+    }
+
+    /// Sets the cost of orthogonal and diagonal moves, before terrain.
+    ///
+    /// The distance heuristics follow these costs when ranking nodes, and
+    /// path costs saturate instead of overflowing on huge costs.
+    #[must_use]
+    pub fn with_costs(mut self, orthogonal_cost: Maze2DCost, diagonal_cost: Maze2DCost) -> Self {
+        self.orthogonal_cost = orthogonal_cost;
+        self.diagonal_cost = diagonal_cost;
+        self
+    }
+    #[inline(always)]
+    pub fn orthogonal_cost(&self) -> Maze2DCost {
+        self.orthogonal_cost
+    }
+    #[inline(always)]
+    pub fn diagonal_cost(&self) -> Maze2DCost {
+        self.diagonal_cost
+    }
+
+    /// The cost of an action, before terrain.
+    #[inline(always)]
+    #[must_use]
+    pub fn move_cost(&self, a: &Maze2DAction) -> Maze2DCost {
+        if a.is_diagonal() {
+            self.diagonal_cost
+        } else {
+            self.orthogonal_cost
+        }
+    }
+
+    /// The distance between two states without walls nor terrain, following
+    /// the connectivity and costs of this space.
+    ///
+    /// The distance heuristics use it to stay admissible for any move costs.
+    #[must_use]
+    pub fn distance_estimate(&self, a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        let delta_x = a.x.get().abs_diff(b.x.get());
+        let delta_y = a.y.get().abs_diff(b.y.get());
+        let [delta_min, delta_max] = std::cmp::minmax(delta_x, delta_y);
+
+        // Diagonals only help when they are cheaper than the two orthogonal
+        // moves they replace.
+        let orthogonal = self.orthogonal_cost;
+        let diagonal = match self.connectivity {
+            Connectivity::Four => orthogonal.saturating_mul(2),
+            Connectivity::Eight => self.diagonal_cost.min(orthogonal.saturating_mul(2)),
+        };
+        delta_min
+            .saturating_mul(diagonal)
+            .saturating_add((delta_max - delta_min).saturating_mul(orthogonal))
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn terrain(&self, state: &Maze2DState) -> Maze2DTerrain {
+        debug_assert!(self.valid(state));
+        match &self.terrain {
+            Some(terrain) => terrain[state.y.get() as usize][state.x.get() as usize],
+            None => Maze2DTerrain::PLAIN,
+        }
+    }
+    pub fn set_terrain(&mut self, state: &Maze2DState, terrain: Maze2DTerrain) {
+        debug_assert!(self.valid(state));
+        if self.terrain.is_none() && terrain == Maze2DTerrain::PLAIN {
+            return;
         }
+        let (max_x, max_y) = self.dimensions();
+        let cells = self
+            .terrain
+            .get_or_insert_with(|| vec![vec![Maze2DTerrain::PLAIN; max_x]; max_y]);
+        cells[state.y.get() as usize][state.x.get() as usize] = terrain;
+    }
+
+    /// Checks whether any cell has terrain other than `PLAIN`.
+    #[must_use]
+    pub fn has_terrain(&self) -> bool {
+        self.terrain
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|&t| t != Maze2DTerrain::PLAIN)
+    }
+
+    /// Sets a cell to `Empty` or `Wall`.
+    ///
+    /// This changes the topology of the space, so searches over it (and
+    /// their `SearchTree`s) are invalidated and need to start over.
+    pub fn set_cell(&mut self, state: &Maze2DState, cell: Maze2DCell) {
+        debug_assert!(self.valid(state));
+        self.map[state.y.get() as usize][state.x.get() as usize] = cell;
     }
 
     pub fn dimensions(&self) -> (usize, usize) {
@@ -154,7 +370,7 @@ impl Maze2DSpace {
         (self.map[0].len(), self.map.len())
     }
     #[inline(always)]
-    fn at(&self, state: &Maze2DState) -> Maze2DCell {
+    pub(crate) fn at(&self, state: &Maze2DState) -> Maze2DCell {
         debug_assert!(self.valid(state));
         unsafe {
             *self
@@ -164,50 +380,108 @@ impl Maze2DSpace {
         }
     }
 
-    pub fn supports_random_state() -> bool {
-        true
-    }
-    pub fn random_state<R: rand::Rng>(&self, r: &mut R) -> Option<Maze2DState> {
-        let (max_x, max_y) = self.dimensions();
-        let max_x = max_x as CoordIntrinsic;
-        let max_y = max_y as CoordIntrinsic;
+    /// Drops the states of a path that can be skipped by walking in a straight
+    /// line between the ones around them.
+    ///
+    /// Actions only move to adjacent cells, so the smoothed path is returned
+    /// as the states it goes through, starting and ending like the original
+    /// path. Empty paths have no states.
+    #[must_use]
+    pub fn smooth_path(&self, p: &Path<Maze2DState, Maze2DAction, Maze2DCost>) -> Vec<Maze2DState> {
+        let states: Vec<Maze2DState> = p.states(self).collect();
+        let Some((&last, _)) = states.split_last() else {
+            return vec![];
+        };
 
-        for _tries in 0..RANDOM_STATE_MAX_TRIES {
-            let x = r.random::<CoordIntrinsic>() % (max_x);
-            let y = r.random::<CoordIntrinsic>() % (max_y);
-            assert!(x < max_x);
-            assert!(y < max_y);
-            if self.map[y as usize][x as usize] == Maze2DCell::Empty {
-                return Maze2DState::new(x, y);
+        let mut smoothed = vec![states[0]];
+        for window in states.windows(2).skip(1) {
+            let anchor = *smoothed.last().unwrap();
+            if !self.line_of_sight(&anchor, &window[1]) {
+                smoothed.push(window[0]);
             }
         }
+        if states.len() > 1 {
+            smoothed.push(last);
+        }
+        smoothed
+    }
 
-        None
+    /// Checks whether the cells along the Bresenham line between two cells
+    /// are all empty.
+    ///
+    /// On 4-connected mazes diagonal steps along the line also need one of the
+    /// cells they cut through to be empty.
+    #[must_use]
+    fn line_of_sight(&self, a: &Maze2DState, b: &Maze2DState) -> bool {
+        let (mut x, mut y) = (a.x.get() as i64, a.y.get() as i64);
+        let (x1, y1) = (b.x.get() as i64, b.y.get() as i64);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let empty = |x: i64, y: i64| self.map[y as usize][x as usize] == Maze2DCell::Empty;
+
+        let mut err = dx + dy;
+        loop {
+            if !empty(x, y) {
+                return false;
+            }
+            if (x, y) == (x1, y1) {
+                return true;
+            }
+
+            let e2 = 2 * err;
+            let step_x = e2 >= dy;
+            let step_y = e2 <= dx;
+            if step_x
+                && step_y
+                && self.connectivity == Connectivity::Four
+                && !empty(x + sx, y)
+                && !empty(x, y + sy)
+            {
+                return false;
+            }
+            if step_x {
+                err += dy;
+                x += sx;
+            }
+            if step_y {
+                err += dx;
+                y += sy;
+            }
+        }
     }
 }
 
 impl Space<Maze2DState, Maze2DAction, Maze2DCost> for Maze2DSpace {
     #[inline(always)]
     fn apply(&self, state: &Maze2DState, action: &Maze2DAction) -> Option<Maze2DState> {
+        if self.connectivity == Connectivity::Four && action.is_diagonal() {
+            return None;
+        }
+
         let x = state.x.get();
         let y = state.y.get();
 
+        let prev = CoordIntrinsic::MAX;
+        let same = 0 as CoordIntrinsic;
+        let next = 1 as CoordIntrinsic;
+
         #[rustfmt::skip]
-        let (x, y) = match action {
-            Maze2DAction::Up        => (x,     y + 1),
-            Maze2DAction::Down      => (x,     y - 1),
-            Maze2DAction::Left      => (x - 1, y    ),
-            Maze2DAction::Right     => (x + 1, y    ),
-            Maze2DAction::LeftUp    => (x - 1, y + 1),
-            Maze2DAction::RightUp   => (x + 1, y + 1),
-            Maze2DAction::LeftDown  => (x - 1, y - 1),
-            Maze2DAction::RightDown => (x + 1, y - 1),
+        let (dx, dy) = match action {
+            Maze2DAction::Up        => (same, next),
+            Maze2DAction::Down      => (same, prev),
+            Maze2DAction::Left      => (prev, same),
+            Maze2DAction::Right     => (next, same),
+            Maze2DAction::LeftUp    => (prev, next),
+            Maze2DAction::RightUp   => (next, next),
+            Maze2DAction::LeftDown  => (prev, prev),
+            Maze2DAction::RightDown => (next, prev),
         };
 
-        Some(Maze2DState {
-            x: Coord::new(x)?,
-            y: Coord::new(y)?,
-        })
+        let s = Maze2DState {
+            x: Coord::new(x.wrapping_add(dx))?,
+            y: Coord::new(y.wrapping_add(dy))?,
+        };
+        (self.valid(&s) && self.at(&s) != Maze2DCell::Wall).then_some(s)
     }
 
     #[inline(always)]
@@ -218,16 +492,23 @@ impl Space<Maze2DState, Maze2DAction, Maze2DCost> for Maze2DSpace {
         state.x.get() < max_x && state.y.get() < max_y
     }
 
+    /// Every cell is a state, even walls.
     #[inline(always)]
-    fn cost(&self, _s: &Maze2DState, a: &Maze2DAction) -> Maze2DCost {
-        debug_assert!(Maze2DAction::Up < Maze2DAction::Right);
-        debug_assert!(Maze2DAction::Down < Maze2DAction::Right);
-        debug_assert!(Maze2DAction::Left < Maze2DAction::Right);
+    fn size(&self) -> Option<usize> {
+        let (max_x, max_y) = self.dimensions();
+        Some(max_x * max_y)
+    }
 
-        if *a <= Maze2DAction::Right {
-            ORTHOGONAL_COST
-        } else {
-            DIAGONAL_COST
+    /// The cost of moving, scaled by the terrain of the destination cell.
+    #[inline(always)]
+    fn cost(&self, s: &Maze2DState, a: &Maze2DAction) -> Maze2DCost {
+        let move_cost = self.move_cost(a);
+        if self.terrain.is_none() {
+            return move_cost;
+        }
+        match self.apply(s, a) {
+            Some(destination) => move_cost.saturating_mul(self.terrain(&destination).weight()),
+            None => move_cost,
         }
     }
 
@@ -235,10 +516,19 @@ impl Space<Maze2DState, Maze2DAction, Maze2DCost> for Maze2DSpace {
     ///
     /// NOTE: These states can only be used with the current Maze
     fn neighbours(&self, state: &Maze2DState) -> Vec<(Maze2DState, Maze2DAction)> {
+        let mut v = Vec::<(Maze2DState, Maze2DAction)>::with_capacity(8);
+        self.for_each_neighbour(state, |s, a| v.push((s, a)));
+        v
+    }
+
+    fn for_each_neighbour(
+        &self,
+        state: &Maze2DState,
+        mut f: impl FnMut(Maze2DState, Maze2DAction),
+    ) {
         #[cfg(feature = "coz_profile")]
         coz::scope!("StateExpansion");
 
-        let mut v = Vec::<(Maze2DState, Maze2DAction)>::with_capacity(8);
         let (max_x, max_y) = self.dimensions();
         debug_assert!(max_x < CoordIntrinsic::MAX as usize);
         debug_assert!(max_y < CoordIntrinsic::MAX as usize);
@@ -248,6 +538,7 @@ impl Space<Maze2DState, Maze2DAction, Maze2DCost> for Maze2DSpace {
         let same = 0 as CoordIntrinsic;
         let next = 1 as CoordIntrinsic;
 
+        let diagonals = self.connectivity == Connectivity::Eight;
         for (dx, dy, action) in [
             // Left
             (prev, prev, Maze2DAction::LeftDown),
@@ -262,6 +553,9 @@ impl Space<Maze2DState, Maze2DAction, Maze2DCost> for Maze2DSpace {
             (next, same, Maze2DAction::Right),
             (next, next, Maze2DAction::RightUp),
         ] {
+            if !diagonals && action.is_diagonal() {
+                continue;
+            }
             let new_x = state.x.get().wrapping_add(dx);
             let new_y = state.y.get().wrapping_add(dy);
             if new_x < max_x && new_y < max_y {
@@ -271,11 +565,48 @@ impl Space<Maze2DState, Maze2DAction, Maze2DCost> for Maze2DSpace {
                 };
                 debug_assert!(self.valid(&s));
                 if self.at(&s) != Maze2DCell::Wall {
-                    v.push((s, action));
+                    f(s, action);
                 }
             }
         }
-        v
+    }
+}
+
+impl SampleableSpace<Maze2DState, Maze2DAction, Maze2DCost> for Maze2DSpace {
+    fn random_state<R: rand::Rng>(&self, r: &mut R) -> Option<Maze2DState> {
+        let (max_x, max_y) = self.dimensions();
+        let max_x = max_x as CoordIntrinsic;
+        let max_y = max_y as CoordIntrinsic;
+
+        for _tries in 0..RANDOM_STATE_MAX_TRIES {
+            let x = r.random::<CoordIntrinsic>() % (max_x);
+            let y = r.random::<CoordIntrinsic>() % (max_y);
+            assert!(x < max_x);
+            assert!(y < max_y);
+            if self.map[y as usize][x as usize] == Maze2DCell::Empty {
+                return Maze2DState::new(x, y);
+            }
+        }
+
+        None
+    }
+}
+
+impl ReversibleSpace<Maze2DState, Maze2DAction, Maze2DCost> for Maze2DSpace {
+    /// Gets the states that can reach a given position.
+    ///
+    /// Moves are symmetric, so these are the neighbours reached with the
+    /// opposite actions.
+    fn reverse_neighbours(&self, state: &Maze2DState) -> Vec<(Maze2DState, Maze2DAction)> {
+        self.neighbours(state)
+            .into_iter()
+            .map(|(s, a)| (s, a.opposite()))
+            .collect()
+    }
+
+    #[cfg(feature = "partial_reverse")]
+    fn partial_reverse_neighbours(&self, state: &Maze2DState) -> Vec<(Maze2DState, Maze2DAction)> {
+        self.reverse_neighbours(state)
     }
 }
 
@@ -309,6 +640,7 @@ pub enum Maze2DSpaceParseError {
         p: std::path::PathBuf,
         e: std::io::Error,
     },
+    #[cfg(feature = "image")]
     #[error("Image error when loading '{p}': {e}")]
     ImageError {
         p: std::path::PathBuf,
@@ -316,6 +648,7 @@ pub enum Maze2DSpaceParseError {
     },
 }
 
+#[cfg(feature = "image")]
 impl std::convert::TryFrom<&std::path::Path> for Maze2DSpace {
     type Error = Maze2DSpaceParseError;
 
@@ -344,6 +677,11 @@ impl std::convert::TryFrom<&std::path::Path> for Maze2DSpace {
                 space.map[y as usize][x as usize] = match px.0 {
                     BLACK => Maze2DCell::Wall,
                     WHITE => Maze2DCell::Empty,
+                    [r, g, b] if r == g && g == b => {
+                        let s = Maze2DState::new(x, y).unwrap();
+                        space.set_terrain(&s, Maze2DTerrain::from_grey(r));
+                        Maze2DCell::Empty
+                    }
                     _ => {
                         log::warn!("Unexpected colour {px:?} at ({x},{y})");
                         Maze2DCell::Empty
@@ -363,6 +701,92 @@ pub struct Maze2DProblem {
     goals: Vec<Maze2DState>,
 }
 
+/// Editing
+///
+/// These return whether the problem changed, so callers know when to re-run
+/// their searches.
+impl Maze2DProblem {
+    /// Toggles a cell between `Empty` and `Wall`.
+    ///
+    /// Starts and goals turned into walls are dropped.
+    pub fn toggle_wall(&mut self, s: &Maze2DState) -> bool {
+        if !self.space.valid(s) {
+            return false;
+        }
+        let cell = match self.space.at(s) {
+            Maze2DCell::Empty => Maze2DCell::Wall,
+            Maze2DCell::Wall => Maze2DCell::Empty,
+        };
+        self.set_cell(s, cell)
+    }
+
+    /// Sets a cell to `Empty` or `Wall`, dropping the starts and goals it
+    /// walls off.
+    pub fn set_cell(&mut self, s: &Maze2DState, cell: Maze2DCell) -> bool {
+        if !self.space.valid(s) || self.space.at(s) == cell {
+            return false;
+        }
+        self.space.set_cell(s, cell);
+        if cell == Maze2DCell::Wall {
+            self.starts.retain(|start| start != s);
+            self.goals.retain(|goal| goal != s);
+        }
+        true
+    }
+
+    /// Adds a start on an empty cell.
+    pub fn add_start(&mut self, s: &Maze2DState) -> bool {
+        if !self.space.valid(s) || self.space.at(s) == Maze2DCell::Wall || self.starts.contains(s) {
+            return false;
+        }
+        self.starts.push(*s);
+        true
+    }
+
+    pub fn remove_start(&mut self, s: &Maze2DState) -> bool {
+        let len = self.starts.len();
+        self.starts.retain(|start| start != s);
+        self.starts.len() != len
+    }
+
+    /// Adds a goal on an empty cell.
+    pub fn add_goal(&mut self, s: &Maze2DState) -> bool {
+        if !self.space.valid(s) || self.space.at(s) == Maze2DCell::Wall || self.goals.contains(s) {
+            return false;
+        }
+        self.goals.push(*s);
+        true
+    }
+
+    pub fn remove_goal(&mut self, s: &Maze2DState) -> bool {
+        let len = self.goals.len();
+        self.goals.retain(|goal| goal != s);
+        self.goals.len() != len
+    }
+
+    /// Sets the terrain of a cell.
+    pub fn set_terrain(&mut self, s: &Maze2DState, terrain: Maze2DTerrain) -> bool {
+        if !self.space.valid(s) || self.space.terrain(s) == terrain {
+            return false;
+        }
+        self.space.set_terrain(s, terrain);
+        true
+    }
+
+    /// Removes all walls, terrain, starts and goals keeping the dimensions.
+    pub fn clear(&mut self) -> bool {
+        let mut changed = !self.starts.is_empty() || !self.goals.is_empty();
+        self.starts.clear();
+        self.goals.clear();
+        for cell in self.space.map.iter_mut().flatten() {
+            changed |= *cell == Maze2DCell::Wall;
+            *cell = Maze2DCell::Empty;
+        }
+        changed |= self.space.terrain.take().is_some();
+        changed
+    }
+}
+
 impl BaseProblem<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Maze2DProblem {
     fn space(&self) -> &Maze2DSpace {
         &self.space
@@ -406,6 +830,101 @@ impl ObjectiveProblem<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Ma
     }
 }
 
+impl Maze2DProblem {
+    /// Sets the cost of orthogonal and diagonal moves, before terrain.
+    #[must_use]
+    pub fn with_costs(mut self, orthogonal_cost: Maze2DCost, diagonal_cost: Maze2DCost) -> Self {
+        self.space = self.space.with_costs(orthogonal_cost, diagonal_cost);
+        self
+    }
+
+    /// Sets the moves allowed between cells.
+    #[must_use]
+    pub fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.space = self.space.with_connectivity(connectivity);
+        self
+    }
+
+    /// Creates a random problem with goals far away from the starts.
+    ///
+    /// Starts are placed randomly, but goals are picked among the farthest
+    /// cells reachable from them (by a bounded uniform-cost exploration). This
+    /// avoids the trivial instances that uniform placement often produces.
+    #[must_use]
+    pub fn randomize_hard<R: rand::Rng>(
+        &self,
+        r: &mut R,
+        num_starts: u16,
+        num_goals: u16,
+    ) -> Option<Maze2DProblem> {
+        use std::cmp::Reverse;
+        use std::collections::BinaryHeap;
+
+        use rand::seq::IndexedRandom;
+        use rustc_hash::FxHashMap;
+
+        let mut starts = vec![];
+        for _tries in 0..RANDOM_STATE_MAX_TRIES {
+            if starts.len() == num_starts as usize {
+                break;
+            }
+            if let Some(random_state) = self.space.random_state::<R>(r) {
+                starts.push(random_state);
+            }
+        }
+        if starts.len() < num_starts as usize {
+            return None;
+        }
+
+        // Uniform-cost exploration from all starts
+        let mut distances = FxHashMap::<Maze2DState, Maze2DCost>::default();
+        let mut open = BinaryHeap::<Reverse<(Maze2DCost, CoordIntrinsic, CoordIntrinsic)>>::new();
+        for s in &starts {
+            distances.insert(*s, 0);
+            open.push(Reverse((0, s.x.get(), s.y.get())));
+        }
+        let mut expansions = 0usize;
+        while let Some(Reverse((g, x, y))) = open.pop() {
+            let s = Maze2DState::new(x, y).unwrap();
+            if distances[&s] < g {
+                continue;
+            }
+            expansions += 1;
+            if expansions > HARD_RANDOMIZE_MAX_EXPANSIONS {
+                break;
+            }
+            for (n, a) in self.space.neighbours(&s) {
+                let n_g = g.saturating_add(self.space.cost(&s, &a));
+                if distances.get(&n).is_none_or(|&d| n_g < d) {
+                    distances.insert(n, n_g);
+                    open.push(Reverse((n_g, n.x.get(), n.y.get())));
+                }
+            }
+        }
+
+        let mut reached: Vec<(Maze2DCost, Maze2DState)> = distances
+            .into_iter()
+            .filter(|(_s, d)| *d > 0)
+            .map(|(s, d)| (d, s))
+            .collect();
+        if reached.len() < num_goals as usize {
+            return None;
+        }
+        reached.sort_unstable_by_key(|(d, s)| Reverse((*d, s.x.get(), s.y.get())));
+        let pool_len = (reached.len() / HARD_RANDOMIZE_FARTHEST_FRACTION).max(num_goals as usize);
+        let goals = reached[..pool_len]
+            .choose_multiple(r, num_goals as usize)
+            .map(|(_d, s)| *s)
+            .collect();
+
+        Some(Maze2DProblem {
+            space: self.space.clone(),
+            starts,
+            goals,
+        })
+    }
+}
+
 #[derive(Copy, Clone, Debug, Display, PartialEq)]
 pub enum Maze2DProblemCell {
     Cell(Maze2DCell),
@@ -447,11 +966,27 @@ pub enum Maze2DProblemParseError {
         x: usize,
         y: usize,
     },
+    #[error("Row {y} has {len} cells, but the first one has {expected}")]
+    RaggedRow {
+        y: usize,
+        len: usize,
+        expected: usize,
+    },
     #[error("I/O error when loading '{p}': {e}")]
     IOError {
         p: std::path::PathBuf,
         e: std::io::Error,
     },
+    #[error("The problem has no starts")]
+    NoStarts,
+    #[error("The problem has no goals")]
+    NoGoals,
+    #[error("'{p}' is not valid UTF-8: {e}")]
+    InvalidUtf8 {
+        p: std::path::PathBuf,
+        e: std::string::FromUtf8Error,
+    },
+    #[cfg(feature = "image")]
     #[error("Image error when loading '{p}': {e}")]
     ImageError {
         p: std::path::PathBuf,
@@ -485,8 +1020,18 @@ impl std::convert::TryFrom<&str> for Maze2DProblem {
             return Err(Maze2DProblemParseError::EmptyInput);
         }
 
-        let max_x = lines[0].len();
+        let max_x = lines[0].chars().count();
         let max_y = lines.len();
+        for (y, line) in lines.iter().enumerate() {
+            let len = line.chars().count();
+            if len != max_x {
+                return Err(Maze2DProblemParseError::RaggedRow {
+                    y,
+                    len,
+                    expected: max_x,
+                });
+            }
+        }
         debug_assert!(max_x < CoordIntrinsic::MAX as usize);
         debug_assert!(max_y < CoordIntrinsic::MAX as usize);
         let mut problem = Maze2DProblem {
@@ -524,6 +1069,39 @@ impl std::convert::TryFrom<&str> for Maze2DProblem {
     }
 }
 
+impl Maze2DProblem {
+    /// Loads an ASCII maze from a text file.
+    ///
+    /// `TryFrom<&Path>` reads images, text files go through here instead.
+    pub fn from_text_file(p: &std::path::Path) -> Result<Self, Maze2DProblemParseError> {
+        let bytes = std::fs::read(p).map_err(|e| Maze2DProblemParseError::IOError {
+            p: p.to_path_buf(),
+            e,
+        })?;
+        let text = String::from_utf8(bytes).map_err(|e| Maze2DProblemParseError::InvalidUtf8 {
+            p: p.to_path_buf(),
+            e,
+        })?;
+
+        Maze2DProblem::try_from(text.as_str())
+    }
+
+    /// Checks that the problem has starts and goals.
+    ///
+    /// Loading doesn't check this, as maps without them are still useful as a
+    /// base for `randomize`.
+    pub fn validate(&self) -> Result<(), Maze2DProblemParseError> {
+        if self.starts.is_empty() {
+            return Err(Maze2DProblemParseError::NoStarts);
+        }
+        if self.goals.is_empty() {
+            return Err(Maze2DProblemParseError::NoGoals);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "image")]
 impl std::convert::TryFrom<&std::path::Path> for Maze2DProblem {
     type Error = Maze2DProblemParseError;
 
@@ -564,6 +1142,11 @@ impl std::convert::TryFrom<&std::path::Path> for Maze2DProblem {
                 p.space.map[y as usize][x as usize] = match px {
                     BLACK => Maze2DCell::Wall,
                     WHITE => Maze2DCell::Empty,
+                    [r, g, b] if r == g && g == b => {
+                        let s = Maze2DState::new(x, y).unwrap();
+                        p.space.set_terrain(&s, Maze2DTerrain::from_grey(r));
+                        Maze2DCell::Empty
+                    }
                     GREEN => {
                         // GREEN (goal)
                         p.goals.push(Maze2DState {
@@ -580,6 +1163,10 @@ impl std::convert::TryFrom<&std::path::Path> for Maze2DProblem {
                         });
                         Maze2DCell::Empty
                     }
+                    YELLOW => {
+                        // YELLOW (solution path)
+                        Maze2DCell::Empty
+                    }
                     RED => {
                         // RED (start & goal)
 
@@ -616,6 +1203,70 @@ impl std::fmt::Display for Maze2DProblem {
             "Maze2DProblem({}x{}) (s:{:?}, g:{:?}):",
             d.0, d.1, self.starts, self.goals
         )?;
+        self.write_map(f, &[])
+    }
+}
+
+/// Rendering
+impl Maze2DProblem {
+    /// Renders the map with the cells along a path marked with `*`.
+    ///
+    /// Starts and goals keep their markers. Like `Display`, this only shows
+    /// the first `MAX_ELEMENTS_DISPLAYED` rows and columns.
+    #[must_use]
+    pub fn render_path(&self, path: &Path<Maze2DState, Maze2DAction, Maze2DCost>) -> String {
+        let path_states: Vec<Maze2DState> = path.states(&self.space).collect();
+
+        let mut rendered = String::new();
+        self.write_map(&mut rendered, &path_states).unwrap();
+        rendered
+    }
+
+    /// Saves the map as an image with the cells along a path in yellow.
+    ///
+    /// Uses the colours `TryFrom<&Path>` reads, so loading the image back
+    /// gives the same problem.
+    #[cfg(feature = "image")]
+    pub fn save_solution_png(
+        &self,
+        path: &Path<Maze2DState, Maze2DAction, Maze2DCost>,
+        out: &std::path::Path,
+    ) -> Result<(), image::ImageError> {
+        use image::Rgb;
+        use image::RgbImage;
+
+        let path_states: Vec<Maze2DState> = path.states(&self.space).collect();
+
+        let (max_x, max_y) = self.space.dimensions();
+        let mut img = RgbImage::new(max_x as u32, max_y as u32);
+        for (y, line) in self.space.map.iter().enumerate() {
+            for (x, cell) in line.iter().enumerate() {
+                let s = Maze2DState::new_from_small_usize(x, y);
+
+                let is_start = self.starts.contains(&s);
+                let is_goal = self.goals.contains(&s);
+
+                let px = match (is_start, is_goal) {
+                    (true, true) => RED,
+                    (true, false) => BLUE,
+                    (false, true) => GREEN,
+                    (false, false) if path_states.contains(&s) => YELLOW,
+                    (false, false) => match cell {
+                        Maze2DCell::Wall => BLACK,
+                        Maze2DCell::Empty => {
+                            let grey = self.space.terrain(&s).to_grey();
+                            [grey, grey, grey]
+                        }
+                    },
+                };
+                img.put_pixel(x as u32, y as u32, Rgb(px));
+            }
+        }
+
+        img.save(out)
+    }
+
+    fn write_map<W: std::fmt::Write>(&self, w: &mut W, path: &[Maze2DState]) -> std::fmt::Result {
         let map = &self.space.map;
         for (y, line) in map.iter().enumerate().take(MAX_ELEMENTS_DISPLAYED) {
             for (x, cell) in line.iter().enumerate().take(MAX_ELEMENTS_DISPLAYED) {
@@ -626,20 +1277,23 @@ impl std::fmt::Display for Maze2DProblem {
 
                 match (is_start, is_goal) {
                     (true, true) => {
-                        write!(f, "!")?;
+                        write!(w, "!")?;
                     }
                     (true, false) => {
-                        write!(f, "S")?;
+                        write!(w, "S")?;
                     }
                     (false, true) => {
-                        write!(f, "G")?;
+                        write!(w, "G")?;
+                    }
+                    (false, false) if path.contains(&s) => {
+                        write!(w, "*")?;
                     }
                     (false, false) => {
-                        write!(f, "{cell}")?;
+                        write!(w, "{cell}")?;
                     }
                 }
             }
-            writeln!(f)?;
+            writeln!(w)?;
         }
 
         Ok(())
@@ -653,7 +1307,7 @@ pub struct Maze2DHeuristicManhattanDistance;
 impl ObjectiveHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost>
     for Maze2DHeuristicManhattanDistance
 {
-    /// The distance of following straight lines
+    /// The distance of following straight lines, at the default costs
     #[inline(always)]
     fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
         let [min_x, max_x] = std::cmp::minmax(a.x.get(), b.x.get());
@@ -663,6 +1317,13 @@ impl ObjectiveHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost>
 
         (delta_x + delta_y) * ORTHOGONAL_COST
     }
+
+    /// The distance of following straight lines, at the costs of the space
+    #[inline(always)]
+    fn h_in(space: &Maze2DSpace, a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        let moves = a.x.get().abs_diff(b.x.get()) + a.y.get().abs_diff(b.y.get());
+        moves.saturating_mul(space.orthogonal_cost())
+    }
 }
 
 #[derive(Debug)]
@@ -672,7 +1333,7 @@ pub struct Maze2DHeuristicDiagonalDistance;
 impl ObjectiveHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost>
     for Maze2DHeuristicDiagonalDistance
 {
-    /// The distance of maximizing useful diagonals
+    /// The distance of maximizing useful diagonals, at the default costs
     #[inline(always)]
     fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
         let [min_x, max_x] = std::cmp::minmax(a.x.get(), b.x.get());
@@ -686,4 +1347,983 @@ impl ObjectiveHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost>
         let orthogonal_cost = (delta_max - delta_min) * ORTHOGONAL_COST;
         orthogonal_cost + diagonal_cost
     }
+
+    /// The distance of maximizing useful diagonals, at the costs of the space
+    #[inline(always)]
+    fn h_in(space: &Maze2DSpace, a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        space.distance_estimate(a, b)
+    }
+}
+
+impl DistanceHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost>
+    for Maze2DHeuristicDiagonalDistance
+{
+    /// The number of moves when diagonals are free to take.
+    #[inline(always)]
+    fn d(a: &Maze2DState, b: &Maze2DState) -> usize {
+        let delta_x = a.x.get().abs_diff(b.x.get());
+        let delta_y = a.y.get().abs_diff(b.y.get());
+        std::cmp::max(delta_x, delta_y) as usize
+    }
+}
+
+/// An axis-aligned rectangle of cells (bounds are inclusive).
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq)]
+#[display("[{min}..{max}]")]
+pub struct Maze2DRegion {
+    pub min: Maze2DState,
+    pub max: Maze2DState,
+}
+
+impl Maze2DRegion {
+    #[must_use]
+    pub fn new(a: Maze2DState, b: Maze2DState) -> Self {
+        let [min_x, max_x] = std::cmp::minmax(a.x, b.x);
+        let [min_y, max_y] = std::cmp::minmax(a.y, b.y);
+        Self {
+            min: Maze2DState { x: min_x, y: min_y },
+            max: Maze2DState { x: max_x, y: max_y },
+        }
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn contains(&self, s: &Maze2DState) -> bool {
+        self.min.x <= s.x && s.x <= self.max.x && self.min.y <= s.y && s.y <= self.max.y
+    }
+
+    /// The cell of the region closest to a given one.
+    #[inline(always)]
+    #[must_use]
+    pub fn closest(&self, s: &Maze2DState) -> Maze2DState {
+        Maze2DState {
+            x: s.x.clamp(self.min.x, self.max.x),
+            y: s.y.clamp(self.min.y, self.max.y),
+        }
+    }
+}
+
+/// A Maze2D problem where the goal is reaching any cell in a region.
+#[derive(Clone, Debug)]
+pub struct Maze2DRegionProblem {
+    space: Maze2DSpace,
+    starts: Vec<Maze2DState>,
+    region: Maze2DRegion,
+}
+
+impl Maze2DRegionProblem {
+    #[must_use]
+    pub fn new(space: Maze2DSpace, starts: Vec<Maze2DState>, region: Maze2DRegion) -> Self {
+        Self {
+            space,
+            starts,
+            region,
+        }
+    }
+
+    #[must_use]
+    pub fn region(&self) -> &Maze2DRegion {
+        &self.region
+    }
+}
+
+impl BaseProblem<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Maze2DRegionProblem {
+    fn space(&self) -> &Maze2DSpace {
+        &self.space
+    }
+    fn starts(&self) -> &[Maze2DState] {
+        &self.starts
+    }
+}
+
+impl ConditionProblem<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Maze2DRegionProblem {
+    #[inline(always)]
+    fn is_goal(&self, s: &Maze2DState) -> bool {
+        self.region.contains(s)
+    }
+}
+
+/// A Maze2D problem where the goal is reaching any of its goals, or escaping
+/// through any border cell.
+#[derive(Clone, Debug)]
+pub struct Maze2DEscapeProblem(pub Maze2DProblem);
+
+impl BaseProblem<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Maze2DEscapeProblem {
+    fn space(&self) -> &Maze2DSpace {
+        self.0.space()
+    }
+    fn starts(&self) -> &[Maze2DState] {
+        self.0.starts()
+    }
+}
+
+impl ObjectiveProblem<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Maze2DEscapeProblem {
+    fn goals(&self) -> &[Maze2DState] {
+        self.0.goals()
+    }
+    fn randomize<R: rand::Rng>(
+        &mut self,
+        r: &mut R,
+        num_starts: u16,
+        num_goals: u16,
+    ) -> Option<Self> {
+        self.0.randomize(r, num_starts, num_goals).map(Self)
+    }
+}
+
+impl ConditionProblem<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Maze2DEscapeProblem {
+    #[inline(always)]
+    fn is_goal(&self, s: &Maze2DState) -> bool {
+        let (max_x, max_y) = self.space().dimensions();
+        let (x, y) = (s.x.get() as usize, s.y.get() as usize);
+        x == 0 || y == 0 || x + 1 == max_x || y + 1 == max_y
+    }
+}
+
+impl MixedProblem<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost> for Maze2DEscapeProblem {}
+
+/// The diagonal distance to the closest cell of a region.
+///
+/// NOTE: `ConditionHeuristic::h` can't see the problem, so the region is set
+/// through the type.
+#[derive(Debug)]
+#[cfg_attr(feature = "inspect", derive(Clone))]
+pub struct Maze2DHeuristicRegionDistance<
+    const MIN_X: u32,
+    const MIN_Y: u32,
+    const MAX_X: u32,
+    const MAX_Y: u32,
+>;
+
+impl<const MIN_X: u32, const MIN_Y: u32, const MAX_X: u32, const MAX_Y: u32>
+    Maze2DHeuristicRegionDistance<MIN_X, MIN_Y, MAX_X, MAX_Y>
+{
+    #[must_use]
+    pub fn region() -> Maze2DRegion {
+        Maze2DRegion::new(
+            Maze2DState::new(MIN_X, MIN_Y).unwrap(),
+            Maze2DState::new(MAX_X, MAX_Y).unwrap(),
+        )
+    }
+}
+
+impl<const MIN_X: u32, const MIN_Y: u32, const MAX_X: u32, const MAX_Y: u32>
+    ConditionHeuristic<Maze2DSpace, Maze2DState, Maze2DAction, Maze2DCost>
+    for Maze2DHeuristicRegionDistance<MIN_X, MIN_Y, MAX_X, MAX_Y>
+{
+    #[inline(always)]
+    fn h(s: &Maze2DState) -> Maze2DCost {
+        <Maze2DHeuristicDiagonalDistance as ObjectiveHeuristic<Maze2DSpace, _, Maze2DAction, _>>::h(
+            s,
+            &Self::region().closest(s),
+        )
+    }
+}
+
+/// Solves a text maze, returning it with the path drawn using `*`.
+///
+/// This only relies on the text format, so it doesn't need file or image I/O
+/// and can be used on targets like `wasm32-unknown-unknown`.
+#[must_use]
+pub fn solve_ascii_maze(maze: &str) -> Option<String> {
+    use crate::algorithms::astar::AStarSearch;
+
+    let problem = Maze2DProblem::try_from(maze).ok()?;
+    let space = problem.space().clone();
+    let mut search = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+    let path = search.find_next_goal()?;
+
+    let mut lines: Vec<Vec<char>> = maze.lines().map(|l| l.chars().collect()).collect();
+    // Keep the start marker
+    for (s, _a, _c) in path.steps(&space).skip(1) {
+        lines[s.y.get() as usize][s.x.get() as usize] = '*';
+    }
+
+    let mut solution = String::with_capacity(maze.len());
+    for line in lines {
+        solution.extend(line);
+        solution.push('\n');
+    }
+    Some(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+    use rustc_hash::FxHashSet;
+
+    #[test]
+    fn solve_ascii() {
+        let maze = indoc! {"
+            #####
+            #S###
+            #.###
+            #..G#
+            #####
+        "};
+        let solution = solve_ascii_maze(maze).unwrap();
+        assert_eq!(
+            solution,
+            indoc! {"
+                #####
+                #S###
+                #*###
+                #.*G#
+                #####
+            "}
+        );
+    }
+
+    #[test]
+    fn edit() {
+        let mut problem = Maze2DProblem::try_from(indoc! {"
+            S.#
+            ..G
+        "})
+        .unwrap();
+        let start = Maze2DState::new(0, 0).unwrap();
+        let wall = Maze2DState::new(2, 0).unwrap();
+        let goal = Maze2DState::new(2, 1).unwrap();
+        let outside = Maze2DState::new(3, 0).unwrap();
+
+        assert!(!problem.add_start(&start));
+        assert!(!problem.add_start(&wall));
+        assert!(!problem.add_goal(&outside));
+        assert!(!problem.toggle_wall(&outside));
+
+        assert!(problem.toggle_wall(&goal));
+        assert!(problem.goals().is_empty());
+        assert!(problem.toggle_wall(&wall));
+        assert!(problem.add_goal(&wall));
+        assert_eq!(problem.goals(), &[wall]);
+
+        assert!(problem.remove_start(&start));
+        assert!(!problem.remove_start(&start));
+        assert!(problem.remove_goal(&wall));
+
+        assert!(problem.clear());
+        assert!(!problem.clear());
+    }
+
+    #[test]
+    fn replan() {
+        use crate::algorithms::dijkstra::DijkstraSearch;
+
+        let mut problem = Maze2DProblem::try_from(indoc! {"
+            S.....G
+            .#####.
+            .......
+        "})
+        .unwrap();
+        let path = DijkstraSearch::new(problem.clone())
+            .find_next_goal()
+            .unwrap();
+        let corridor = Maze2DState::new(3, 0).unwrap();
+        assert!(path.states(problem.space()).any(|s| s == corridor));
+
+        assert!(problem.set_cell(&corridor, Maze2DCell::Wall));
+        assert!(!problem.set_cell(&corridor, Maze2DCell::Wall));
+        assert_eq!(problem.space().at(&corridor), Maze2DCell::Wall);
+
+        let detour = DijkstraSearch::new(problem.clone())
+            .find_next_goal()
+            .unwrap();
+        assert!(problem.space().valid_path(&detour));
+        assert!(detour.states(problem.space()).all(|s| s != corridor));
+        assert!(detour.cost() > path.cost());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn randomize_hard() {
+        use std::path::PathBuf;
+
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        use crate::algorithms::astar::AStarSearch;
+
+        fn cost(p: Maze2DProblem) -> Maze2DCost {
+            let mut search = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(p);
+            search.find_next_goal().unwrap().cost()
+        }
+
+        let mut problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+        let mut uniform_total = 0;
+        let mut hard_total = 0;
+        for _ in 0..20 {
+            let hard = problem.randomize_hard(&mut rng, 1, 1).unwrap();
+            assert_eq!(hard.starts().len(), 1);
+            assert_eq!(hard.goals().len(), 1);
+            hard_total += cost(hard);
+            uniform_total += cost(problem.randomize(&mut rng, 1, 1).unwrap());
+        }
+        assert!(hard_total > uniform_total);
+    }
+
+    #[test]
+    fn randomize_hard_huge_costs() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        let huge = Maze2DCost::MAX / 2;
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.....
+            ......
+            .....G
+        "})
+        .unwrap()
+        .with_costs(huge, huge);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+        // Distances saturate instead of overflowing.
+        let hard = problem.randomize_hard(&mut rng, 1, 1).unwrap();
+        assert_eq!(hard.goals().len(), 1);
+    }
+
+    #[test]
+    fn path_shape() {
+        use crate::space::Path;
+
+        let s = Maze2DState::default();
+        let mut path = Path::<Maze2DState, Maze2DAction, Maze2DCost>::with_capacity(s, 5);
+        assert_eq!(path.turn_count(), 0);
+        for a in [
+            Maze2DAction::Right,
+            Maze2DAction::Right,
+            Maze2DAction::RightUp,
+            Maze2DAction::RightUp,
+            Maze2DAction::Up,
+        ] {
+            path.append((s, a), 1);
+        }
+        assert_eq!(path.turn_count(), 2);
+        assert_eq!(path.diagonal_step_count(), 2);
+    }
+
+    #[test]
+    fn path_steps() {
+        use crate::space::Path;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            ...
+            .#.
+        "})
+        .unwrap();
+        let space = problem.space();
+        let s = Maze2DState::new(0, 0).unwrap();
+        let right = Maze2DState::new(1, 0).unwrap();
+
+        let mut path = Path::<Maze2DState, Maze2DAction, Maze2DCost>::with_capacity(s, 2);
+        path.append((right, Maze2DAction::Right), ORTHOGONAL_COST);
+        assert_eq!(
+            path.steps(space).collect::<Vec<_>>(),
+            vec![(s, Maze2DAction::Right, ORTHOGONAL_COST)]
+        );
+
+        // The wall can't be stepped into, so replaying stops before it.
+        path.append(
+            (Maze2DState::new(1, 1).unwrap(), Maze2DAction::Up),
+            ORTHOGONAL_COST,
+        );
+        assert_eq!(
+            path.steps(space).collect::<Vec<_>>(),
+            vec![(s, Maze2DAction::Right, ORTHOGONAL_COST)]
+        );
+        assert_eq!(path.states(space).collect::<Vec<_>>(), vec![s, right]);
+    }
+
+    #[test]
+    fn validate_path() {
+        use crate::space::Path;
+        use crate::space::PathError;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            ...
+            .#.
+        "})
+        .unwrap();
+        let space = problem.space();
+        let s = Maze2DState::new(0, 0).unwrap();
+        let right = Maze2DState::new(1, 0).unwrap();
+        let below = Maze2DState::new(0, 1).unwrap();
+
+        let mut path = Path::<Maze2DState, Maze2DAction, Maze2DCost>::with_capacity(s, 1);
+        path.append((right, Maze2DAction::Right), ORTHOGONAL_COST);
+        assert_eq!(space.validate_path(&path), Ok(()));
+        assert!(space.valid_path(&path));
+        assert_eq!(space.validate_path(&Path::new_empty()), Ok(()));
+
+        path.append(
+            (Maze2DState::new(1, 1).unwrap(), Maze2DAction::Up),
+            ORTHOGONAL_COST,
+        );
+        assert_eq!(
+            space.validate_path(&path),
+            Err(PathError::InvalidAction {
+                step: 1,
+                state: right
+            })
+        );
+        assert!(!space.valid_path(&path));
+
+        let mut path = Path::with_capacity(s, 1);
+        path.append((right, Maze2DAction::Up), ORTHOGONAL_COST);
+        assert_eq!(
+            space.validate_path(&path),
+            Err(PathError::EndpointMismatch {
+                reached: Some(below),
+                end: Some(right)
+            })
+        );
+
+        let mut path = Path::with_capacity(s, 1);
+        path.append((right, Maze2DAction::Right), DIAGONAL_COST);
+        assert_eq!(
+            space.validate_path(&path),
+            Err(PathError::CostMismatch {
+                cost: DIAGONAL_COST,
+                actual: ORTHOGONAL_COST
+            })
+        );
+    }
+
+    #[test]
+    fn path_concat() {
+        use crate::algorithms::astar::AStarSearch;
+        use crate::space::PathJoinError;
+
+        type Search = AStarSearch<
+            Maze2DHeuristicDiagonalDistance,
+            Maze2DProblem,
+            Maze2DSpace,
+            Maze2DState,
+            Maze2DAction,
+            Maze2DCost,
+        >;
+
+        let first = Maze2DProblem::try_from(indoc! {"
+            S..G...
+            #####..
+        "})
+        .unwrap();
+        let second = Maze2DProblem::try_from(indoc! {"
+            ...S...
+            #####.G
+        "})
+        .unwrap();
+        let space = first.space().clone();
+
+        let mut path = Search::new(first).find_next_goal().unwrap();
+        let mut rest = Search::new(second).find_next_goal().unwrap();
+        let cost = path.cost() + rest.cost();
+        let len = path.len() + rest.len();
+
+        assert_eq!(
+            rest.concat(&path).unwrap_err(),
+            PathJoinError::EndpointMismatch
+        );
+        path.concat(&rest).unwrap();
+        assert_eq!(path.cost(), cost);
+        assert_eq!(path.len(), len);
+        assert_eq!(path.end(), rest.end());
+        assert!(space.valid_path(&path));
+
+        let mut empty = Path::new_empty();
+        assert_eq!(empty.concat(&rest), Err(PathJoinError::EmptyPath));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn path_ron() {
+        use crate::algorithms::astar::AStarSearch;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S..#...
+            .#...#G
+        "})
+        .unwrap();
+        let path = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem)
+            .find_next_goal()
+            .unwrap();
+
+        let ron = ron::to_string(&path).unwrap();
+        let loaded: Path<Maze2DState, Maze2DAction, Maze2DCost> = ron::from_str(&ron).unwrap();
+        assert_eq!(loaded, path);
+    }
+
+    #[test]
+    fn flood_fill() {
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#..
+            ..#.G
+            ###..
+        "})
+        .unwrap();
+        let reached = crate::space::flood_fill(problem.space(), problem.starts());
+
+        let mut expected = FxHashSet::default();
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            expected.insert(Maze2DState::new(x, y).unwrap());
+        }
+        assert_eq!(reached, expected);
+
+        let reached = crate::space::flood_fill(problem.space(), problem.goals());
+        assert_eq!(reached.len(), 6);
+    }
+
+    #[test]
+    fn region() {
+        // "Reach the right wall"
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#..
+            ..#..
+            .....
+        "})
+        .unwrap();
+        type RightColumn = Maze2DHeuristicRegionDistance<4, 0, 4, 2>;
+        let region = RightColumn::region();
+        let problem =
+            Maze2DRegionProblem::new(problem.space().clone(), problem.starts().to_vec(), region);
+
+        assert!(problem.is_goal(&Maze2DState::new(4, 1).unwrap()));
+        assert!(!problem.is_goal(&Maze2DState::new(3, 1).unwrap()));
+
+        assert_eq!(RightColumn::h(&Maze2DState::new(4, 2).unwrap()), 0);
+        assert_eq!(
+            RightColumn::h(&Maze2DState::new(3, 0).unwrap()),
+            ORTHOGONAL_COST
+        );
+        assert_eq!(RightColumn::h(&problem.starts()[0]), 4 * ORTHOGONAL_COST);
+    }
+
+    #[test]
+    fn apply_path() {
+        use crate::algorithms::astar::AStarSearch;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#...
+            .##.#.
+            ...#G.
+        "})
+        .unwrap();
+        let space = problem.space().clone();
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        let path = search.find_next_goal().unwrap();
+
+        let start = path.start().unwrap();
+        assert_eq!(space.apply_path(start, path.actions()), path.end());
+        assert!(space.valid_path(&path));
+        assert_eq!(space.apply_path(start, &[Maze2DAction::Down]), None);
+        assert_eq!(
+            space.apply_path(start, &[Maze2DAction::Right, Maze2DAction::Right]),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn four_connected() {
+        use std::path::PathBuf;
+
+        use crate::algorithms::astar::AStarSearch;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap()
+                .with_connectivity(Connectivity::Four);
+        let space = problem.space().clone();
+        assert_eq!(space.connectivity(), Connectivity::Four);
+
+        let mut search =
+            AStarSearch::<Maze2DHeuristicManhattanDistance, _, _, _, _, _>::new(problem);
+        let path = search.find_next_goal().unwrap();
+
+        assert!(space.valid_path(&path));
+        assert!(path.actions().iter().all(|a| !a.is_diagonal()));
+        // Diagonal moves are cheaper than two orthogonal ones.
+        assert!(path.cost() > 1805);
+    }
+
+    #[test]
+    fn terrain() {
+        use crate::algorithms::astar::AStarSearch;
+
+        // The straight row is mud, but there's a detour on the road below.
+        let mut problem = Maze2DProblem::try_from(indoc! {"
+            S....G
+            ......
+        "})
+        .unwrap();
+        let mud = Maze2DTerrain::new(8).unwrap();
+        for x in 1..5 {
+            let s = Maze2DState::new_from_usize(x, 0).unwrap();
+            assert!(problem.set_terrain(&s, mud));
+        }
+        let space = problem.space().clone();
+
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        let path = search.find_next_goal().unwrap();
+
+        assert!(space.valid_path(&path));
+        assert_eq!(path.cost(), 2 * DIAGONAL_COST + 3 * ORTHOGONAL_COST);
+        for (s, _a, _c) in path.steps(&space) {
+            assert_eq!(space.terrain(&s), Maze2DTerrain::PLAIN);
+        }
+    }
+
+    #[test]
+    fn render_path() {
+        use crate::algorithms::astar::AStarSearch;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            ######
+            S....G
+            ######
+        "})
+        .unwrap();
+        let path =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone())
+                .find_next_goal()
+                .unwrap();
+
+        let rendered = problem.render_path(&path);
+        assert!(rendered.contains("S****G"), "{rendered}");
+        assert_eq!(rendered.matches('*').count(), path.len() - 1);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn save_solution_png() {
+        use std::path::PathBuf;
+
+        use crate::algorithms::astar::AStarSearch;
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        let path =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone())
+                .find_next_goal()
+                .unwrap();
+
+        let out = std::env::temp_dir().join(format!("search-solution-{}.png", std::process::id()));
+        problem.save_solution_png(&path, &out).unwrap();
+        let reloaded = Maze2DProblem::try_from(out.as_path()).unwrap();
+        std::fs::remove_file(&out).unwrap();
+
+        assert_eq!(reloaded.starts().len(), problem.starts().len());
+        assert_eq!(reloaded.goals().len(), problem.goals().len());
+        assert_eq!(reloaded.starts(), problem.starts());
+        assert_eq!(reloaded.goals(), problem.goals());
+        assert_eq!(reloaded.space().dimensions(), problem.space().dimensions());
+    }
+
+    #[test]
+    fn from_text_file() {
+        use std::path::PathBuf;
+
+        // `://data/problems/Maze2D/corridor.txt`
+        let problem =
+            Maze2DProblem::from_text_file(&PathBuf::from("data/problems/Maze2D/corridor.txt"))
+                .unwrap();
+        assert_eq!(problem.space().dimensions(), (7, 5));
+        assert_eq!(
+            problem.starts(),
+            &[Maze2DState::new_from_usize(1, 1).unwrap()]
+        );
+        assert_eq!(
+            problem.goals(),
+            &[Maze2DState::new_from_usize(5, 1).unwrap()]
+        );
+
+        assert!(matches!(
+            Maze2DProblem::from_text_file(&PathBuf::from("data/problems/Maze2D/missing.txt")),
+            Err(Maze2DProblemParseError::IOError { .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn validate() {
+        use std::path::PathBuf;
+
+        // `://data/problems/Maze2D/blank_10.png` is all white.
+        let blank =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/blank_10.png").as_path())
+                .unwrap();
+        assert!(matches!(
+            blank.validate(),
+            Err(Maze2DProblemParseError::NoStarts)
+        ));
+
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+        assert!(problem.validate().is_ok());
+
+        let no_goals = Maze2DProblem::try_from("S..\n").unwrap();
+        assert!(matches!(
+            no_goals.validate(),
+            Err(Maze2DProblemParseError::NoGoals)
+        ));
+    }
+
+    #[test]
+    fn size() {
+        use crate::problems::maze_2d_float::Maze2DSpaceF;
+        use crate::problems::maze_2d_jps::Maze2DJumpPointSpace;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#..
+            ..#.G
+            .....
+        "})
+        .unwrap();
+        let space = problem.space();
+        assert_eq!(space.dimensions(), (5, 3));
+        assert_eq!(space.size(), Some(15));
+
+        assert_eq!(Maze2DSpaceF::new(space.clone()).size(), Some(15));
+        let jps = Maze2DJumpPointSpace::new(space.clone(), problem.goals()).unwrap();
+        assert_eq!(jps.size(), Some(15));
+
+        assert_eq!(Maze2DSpace::new_from_map(vec![]).size(), Some(0));
+    }
+
+    #[test]
+    fn costs() {
+        use crate::algorithms::astar::AStarSearch;
+        use crate::algorithms::dijkstra::DijkstraSearch;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.....
+            ..#...
+            .....G
+        "})
+        .unwrap();
+        let path = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(
+            problem.clone().with_costs(ORTHOGONAL_COST, u32::MAX / 2),
+        )
+        .find_next_goal()
+        .unwrap();
+        assert!(path.actions().iter().all(|a| !a.is_diagonal()));
+        assert_eq!(path.cost(), 7 * ORTHOGONAL_COST);
+
+        // Estimates match the distances on mazes without walls.
+        let (start, goal) = (problem.starts()[0], problem.goals()[0]);
+        for (connectivity, orthogonal, diagonal) in [
+            (Connectivity::Eight, 10, 15),
+            (Connectivity::Eight, 10, 30),
+            (Connectivity::Four, 10, 15),
+        ] {
+            let mut problem = problem
+                .clone()
+                .with_connectivity(connectivity)
+                .with_costs(orthogonal, diagonal);
+            problem.toggle_wall(&Maze2DState::new(2, 1).unwrap());
+            let space = problem.space();
+            assert_eq!(space.move_cost(&Maze2DAction::Right), orthogonal);
+            assert_eq!(space.move_cost(&Maze2DAction::RightUp), diagonal);
+            let cost = DijkstraSearch::new(problem.clone())
+                .find_next_goal()
+                .unwrap()
+                .cost();
+            assert_eq!(space.distance_estimate(&start, &goal), cost);
+
+            // The heuristics follow cheaper moves too.
+            let astar_cost =
+                AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone())
+                    .find_next_goal()
+                    .unwrap()
+                    .cost();
+            assert_eq!(astar_cost, cost);
+        }
+
+        // Huge costs saturate instead of overflowing.
+        let path = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(
+            problem.clone().with_costs(u32::MAX / 2, u32::MAX / 2),
+        )
+        .find_next_goal()
+        .unwrap();
+        assert_eq!(path.cost(), u32::MAX);
+    }
+
+    #[test]
+    fn for_each_neighbour() {
+        use Maze2DAction::*;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#..
+            .#..#
+            ..#.G
+        "})
+        .unwrap();
+
+        let cell = |x, y| Maze2DState::new_from_usize(x, y).unwrap();
+        let neighbours = |space: &Maze2DSpace, x, y| {
+            let s = cell(x, y);
+            let mut visited = vec![];
+            space.for_each_neighbour(&s, |t, a| visited.push((t, a)));
+            visited
+        };
+
+        let space = problem.space().clone();
+        let mut around = neighbours(&space, 3, 1);
+        around.sort_by_key(|(_t, a)| *a as u8);
+        assert_eq!(
+            around,
+            vec![
+                (cell(3, 2), Up),
+                (cell(3, 0), Down),
+                (cell(2, 1), Left),
+                (cell(4, 2), RightUp),
+                (cell(4, 0), RightDown),
+            ]
+        );
+
+        // Every action `apply` accepts, and nothing else.
+        let actions = [Up, Down, Left, Right, LeftUp, RightUp, LeftDown, RightDown];
+        for connectivity in [Connectivity::Eight, Connectivity::Four] {
+            let space = problem.space().clone().with_connectivity(connectivity);
+            let (max_x, max_y) = space.dimensions();
+            for (x, y) in (0..max_x).flat_map(|x| (0..max_y).map(move |y| (x, y))) {
+                let s = cell(x, y);
+                let mut expected: Vec<_> = actions
+                    .iter()
+                    .filter_map(|a| space.apply(&s, a).map(|t| (t, *a)))
+                    .collect();
+                let mut visited = neighbours(&space, x, y);
+                expected.sort_by_key(|(_t, a)| *a as u8);
+                visited.sort_by_key(|(_t, a)| *a as u8);
+                assert_eq!(visited, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_path() {
+        use crate::algorithms::astar::AStarSearch;
+
+        let solve = |problem: &Maze2DProblem| {
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone())
+                .find_next_goal()
+                .unwrap()
+        };
+
+        // Straight corridors collapse to their endpoints.
+        let problem = Maze2DProblem::try_from(indoc! {"
+            #######
+            S.....G
+            #######
+        "})
+        .unwrap();
+        let (start, goal) = (problem.starts()[0], problem.goals()[0]);
+        let path = solve(&problem);
+        assert_eq!(path.len(), 6);
+        assert_eq!(problem.space().smooth_path(&path), vec![start, goal]);
+
+        // Corners are kept.
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S....
+            ####.
+            ####.
+            ....G
+        "})
+        .unwrap();
+        let space = problem.space();
+        let path = solve(&problem);
+        let states: Vec<_> = path.states(space).collect();
+        let smoothed = space.smooth_path(&path);
+        assert_eq!(smoothed.first(), states.first());
+        assert_eq!(smoothed.last(), states.last());
+        assert!(smoothed.len() > 2 && smoothed.len() < states.len());
+        assert!(smoothed.iter().all(|s| states.contains(s)));
+        for pair in smoothed.windows(2) {
+            assert!(space.line_of_sight(&pair[0], &pair[1]));
+        }
+
+        // Lines can't squeeze diagonally between walls without diagonal moves.
+        let space = Maze2DProblem::try_from(indoc! {"
+            S#
+            #G
+        "})
+        .unwrap()
+        .space()
+        .clone();
+        let (a, b) = (
+            Maze2DState::new(0, 0).unwrap(),
+            Maze2DState::new(1, 1).unwrap(),
+        );
+        assert!(space.line_of_sight(&a, &b));
+        assert!(
+            !space
+                .clone()
+                .with_connectivity(Connectivity::Four)
+                .line_of_sight(&a, &b)
+        );
+
+        assert!(space.smooth_path(&Path::new_empty()).is_empty());
+    }
+
+    #[test]
+    fn reverse_neighbours() {
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#..
+            .#..#
+            ..#.G
+        "})
+        .unwrap();
+        for connectivity in [Connectivity::Four, Connectivity::Eight] {
+            let space = problem.space().clone().with_connectivity(connectivity);
+            let (max_x, max_y) = space.dimensions();
+            for (x, y) in (0..max_x).flat_map(|x| (0..max_y).map(move |y| (x, y))) {
+                let s = Maze2DState::new_from_usize(x, y).unwrap();
+                if space.at(&s) == Maze2DCell::Wall {
+                    continue;
+                }
+                // Predecessors come with the action taking them forward.
+                for (t, a) in space.neighbours(&s) {
+                    assert!(space.reverse_neighbours(&t).contains(&(s, a)));
+                    assert_eq!(space.apply(&t, &a.opposite()), Some(s));
+                    assert_eq!(a.opposite().opposite(), a);
+                }
+                for (t, a) in space.reverse_neighbours(&s) {
+                    assert_eq!(space.apply(&t, &a), Some(s));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solve_ascii_unreachable() {
+        let maze = indoc! {"
+            S.#..
+            ..#.G
+        "};
+        assert_eq!(solve_ascii_maze(maze), None);
+        assert_eq!(solve_ascii_maze("S?G"), None);
+    }
+
+    #[test]
+    fn solve_ascii_ragged() {
+        assert_eq!(solve_ascii_maze("S.\n...G\n"), None);
+        assert_eq!(solve_ascii_maze("S..\n.G\n"), None);
+        assert!(matches!(
+            Maze2DProblem::try_from("S.\n..G"),
+            Err(Maze2DProblemParseError::RaggedRow {
+                y: 1,
+                len: 3,
+                expected: 2
+            })
+        ));
+    }
 }
@@ -0,0 +1,246 @@
+//! A finite 2D maze with floating point costs.
+//!
+//! `Maze2DCost` is an integer, which rules out exact Euclidean distances. This
+//! wraps `Maze2DSpace` using `FloatCost<f32>` instead, allowing heuristics like
+//! `Maze2DHeuristicEuclidean`.
+
+use crate::float_cost::FloatCost;
+use crate::problem::BaseProblem;
+use crate::problem::ObjectiveProblem;
+use crate::problems::maze_2d::DIAGONAL_COST;
+use crate::problems::maze_2d::Maze2DAction;
+use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+use crate::problems::maze_2d::Maze2DHeuristicManhattanDistance;
+use crate::problems::maze_2d::Maze2DProblem;
+use crate::problems::maze_2d::Maze2DSpace;
+use crate::problems::maze_2d::Maze2DState;
+use crate::problems::maze_2d::ORTHOGONAL_COST;
+use crate::space::ObjectiveHeuristic;
+use crate::space::SampleableSpace;
+use crate::space::Space;
+
+pub type Maze2DCostF = FloatCost<f32>;
+
+#[inline(always)]
+#[must_use]
+fn to_float(c: u32) -> Maze2DCostF {
+    FloatCost::new(c as f32).unwrap()
+}
+
+/// A `Maze2DSpace` with `FloatCost<f32>` costs.
+#[derive(Clone, Debug)]
+pub struct Maze2DSpaceF {
+    space: Maze2DSpace,
+}
+
+impl Maze2DSpaceF {
+    #[must_use]
+    pub fn new(space: Maze2DSpace) -> Self {
+        Self { space }
+    }
+
+    /// The underlying grid.
+    #[must_use]
+    pub fn grid(&self) -> &Maze2DSpace {
+        &self.space
+    }
+}
+
+impl Space<Maze2DState, Maze2DAction, Maze2DCostF> for Maze2DSpaceF {
+    #[inline(always)]
+    fn apply(&self, state: &Maze2DState, action: &Maze2DAction) -> Option<Maze2DState> {
+        self.space.apply(state, action)
+    }
+
+    #[inline(always)]
+    fn valid(&self, state: &Maze2DState) -> bool {
+        self.space.valid(state)
+    }
+
+    #[inline(always)]
+    fn cost(&self, s: &Maze2DState, a: &Maze2DAction) -> Maze2DCostF {
+        to_float(self.space.cost(s, a))
+    }
+
+    #[inline(always)]
+    fn neighbours(&self, state: &Maze2DState) -> Vec<(Maze2DState, Maze2DAction)> {
+        self.space.neighbours(state)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> Option<usize> {
+        self.space.size()
+    }
+}
+
+impl SampleableSpace<Maze2DState, Maze2DAction, Maze2DCostF> for Maze2DSpaceF {
+    fn random_state<R: rand::Rng>(&self, r: &mut R) -> Option<Maze2DState> {
+        self.space.random_state(r)
+    }
+}
+
+/// The straight line distance.
+///
+/// `DIAGONAL_COST` rounds `√2 * ORTHOGONAL_COST` down, so distances are scaled
+/// by the same ratio to stay admissible.
+#[derive(Debug)]
+#[cfg_attr(feature = "inspect", derive(Clone))]
+pub struct Maze2DHeuristicEuclidean;
+
+impl ObjectiveHeuristic<Maze2DSpaceF, Maze2DState, Maze2DAction, Maze2DCostF>
+    for Maze2DHeuristicEuclidean
+{
+    #[inline(always)]
+    fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCostF {
+        let dx = a.x.get().abs_diff(b.x.get()) as f32;
+        let dy = a.y.get().abs_diff(b.y.get()) as f32;
+        let scale = DIAGONAL_COST as f32 / (std::f32::consts::SQRT_2 * ORTHOGONAL_COST as f32);
+
+        FloatCost::new(dx.hypot(dy) * ORTHOGONAL_COST as f32 * scale).unwrap()
+    }
+
+    /// The straight line distance at the cheapest cost per unit of length the
+    /// space allows.
+    #[inline(always)]
+    fn h_in(space: &Maze2DSpaceF, a: &Maze2DState, b: &Maze2DState) -> Maze2DCostF {
+        let dx = a.x.get().abs_diff(b.x.get()) as f32;
+        let dy = a.y.get().abs_diff(b.y.get()) as f32;
+        let orthogonal = space.space.orthogonal_cost() as f32;
+        let diagonal = space.space.diagonal_cost() as f32 / std::f32::consts::SQRT_2;
+
+        FloatCost::new(dx.hypot(dy) * orthogonal.min(diagonal)).unwrap()
+    }
+}
+
+impl ObjectiveHeuristic<Maze2DSpaceF, Maze2DState, Maze2DAction, Maze2DCostF>
+    for Maze2DHeuristicManhattanDistance
+{
+    #[inline(always)]
+    fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCostF {
+        to_float(<Self as ObjectiveHeuristic<Maze2DSpace, _, _, _>>::h(a, b))
+    }
+
+    #[inline(always)]
+    fn h_in(space: &Maze2DSpaceF, a: &Maze2DState, b: &Maze2DState) -> Maze2DCostF {
+        to_float(<Self as ObjectiveHeuristic<Maze2DSpace, _, _, _>>::h_in(
+            &space.space,
+            a,
+            b,
+        ))
+    }
+}
+
+impl ObjectiveHeuristic<Maze2DSpaceF, Maze2DState, Maze2DAction, Maze2DCostF>
+    for Maze2DHeuristicDiagonalDistance
+{
+    #[inline(always)]
+    fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCostF {
+        to_float(<Self as ObjectiveHeuristic<Maze2DSpace, _, _, _>>::h(a, b))
+    }
+
+    #[inline(always)]
+    fn h_in(space: &Maze2DSpaceF, a: &Maze2DState, b: &Maze2DState) -> Maze2DCostF {
+        to_float(<Self as ObjectiveHeuristic<Maze2DSpace, _, _, _>>::h_in(
+            &space.space,
+            a,
+            b,
+        ))
+    }
+}
+
+/// A `Maze2DProblem` with `FloatCost<f32>` costs.
+#[derive(Clone, Debug)]
+pub struct Maze2DProblemF {
+    space: Maze2DSpaceF,
+    problem: Maze2DProblem,
+}
+
+impl std::convert::From<Maze2DProblem> for Maze2DProblemF {
+    fn from(problem: Maze2DProblem) -> Self {
+        Self {
+            space: Maze2DSpaceF::new(problem.space().clone()),
+            problem,
+        }
+    }
+}
+
+impl BaseProblem<Maze2DSpaceF, Maze2DState, Maze2DAction, Maze2DCostF> for Maze2DProblemF {
+    fn space(&self) -> &Maze2DSpaceF {
+        &self.space
+    }
+    fn starts(&self) -> &[Maze2DState] {
+        self.problem.starts()
+    }
+}
+
+impl ObjectiveProblem<Maze2DSpaceF, Maze2DState, Maze2DAction, Maze2DCostF> for Maze2DProblemF {
+    fn goals(&self) -> &[Maze2DState] {
+        self.problem.goals()
+    }
+
+    fn randomize<R: rand::Rng>(
+        &mut self,
+        r: &mut R,
+        num_starts: u16,
+        num_goals: u16,
+    ) -> Option<Maze2DProblemF> {
+        self.problem
+            .randomize(r, num_starts, num_goals)
+            .map(Maze2DProblemF::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::astar::AStarSearch;
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn solve_maze2d() {
+        use std::path::PathBuf;
+
+        // Solve `two-paths.png` (`://data/problems/Maze2D/two-paths.png`)
+        let problem = Maze2DProblemF::from(
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap(),
+        );
+        let start = problem.starts()[0];
+        let goal = problem.goals()[0];
+        let space = problem.space().clone();
+
+        let euclidean = <Maze2DHeuristicEuclidean as ObjectiveHeuristic<Maze2DSpaceF, _, _, _>>::h(
+            &start, &goal,
+        );
+        let diagonal =
+            <Maze2DHeuristicDiagonalDistance as ObjectiveHeuristic<Maze2DSpaceF, _, _, _>>::h(
+                &start, &goal,
+            );
+        assert!(euclidean <= diagonal);
+
+        let mut search = AStarSearch::<Maze2DHeuristicEuclidean, _, _, _, _, _>::new(problem);
+        let path = search.find_next_goal().unwrap();
+        assert!(space.valid_path(&path));
+        assert_eq!(path.cost(), to_float(1805));
+    }
+
+    #[test]
+    fn euclidean_admissible() {
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance as Diagonal;
+
+        let origin = Maze2DState::new_from_usize(0, 0).unwrap();
+        for x in 0..20 {
+            for y in 0..20 {
+                let s = Maze2DState::new_from_usize(x, y).unwrap();
+                let euclidean =
+                    <Maze2DHeuristicEuclidean as ObjectiveHeuristic<Maze2DSpaceF, _, _, _>>::h(
+                        &origin, &s,
+                    );
+                let diagonal =
+                    <Diagonal as ObjectiveHeuristic<Maze2DSpaceF, _, _, _>>::h(&origin, &s);
+                assert!(euclidean <= diagonal, "h({s})={euclidean} > {diagonal}");
+            }
+        }
+    }
+}
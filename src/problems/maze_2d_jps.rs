@@ -0,0 +1,483 @@
+//! Jump Point Search (JPS) over a finite 2D maze.
+//!
+//! `Maze2DSpace` is a uniform-cost 8-connected grid, so most optimal paths
+//! have many symmetric variants. Instead of generating adjacent cells, this
+//! space scans in a straight line until reaching a "jump point" (a goal or a
+//! cell with a forced neighbour), skipping the symmetric paths in between.
+//!
+//! Any search over this space finds paths of jumps, which can be expanded back
+//! into paths over `Maze2DSpace` with `Maze2DJumpPointSpace::expand_path`.
+
+use derive_more::Display;
+use rustc_hash::FxHashSet;
+use thiserror::Error;
+
+use crate::problem::BaseProblem;
+use crate::problem::ObjectiveProblem;
+use crate::problems::maze_2d::Connectivity;
+use crate::problems::maze_2d::Maze2DAction;
+use crate::problems::maze_2d::Maze2DCell;
+use crate::problems::maze_2d::Maze2DCost;
+use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+use crate::problems::maze_2d::Maze2DHeuristicManhattanDistance;
+use crate::problems::maze_2d::Maze2DProblem;
+use crate::problems::maze_2d::Maze2DSpace;
+use crate::problems::maze_2d::Maze2DState;
+use crate::space::Action;
+use crate::space::ObjectiveHeuristic;
+use crate::space::Path;
+use crate::space::SampleableSpace;
+use crate::space::Space;
+
+/// Repeats a `Maze2DAction` a number of times.
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq)]
+#[display("{action}×{steps}")]
+pub struct Maze2DJump {
+    pub action: Maze2DAction,
+    pub steps: u32,
+}
+
+impl Action for Maze2DJump {
+    #[inline(always)]
+    fn turns_from(&self, prev: &Self) -> bool {
+        self.action != prev.action
+    }
+
+    #[inline(always)]
+    fn is_diagonal(&self) -> bool {
+        self.action.is_diagonal()
+    }
+}
+
+/// The direction `(dx, dy)` of each action.
+#[rustfmt::skip]
+const DIRECTIONS: [(i64, i64, Maze2DAction); 8] = [
+    ( 0,  1, Maze2DAction::Up),
+    ( 0, -1, Maze2DAction::Down),
+    (-1,  0, Maze2DAction::Left),
+    ( 1,  0, Maze2DAction::Right),
+    (-1,  1, Maze2DAction::LeftUp),
+    ( 1,  1, Maze2DAction::RightUp),
+    (-1, -1, Maze2DAction::LeftDown),
+    ( 1, -1, Maze2DAction::RightDown),
+];
+
+/// A `Maze2DSpace` generating jump points as successors.
+///
+/// Goals are needed to stop jumps on them.
+///
+/// NOTE: Expansions don't know the direction they were reached from, so they
+/// jump in all 8 directions instead of just the natural and forced ones. This
+/// generates some redundant successors, but keeps the `Space` interface.
+///
+/// NOTE: Jumps need an 8-connected grid (`Connectivity::Eight`) with `PLAIN`
+/// terrain, where a diagonal move costs between one and two orthogonal moves,
+/// so other mazes are rejected.
+#[derive(Clone, Debug)]
+pub struct Maze2DJumpPointSpace {
+    space: Maze2DSpace,
+    goals: FxHashSet<Maze2DState>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Maze2DJumpPointError {
+    #[error("Jump points need an 8-connected maze")]
+    FourConnected,
+    #[error("Jump points need a maze without terrain")]
+    Terrain,
+    #[error(
+        "Jump points need diagonal moves costing 1-2 orthogonal ones ({orthogonal}/{diagonal})"
+    )]
+    Costs {
+        orthogonal: Maze2DCost,
+        diagonal: Maze2DCost,
+    },
+}
+
+impl Maze2DJumpPointSpace {
+    pub fn new(space: Maze2DSpace, goals: &[Maze2DState]) -> Result<Self, Maze2DJumpPointError> {
+        if space.connectivity() == Connectivity::Four {
+            return Err(Maze2DJumpPointError::FourConnected);
+        }
+        if space.has_terrain() {
+            return Err(Maze2DJumpPointError::Terrain);
+        }
+        // Otherwise straight jumps can skip cheaper zig-zags, or the other way
+        // around.
+        let (orthogonal, diagonal) = (space.orthogonal_cost(), space.diagonal_cost());
+        if diagonal < orthogonal || diagonal > orthogonal.saturating_mul(2) {
+            return Err(Maze2DJumpPointError::Costs {
+                orthogonal,
+                diagonal,
+            });
+        }
+        Ok(Self {
+            space,
+            goals: FxHashSet::from_iter(goals.iter().cloned()),
+        })
+    }
+
+    /// The underlying grid.
+    #[must_use]
+    pub fn grid(&self) -> &Maze2DSpace {
+        &self.space
+    }
+
+    /// Expands a path of jumps into a path of single steps on the grid.
+    #[must_use]
+    pub fn expand_path(
+        &self,
+        path: &Path<Maze2DState, Maze2DJump, Maze2DCost>,
+    ) -> Path<Maze2DState, Maze2DAction, Maze2DCost> {
+        let Some(start) = path.start() else {
+            return Path::new_empty();
+        };
+
+        let len = path.actions().iter().map(|j| j.steps as usize).sum();
+        let mut expanded = Path::with_capacity(start, len);
+        let mut state = start;
+        for jump in path.actions() {
+            for _step in 0..jump.steps {
+                let c = self.space.cost(&state, &jump.action);
+                state = self
+                    .space
+                    .apply(&state, &jump.action)
+                    .expect("Jumps must only cross empty cells");
+                expanded.append((state, jump.action), c);
+            }
+        }
+        expanded
+    }
+
+    #[inline(always)]
+    #[must_use]
+    fn state(&self, x: i64, y: i64) -> Option<Maze2DState> {
+        let s = Maze2DState::new_from_usize(x.try_into().ok()?, y.try_into().ok()?)?;
+        self.space.valid(&s).then_some(s)
+    }
+
+    /// Checks whether a cell is inside the maze and empty.
+    #[inline(always)]
+    #[must_use]
+    fn walkable(&self, x: i64, y: i64) -> bool {
+        self.state(x, y)
+            .is_some_and(|s| self.space.at(&s) == Maze2DCell::Empty)
+    }
+
+    /// Scans from a state until finding a jump point.
+    ///
+    /// Returns the jump point and the number of steps to reach it.
+    #[must_use]
+    fn jump(&self, from: &Maze2DState, dx: i64, dy: i64) -> Option<(Maze2DState, u32)> {
+        let mut x = from.x.get() as i64;
+        let mut y = from.y.get() as i64;
+        let mut steps = 0u32;
+
+        loop {
+            x += dx;
+            y += dy;
+            steps += 1;
+
+            let s = self.state(x, y)?;
+            if self.space.at(&s) == Maze2DCell::Wall {
+                return None;
+            }
+            if self.goals.contains(&s) {
+                return Some((s, steps));
+            }
+
+            let forced = match (dx, dy) {
+                (0, dy) => {
+                    (!self.walkable(x + 1, y) && self.walkable(x + 1, y + dy))
+                        || (!self.walkable(x - 1, y) && self.walkable(x - 1, y + dy))
+                }
+                (dx, 0) => {
+                    (!self.walkable(x, y + 1) && self.walkable(x + dx, y + 1))
+                        || (!self.walkable(x, y - 1) && self.walkable(x + dx, y - 1))
+                }
+                (dx, dy) => {
+                    (!self.walkable(x - dx, y) && self.walkable(x - dx, y + dy))
+                        || (!self.walkable(x, y - dy) && self.walkable(x + dx, y - dy))
+                        // Diagonal moves stop where a straight jump would.
+                        || self.jump(&s, dx, 0).is_some()
+                        || self.jump(&s, 0, dy).is_some()
+                }
+            };
+            if forced {
+                return Some((s, steps));
+            }
+        }
+    }
+}
+
+impl Space<Maze2DState, Maze2DJump, Maze2DCost> for Maze2DJumpPointSpace {
+    #[inline(always)]
+    fn apply(&self, state: &Maze2DState, jump: &Maze2DJump) -> Option<Maze2DState> {
+        (0..jump.steps).try_fold(*state, |s, _step| self.space.apply(&s, &jump.action))
+    }
+
+    #[inline(always)]
+    fn valid(&self, state: &Maze2DState) -> bool {
+        self.space.valid(state)
+    }
+
+    #[inline(always)]
+    fn cost(&self, _s: &Maze2DState, jump: &Maze2DJump) -> Maze2DCost {
+        self.space
+            .move_cost(&jump.action)
+            .saturating_mul(jump.steps)
+    }
+
+    /// Gets the jump points reachable in a straight line.
+    fn neighbours(&self, state: &Maze2DState) -> Vec<(Maze2DState, Maze2DJump)> {
+        #[cfg(feature = "coz_profile")]
+        coz::scope!("StateExpansion");
+
+        DIRECTIONS
+            .iter()
+            .filter_map(|(dx, dy, action)| {
+                let (s, steps) = self.jump(state, *dx, *dy)?;
+                Some((
+                    s,
+                    Maze2DJump {
+                        action: *action,
+                        steps,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    #[inline(always)]
+    fn size(&self) -> Option<usize> {
+        self.space.size()
+    }
+}
+
+impl SampleableSpace<Maze2DState, Maze2DJump, Maze2DCost> for Maze2DJumpPointSpace {
+    fn random_state<R: rand::Rng>(&self, r: &mut R) -> Option<Maze2DState> {
+        self.space.random_state(r)
+    }
+}
+
+impl ObjectiveHeuristic<Maze2DJumpPointSpace, Maze2DState, Maze2DJump, Maze2DCost>
+    for Maze2DHeuristicManhattanDistance
+{
+    #[inline(always)]
+    fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        <Self as ObjectiveHeuristic<Maze2DSpace, _, Maze2DAction, _>>::h(a, b)
+    }
+
+    #[inline(always)]
+    fn h_in(space: &Maze2DJumpPointSpace, a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        <Self as ObjectiveHeuristic<Maze2DSpace, _, Maze2DAction, _>>::h_in(&space.space, a, b)
+    }
+}
+
+impl ObjectiveHeuristic<Maze2DJumpPointSpace, Maze2DState, Maze2DJump, Maze2DCost>
+    for Maze2DHeuristicDiagonalDistance
+{
+    #[inline(always)]
+    fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        <Self as ObjectiveHeuristic<Maze2DSpace, _, Maze2DAction, _>>::h(a, b)
+    }
+
+    #[inline(always)]
+    fn h_in(space: &Maze2DJumpPointSpace, a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        <Self as ObjectiveHeuristic<Maze2DSpace, _, Maze2DAction, _>>::h_in(&space.space, a, b)
+    }
+}
+
+/// A `Maze2DProblem` searched through jump points.
+#[derive(Clone, Debug)]
+pub struct Maze2DJumpPointProblem {
+    space: Maze2DJumpPointSpace,
+    problem: Maze2DProblem,
+}
+
+impl std::convert::TryFrom<Maze2DProblem> for Maze2DJumpPointProblem {
+    type Error = Maze2DJumpPointError;
+
+    fn try_from(problem: Maze2DProblem) -> Result<Self, Self::Error> {
+        Ok(Self {
+            space: Maze2DJumpPointSpace::new(problem.space().clone(), problem.goals())?,
+            problem,
+        })
+    }
+}
+
+impl BaseProblem<Maze2DJumpPointSpace, Maze2DState, Maze2DJump, Maze2DCost>
+    for Maze2DJumpPointProblem
+{
+    fn space(&self) -> &Maze2DJumpPointSpace {
+        &self.space
+    }
+    fn starts(&self) -> &[Maze2DState] {
+        self.problem.starts()
+    }
+}
+
+impl ObjectiveProblem<Maze2DJumpPointSpace, Maze2DState, Maze2DJump, Maze2DCost>
+    for Maze2DJumpPointProblem
+{
+    fn goals(&self) -> &[Maze2DState] {
+        self.problem.goals()
+    }
+
+    fn randomize<R: rand::Rng>(
+        &mut self,
+        r: &mut R,
+        num_starts: u16,
+        num_goals: u16,
+    ) -> Option<Maze2DJumpPointProblem> {
+        self.problem
+            .randomize(r, num_starts, num_goals)
+            .and_then(|problem| Maze2DJumpPointProblem::try_from(problem).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::astar::AStarSearch;
+
+    #[test]
+    fn matches_astar() {
+        use indoc::indoc;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.........#.......
+            .######...#..###..
+            ......#...#....#..
+            .####.#...####.#..
+            ....#.#........#.G
+        "})
+        .unwrap();
+
+        let expected =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone())
+                .find_next_goal()
+                .unwrap();
+
+        let problem = Maze2DJumpPointProblem::try_from(problem).unwrap();
+        let space = problem.space().clone();
+        let path = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem)
+            .find_next_goal()
+            .unwrap();
+        assert!(space.valid_path(&path));
+        assert_eq!(path.cost(), expected.cost());
+
+        let expanded = space.expand_path(&path);
+        assert!(space.grid().valid_path(&expanded));
+        assert_eq!(expanded.cost(), expected.cost());
+        assert_eq!(expanded.end(), expected.end());
+    }
+
+    #[test]
+    fn unsupported_mazes() {
+        use crate::problems::maze_2d::Maze2DTerrain;
+
+        let problem = Maze2DProblem::try_from("S.#.G").unwrap();
+        let four = problem.clone().with_connectivity(Connectivity::Four);
+        assert_eq!(
+            Maze2DJumpPointProblem::try_from(four).unwrap_err(),
+            Maze2DJumpPointError::FourConnected
+        );
+
+        let mut terrain = problem.clone();
+        let mud = Maze2DTerrain::new(3).unwrap();
+        assert!(terrain.set_terrain(&Maze2DState::new(1, 0).unwrap(), mud));
+        assert_eq!(
+            Maze2DJumpPointProblem::try_from(terrain.clone()).unwrap_err(),
+            Maze2DJumpPointError::Terrain
+        );
+
+        // Plain terrain is fine.
+        assert!(terrain.set_terrain(&Maze2DState::new(1, 0).unwrap(), Maze2DTerrain::PLAIN));
+        assert!(Maze2DJumpPointProblem::try_from(terrain).is_ok());
+    }
+
+    #[test]
+    fn costs() {
+        use indoc::indoc;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S...
+            ....
+            ....
+            ...G
+        "})
+        .unwrap();
+        let astar_cost = |problem: Maze2DProblem| {
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem)
+                .find_next_goal()
+                .unwrap()
+                .cost()
+        };
+        let jps_cost = |problem: Maze2DJumpPointProblem| {
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem)
+                .find_next_goal()
+                .unwrap()
+                .cost()
+        };
+
+        // Diagonals are pricier than going around, which jumps would miss.
+        let pricey = problem.clone().with_costs(10, 30);
+        assert_eq!(astar_cost(pricey.clone()), 60);
+        assert_eq!(
+            Maze2DJumpPointProblem::try_from(pricey).unwrap_err(),
+            Maze2DJumpPointError::Costs {
+                orthogonal: 10,
+                diagonal: 30
+            }
+        );
+        let cheap = problem.clone().with_costs(10, 5);
+        assert_eq!(
+            Maze2DJumpPointProblem::try_from(cheap).unwrap_err(),
+            Maze2DJumpPointError::Costs {
+                orthogonal: 10,
+                diagonal: 5
+            }
+        );
+
+        for (orthogonal, diagonal) in [(10, 10), (10, 14), (10, 20)] {
+            let problem = problem.clone().with_costs(orthogonal, diagonal);
+            let expected = astar_cost(problem.clone());
+            assert_eq!(
+                jps_cost(Maze2DJumpPointProblem::try_from(problem).unwrap()),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn solve_maze2d() {
+        use std::path::PathBuf;
+
+        // Solve `two-paths.png` (`://data/problems/Maze2D/two-paths.png`)
+        let problem =
+            Maze2DProblem::try_from(PathBuf::from("data/problems/Maze2D/two-paths.png").as_path())
+                .unwrap();
+
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone());
+        assert_eq!(search.find_next_goal().unwrap().cost(), 1805);
+        let grid_expanded = search.expanded_count();
+
+        let problem = Maze2DJumpPointProblem::try_from(problem).unwrap();
+        let space = problem.space().clone();
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        let path = search.find_next_goal().unwrap();
+        assert_eq!(path.cost(), 1805);
+        assert_eq!(space.expand_path(&path).cost(), 1805);
+        let jps_expanded = search.expanded_count();
+
+        assert!(
+            jps_expanded < grid_expanded,
+            "JPS expanded {jps_expanded} nodes, A* expanded {grid_expanded}"
+        );
+    }
+}
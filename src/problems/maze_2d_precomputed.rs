@@ -0,0 +1,202 @@
+//! A static `Maze2DSpace` with its neighbours precomputed.
+//!
+//! Expanding a cell on `Maze2DSpace` checks its 8 surrounding cells every
+//! time. Maps that don't change can do it once per cell up-front, keeping all
+//! the neighbour lists in a single flat buffer.
+
+use crate::problems::maze_2d::Maze2DAction;
+use crate::problems::maze_2d::Maze2DCost;
+use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+use crate::problems::maze_2d::Maze2DHeuristicManhattanDistance;
+use crate::problems::maze_2d::Maze2DSpace;
+use crate::problems::maze_2d::Maze2DState;
+use crate::space::ObjectiveHeuristic;
+use crate::space::SampleableSpace;
+use crate::space::Space;
+
+/// A `Maze2DSpace` that looks up the neighbours of a cell instead of
+/// computing them.
+///
+/// The neighbours of the cell `(x, y)` are stored at
+/// `neighbours[offsets[y * width + x]..offsets[y * width + x + 1]]`.
+///
+/// NOTE: The grid can't be edited, as the neighbours would go stale.
+#[derive(Clone, Debug)]
+pub struct PrecomputedMaze2DSpace {
+    space: Maze2DSpace,
+    offsets: Vec<u32>,
+    neighbours: Vec<(Maze2DState, Maze2DAction)>,
+}
+
+impl PrecomputedMaze2DSpace {
+    #[must_use]
+    pub fn new(space: Maze2DSpace) -> Self {
+        let (max_x, max_y) = space.dimensions();
+        let mut offsets = Vec::with_capacity(max_x * max_y + 1);
+        let mut neighbours = Vec::new();
+
+        offsets.push(0);
+        for y in 0..max_y {
+            for x in 0..max_x {
+                let s = Maze2DState::new_from_usize(x, y).unwrap();
+                neighbours.extend(space.neighbours(&s));
+                offsets.push(neighbours.len() as u32);
+            }
+        }
+
+        Self {
+            space,
+            offsets,
+            neighbours,
+        }
+    }
+
+    /// The underlying grid.
+    #[must_use]
+    pub fn grid(&self) -> &Maze2DSpace {
+        &self.space
+    }
+
+    /// The neighbours of a cell, without allocating.
+    #[inline(always)]
+    #[must_use]
+    pub fn neighbours_slice(&self, state: &Maze2DState) -> &[(Maze2DState, Maze2DAction)] {
+        debug_assert!(self.valid(state));
+        let (max_x, _max_y) = self.space.dimensions();
+        let i = state.y.get() as usize * max_x + state.x.get() as usize;
+        &self.neighbours[self.offsets[i] as usize..self.offsets[i + 1] as usize]
+    }
+}
+
+impl std::convert::From<Maze2DSpace> for PrecomputedMaze2DSpace {
+    fn from(space: Maze2DSpace) -> Self {
+        Self::new(space)
+    }
+}
+
+impl Space<Maze2DState, Maze2DAction, Maze2DCost> for PrecomputedMaze2DSpace {
+    #[inline(always)]
+    fn apply(&self, state: &Maze2DState, action: &Maze2DAction) -> Option<Maze2DState> {
+        self.space.apply(state, action)
+    }
+
+    #[inline(always)]
+    fn valid(&self, state: &Maze2DState) -> bool {
+        self.space.valid(state)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> Option<usize> {
+        self.space.size()
+    }
+
+    #[inline(always)]
+    fn cost(&self, s: &Maze2DState, a: &Maze2DAction) -> Maze2DCost {
+        self.space.cost(s, a)
+    }
+
+    /// Copies the precomputed neighbours of a cell.
+    ///
+    /// NOTE: This allocates, unlike `neighbours_slice` and
+    /// `for_each_neighbour`.
+    #[inline(always)]
+    fn neighbours(&self, state: &Maze2DState) -> Vec<(Maze2DState, Maze2DAction)> {
+        self.neighbours_slice(state).to_vec()
+    }
+
+    #[inline(always)]
+    fn for_each_neighbour(
+        &self,
+        state: &Maze2DState,
+        mut f: impl FnMut(Maze2DState, Maze2DAction),
+    ) {
+        for (s, a) in self.neighbours_slice(state) {
+            f(*s, *a);
+        }
+    }
+}
+
+impl SampleableSpace<Maze2DState, Maze2DAction, Maze2DCost> for PrecomputedMaze2DSpace {
+    fn random_state<R: rand::Rng>(&self, r: &mut R) -> Option<Maze2DState> {
+        self.space.random_state(r)
+    }
+}
+
+impl ObjectiveHeuristic<PrecomputedMaze2DSpace, Maze2DState, Maze2DAction, Maze2DCost>
+    for Maze2DHeuristicManhattanDistance
+{
+    #[inline(always)]
+    fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        <Self as ObjectiveHeuristic<Maze2DSpace, _, Maze2DAction, _>>::h(a, b)
+    }
+
+    #[inline(always)]
+    fn h_in(space: &PrecomputedMaze2DSpace, a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        <Self as ObjectiveHeuristic<Maze2DSpace, _, Maze2DAction, _>>::h_in(&space.space, a, b)
+    }
+}
+
+impl ObjectiveHeuristic<PrecomputedMaze2DSpace, Maze2DState, Maze2DAction, Maze2DCost>
+    for Maze2DHeuristicDiagonalDistance
+{
+    #[inline(always)]
+    fn h(a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        <Self as ObjectiveHeuristic<Maze2DSpace, _, Maze2DAction, _>>::h(a, b)
+    }
+
+    #[inline(always)]
+    fn h_in(space: &PrecomputedMaze2DSpace, a: &Maze2DState, b: &Maze2DState) -> Maze2DCost {
+        <Self as ObjectiveHeuristic<Maze2DSpace, _, Maze2DAction, _>>::h_in(&space.space, a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::algorithms::astar::AStarSearch;
+    use crate::problem::BaseProblem;
+    use crate::problem::FloodProblem;
+    use crate::problem::ObjectiveProblem;
+    use crate::problems::maze_2d::Connectivity;
+    use crate::problems::maze_2d::Maze2DProblem;
+
+    #[test]
+    fn matches_maze2d() {
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S.#..
+            .#..#
+            ..#.G
+        "})
+        .unwrap();
+
+        for connectivity in [Connectivity::Eight, Connectivity::Four] {
+            let space = problem.space().clone().with_connectivity(connectivity);
+            let precomputed = PrecomputedMaze2DSpace::new(space.clone());
+            let (max_x, max_y) = space.dimensions();
+
+            // Every cell, including walls and corners.
+            for (x, y) in (0..max_x).flat_map(|x| (0..max_y).map(move |y| (x, y))) {
+                let s = Maze2DState::new_from_usize(x, y).unwrap();
+                assert_eq!(precomputed.neighbours(&s), space.neighbours(&s));
+                assert_eq!(precomputed.neighbours_slice(&s), space.neighbours(&s));
+            }
+            assert_eq!(precomputed.size(), space.size());
+        }
+
+        let (start, goal) = (problem.starts()[0], problem.goals()[0]);
+        let precomputed = PrecomputedMaze2DSpace::from(problem.space().clone());
+        let path = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(
+            FloodProblem::new(precomputed.clone(), start).with_goal(goal),
+        )
+        .find_next_goal()
+        .unwrap();
+        let expected = AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem)
+            .find_next_goal()
+            .unwrap();
+        assert!(precomputed.valid_path(&path));
+        assert_eq!(path.cost(), expected.cost());
+    }
+}
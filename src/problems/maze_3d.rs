@@ -0,0 +1,518 @@
+//! Voxel mazes.
+//!
+//! These mirror `Maze2D`, adding a `z` coordinate. ASCII mazes are stacks of
+//! 2D layers separated by blank lines, with `z` growing on each layer.
+
+use derive_more::Display;
+use thiserror::Error;
+
+use crate::problem::BaseProblem;
+use crate::problem::ObjectiveProblem;
+use crate::problems::maze_2d::Coord;
+use crate::problems::maze_2d::CoordIntrinsic;
+use crate::problems::maze_2d::DIAGONAL_COST;
+use crate::problems::maze_2d::ORTHOGONAL_COST;
+use crate::space::Action;
+use crate::space::ObjectiveHeuristic;
+use crate::space::SampleableSpace;
+use crate::space::Space;
+use crate::space::State;
+
+const RANDOM_STATE_MAX_TRIES: usize = 10_000;
+
+pub type Maze3DCost = CoordIntrinsic;
+
+/// The cost of moving along the three axes at once.
+pub(crate) const CUBE_DIAGONAL_COST: Maze3DCost = 173u32; // 1.732050807568877
+
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[display("({x},{y},{z})")]
+pub struct Maze3DState {
+    pub x: Coord,
+    pub y: Coord,
+    pub z: Coord,
+}
+
+impl Maze3DState {
+    pub fn new_from_usize(x: usize, y: usize, z: usize) -> Option<Maze3DState> {
+        let coord = |c: usize| {
+            (c < CoordIntrinsic::MAX as usize).then(|| Coord::new(c as CoordIntrinsic))?
+        };
+
+        Some(Maze3DState {
+            x: coord(x)?,
+            y: coord(y)?,
+            z: coord(z)?,
+        })
+    }
+}
+impl State for Maze3DState {}
+
+/// A move to one of the 26 surrounding cells, as the change on each axis.
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[display("({dx:+},{dy:+},{dz:+})")]
+pub struct Maze3DAction {
+    dx: i8,
+    dy: i8,
+    dz: i8,
+}
+
+impl Action for Maze3DAction {
+    #[inline(always)]
+    fn is_diagonal(&self) -> bool {
+        self.axes() > 1
+    }
+}
+
+impl Maze3DAction {
+    /// The moves along a single axis.
+    pub const ORTHOGONAL: [Maze3DAction; 6] = [
+        Maze3DAction::new(-1, 0, 0),
+        Maze3DAction::new(1, 0, 0),
+        Maze3DAction::new(0, -1, 0),
+        Maze3DAction::new(0, 1, 0),
+        Maze3DAction::new(0, 0, -1),
+        Maze3DAction::new(0, 0, 1),
+    ];
+
+    const fn new(dx: i8, dy: i8, dz: i8) -> Self {
+        Self { dx, dy, dz }
+    }
+
+    /// Every move to a surrounding cell.
+    pub fn all() -> impl Iterator<Item = Maze3DAction> {
+        (-1..=1).flat_map(|dx| {
+            (-1..=1).flat_map(move |dy| {
+                (-1..=1)
+                    .map(move |dz| Maze3DAction::new(dx, dy, dz))
+                    .filter(|a| a.axes() > 0)
+            })
+        })
+    }
+
+    /// The number of axes this action moves along.
+    #[inline(always)]
+    #[must_use]
+    pub fn axes(&self) -> usize {
+        [self.dx, self.dy, self.dz]
+            .iter()
+            .filter(|d| **d != 0)
+            .count()
+    }
+
+    /// The action undoing this one.
+    #[must_use]
+    pub fn opposite(&self) -> Self {
+        Self::new(-self.dx, -self.dy, -self.dz)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Display, PartialEq)]
+pub enum Maze3DCell {
+    #[display("░")]
+    Empty,
+    #[display("█")]
+    Wall,
+}
+
+/// The moves allowed between cells.
+///
+/// - `Six` pairs with `Maze3DHeuristicManhattanDistance`.
+/// - `TwentySix` pairs with `Maze3DHeuristicDiagonalDistance`.
+#[derive(Copy, Clone, Debug, Default, Display, PartialEq, Eq)]
+pub enum Maze3DConnectivity {
+    /// Only moves along a single axis.
+    #[default]
+    #[display("6-connected")]
+    Six,
+    /// Moves to any surrounding cell, including diagonals.
+    #[display("26-connected")]
+    TwentySix,
+}
+
+#[derive(Clone)]
+pub struct Maze3DSpace {
+    /// The cells, indexed as `map[z][y][x]`.
+    pub map: Vec<Vec<Vec<Maze3DCell>>>,
+    connectivity: Maze3DConnectivity,
+}
+
+impl Maze3DSpace {
+    pub fn new_from_map(map: Vec<Vec<Vec<Maze3DCell>>>) -> Self {
+        Self {
+            map,
+            connectivity: Maze3DConnectivity::default(),
+        }
+    }
+
+    /// Sets the moves allowed between cells.
+    #[must_use]
+    pub fn with_connectivity(mut self, connectivity: Maze3DConnectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+    #[inline(always)]
+    pub fn connectivity(&self) -> Maze3DConnectivity {
+        self.connectivity
+    }
+
+    pub fn dimensions(&self) -> (usize, usize, usize) {
+        match self.map.first().and_then(|layer| layer.first()) {
+            Some(row) => (row.len(), self.map[0].len(), self.map.len()),
+            None => (0, 0, 0),
+        }
+    }
+    #[inline(always)]
+    pub(crate) fn at(&self, state: &Maze3DState) -> Maze3DCell {
+        debug_assert!(self.valid(state));
+        self.map[state.z.get() as usize][state.y.get() as usize][state.x.get() as usize]
+    }
+}
+
+impl Space<Maze3DState, Maze3DAction, Maze3DCost> for Maze3DSpace {
+    #[inline(always)]
+    fn apply(&self, state: &Maze3DState, action: &Maze3DAction) -> Option<Maze3DState> {
+        if self.connectivity == Maze3DConnectivity::Six && action.is_diagonal() {
+            return None;
+        }
+
+        let step = |c: Coord, d: i8| Coord::new(c.get().wrapping_add_signed(d as i32));
+        let s = Maze3DState {
+            x: step(state.x, action.dx)?,
+            y: step(state.y, action.dy)?,
+            z: step(state.z, action.dz)?,
+        };
+        (self.valid(&s) && self.at(&s) != Maze3DCell::Wall).then_some(s)
+    }
+
+    #[inline(always)]
+    fn valid(&self, state: &Maze3DState) -> bool {
+        let (max_x, max_y, max_z) = self.dimensions();
+
+        (state.x.get() as usize) < max_x
+            && (state.y.get() as usize) < max_y
+            && (state.z.get() as usize) < max_z
+    }
+
+    /// Every cell is a state, even walls.
+    #[inline(always)]
+    fn size(&self) -> Option<usize> {
+        let (max_x, max_y, max_z) = self.dimensions();
+        Some(max_x * max_y * max_z)
+    }
+
+    #[inline(always)]
+    fn cost(&self, _s: &Maze3DState, a: &Maze3DAction) -> Maze3DCost {
+        match a.axes() {
+            1 => ORTHOGONAL_COST,
+            2 => DIAGONAL_COST,
+            _ => CUBE_DIAGONAL_COST,
+        }
+    }
+
+    fn neighbours(&self, state: &Maze3DState) -> Vec<(Maze3DState, Maze3DAction)> {
+        let actions: Vec<Maze3DAction> = match self.connectivity {
+            Maze3DConnectivity::Six => Maze3DAction::ORTHOGONAL.to_vec(),
+            Maze3DConnectivity::TwentySix => Maze3DAction::all().collect(),
+        };
+        actions
+            .into_iter()
+            .filter_map(|a| Some((self.apply(state, &a)?, a)))
+            .collect()
+    }
+}
+
+impl SampleableSpace<Maze3DState, Maze3DAction, Maze3DCost> for Maze3DSpace {
+    fn random_state<R: rand::Rng>(&self, r: &mut R) -> Option<Maze3DState> {
+        let (max_x, max_y, max_z) = self.dimensions();
+        if max_x * max_y * max_z == 0 {
+            return None;
+        }
+
+        for _tries in 0..RANDOM_STATE_MAX_TRIES {
+            let s = Maze3DState::new_from_usize(
+                r.random_range(0..max_x),
+                r.random_range(0..max_y),
+                r.random_range(0..max_z),
+            )?;
+            if self.at(&s) == Maze3DCell::Empty {
+                return Some(s);
+            }
+        }
+
+        None
+    }
+}
+
+impl std::fmt::Debug for Maze3DSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Maze3D{:?}", self.dimensions())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Maze3DProblem {
+    space: Maze3DSpace,
+    starts: Vec<Maze3DState>,
+    goals: Vec<Maze3DState>,
+}
+
+impl Maze3DProblem {
+    /// Sets the moves allowed between cells.
+    #[must_use]
+    pub fn with_connectivity(mut self, connectivity: Maze3DConnectivity) -> Self {
+        self.space = self.space.with_connectivity(connectivity);
+        self
+    }
+}
+
+impl BaseProblem<Maze3DSpace, Maze3DState, Maze3DAction, Maze3DCost> for Maze3DProblem {
+    fn space(&self) -> &Maze3DSpace {
+        &self.space
+    }
+    fn starts(&self) -> &[Maze3DState] {
+        &self.starts
+    }
+}
+
+impl ObjectiveProblem<Maze3DSpace, Maze3DState, Maze3DAction, Maze3DCost> for Maze3DProblem {
+    fn goals(&self) -> &[Maze3DState] {
+        &self.goals
+    }
+
+    fn randomize<R: rand::Rng>(
+        &mut self,
+        r: &mut R,
+        num_starts: u16,
+        num_goals: u16,
+    ) -> Option<Maze3DProblem> {
+        let mut starts = vec![];
+        let mut goals = vec![];
+        while starts.len() < num_starts as usize {
+            starts.push(self.space.random_state(r)?);
+        }
+        while goals.len() < num_goals as usize {
+            goals.push(self.space.random_state(r)?);
+        }
+
+        Some(Maze3DProblem {
+            space: self.space.clone(),
+            starts,
+            goals,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Maze3DProblemParseError {
+    #[error("Empty input")]
+    EmptyInput,
+    #[error("Invalid character '{ch}' found at ({x},{y},{z})")]
+    InvalidCharacter {
+        ch: char,
+        x: usize,
+        y: usize,
+        z: usize,
+    },
+    #[error("Layer {z} doesn't match the dimensions of the first one")]
+    MismatchedLayer { z: usize },
+}
+
+impl std::str::FromStr for Maze3DProblem {
+    type Err = Maze3DProblemParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let layers: Vec<Vec<&str>> = s
+            .split("\n\n")
+            .map(|layer| layer.lines().collect::<Vec<_>>())
+            .filter(|layer| !layer.is_empty())
+            .collect();
+        if layers.is_empty() || layers[0][0].is_empty() {
+            return Err(Maze3DProblemParseError::EmptyInput);
+        }
+
+        let (max_x, max_y) = (layers[0][0].chars().count(), layers[0].len());
+        let mut map = vec![];
+        let mut starts = vec![];
+        let mut goals = vec![];
+        for (z, layer) in layers.iter().enumerate() {
+            if layer.len() != max_y || layer.iter().any(|l| l.chars().count() != max_x) {
+                return Err(Maze3DProblemParseError::MismatchedLayer { z });
+            }
+
+            let mut cells = vec![];
+            for (y, line) in layer.iter().enumerate() {
+                let mut row = vec![];
+                for (x, ch) in line.chars().enumerate() {
+                    let state = || Maze3DState::new_from_usize(x, y, z).unwrap();
+                    row.push(match ch {
+                        ' ' | '.' => Maze3DCell::Empty,
+                        '#' | '█' => Maze3DCell::Wall,
+                        'S' => {
+                            starts.push(state());
+                            Maze3DCell::Empty
+                        }
+                        'G' => {
+                            goals.push(state());
+                            Maze3DCell::Empty
+                        }
+                        ch => {
+                            return Err(Maze3DProblemParseError::InvalidCharacter { ch, x, y, z });
+                        }
+                    });
+                }
+                cells.push(row);
+            }
+            map.push(cells);
+        }
+
+        Ok(Maze3DProblem {
+            space: Maze3DSpace::new_from_map(map),
+            starts,
+            goals,
+        })
+    }
+}
+
+/// The per-axis differences between two states, sorted.
+#[inline(always)]
+fn sorted_deltas(a: &Maze3DState, b: &Maze3DState) -> [Maze3DCost; 3] {
+    let mut deltas = [
+        a.x.get().abs_diff(b.x.get()),
+        a.y.get().abs_diff(b.y.get()),
+        a.z.get().abs_diff(b.z.get()),
+    ];
+    deltas.sort_unstable();
+    deltas
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "inspect", derive(Clone))]
+pub struct Maze3DHeuristicManhattanDistance;
+
+impl ObjectiveHeuristic<Maze3DSpace, Maze3DState, Maze3DAction, Maze3DCost>
+    for Maze3DHeuristicManhattanDistance
+{
+    /// The distance of following straight lines
+    #[inline(always)]
+    fn h(a: &Maze3DState, b: &Maze3DState) -> Maze3DCost {
+        sorted_deltas(a, b).iter().sum::<Maze3DCost>() * ORTHOGONAL_COST
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "inspect", derive(Clone))]
+pub struct Maze3DHeuristicDiagonalDistance;
+
+impl ObjectiveHeuristic<Maze3DSpace, Maze3DState, Maze3DAction, Maze3DCost>
+    for Maze3DHeuristicDiagonalDistance
+{
+    /// The distance of maximizing useful diagonals
+    #[inline(always)]
+    fn h(a: &Maze3DState, b: &Maze3DState) -> Maze3DCost {
+        let [low, mid, high] = sorted_deltas(a, b);
+
+        low * CUBE_DIAGONAL_COST + (mid - low) * DIAGONAL_COST + (high - mid) * ORTHOGONAL_COST
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    use crate::algorithms::astar::AStarSearch;
+    use crate::algorithms::dijkstra::DijkstraSearch;
+
+    const CUBE: &str = indoc! {"
+        S..
+        ##.
+        ...
+
+        ###
+        ###
+        .##
+
+        ...
+        .#.
+        ..G
+    "};
+
+    #[test]
+    fn parse() {
+        let problem = CUBE.parse::<Maze3DProblem>().unwrap();
+        assert_eq!(problem.space().dimensions(), (3, 3, 3));
+        assert_eq!(problem.space().size(), Some(27));
+        assert_eq!(
+            problem.starts(),
+            [Maze3DState::new_from_usize(0, 0, 0).unwrap()]
+        );
+        assert_eq!(
+            problem.goals(),
+            [Maze3DState::new_from_usize(2, 2, 2).unwrap()]
+        );
+
+        assert!(matches!(
+            "S.\n\n.G.\n".parse::<Maze3DProblem>(),
+            Err(Maze3DProblemParseError::MismatchedLayer { z: 1 })
+        ));
+        assert!(matches!(
+            "S?\n".parse::<Maze3DProblem>(),
+            Err(Maze3DProblemParseError::InvalidCharacter { ch: '?', .. })
+        ));
+    }
+
+    #[test]
+    fn solve() {
+        let problem = CUBE.parse::<Maze3DProblem>().unwrap();
+        let path =
+            AStarSearch::<Maze3DHeuristicManhattanDistance, _, _, _, _, _>::new(problem.clone())
+                .find_next_goal()
+                .unwrap();
+
+        assert!(problem.space().valid_path(&path));
+        assert_eq!(path.start(), Some(problem.starts()[0]));
+        assert_eq!(path.end(), Some(problem.goals()[0]));
+        // Around the first layer, down the only hole and across the last one.
+        assert_eq!(path.cost(), 10 * ORTHOGONAL_COST);
+        assert_eq!(
+            path.cost(),
+            DijkstraSearch::new(problem)
+                .find_next_goal()
+                .unwrap()
+                .cost()
+        );
+    }
+
+    #[test]
+    fn diagonals() {
+        let problem = CUBE
+            .parse::<Maze3DProblem>()
+            .unwrap()
+            .with_connectivity(Maze3DConnectivity::TwentySix);
+        let space = problem.space();
+        let start = problem.starts()[0];
+        assert_eq!(space.neighbours(&start).len(), 1);
+        assert_eq!(Maze3DAction::all().count(), 26);
+
+        let path =
+            AStarSearch::<Maze3DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem.clone())
+                .find_next_goal()
+                .unwrap();
+        assert!(space.valid_path(&path));
+        assert_eq!(
+            path.cost(),
+            DijkstraSearch::new(problem.clone())
+                .find_next_goal()
+                .unwrap()
+                .cost()
+        );
+        assert!(
+            path.cost()
+                >= Maze3DHeuristicDiagonalDistance::h(&problem.starts()[0], &problem.goals()[0])
+        );
+    }
+}
@@ -5,3 +5,7 @@
 //! take us to new states.
 
 pub mod maze_2d;
+pub mod maze_2d_float;
+pub mod maze_2d_jps;
+pub mod maze_2d_precomputed;
+pub mod maze_3d;
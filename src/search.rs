@@ -1,13 +1,26 @@
 use std::fmt::Debug;
 
-use typed_arena::Arena;
-
+use crate::data_structures::chunked_arena::ChunkedArena;
 use crate::space::Action;
-use crate::space::Cost;
+use crate::space::OrdCost;
 use crate::space::Path;
 use crate::space::Space;
 use crate::space::State;
 
+/// Default capacity of the open list of searches.
+pub const DEFAULT_OPEN_CAPACITY: usize = 2048;
+/// Default capacity of the `State -> SearchTreeIndex` map of searches.
+pub const DEFAULT_NODE_MAP_CAPACITY: usize = 2048;
+/// Largest node capacity pre-allocated from `Space::size()`.
+///
+/// Searches rarely explore the whole of a large Space, so growing past this
+/// is left to the collections.
+pub const MAX_NODE_CAPACITY_HINT: usize = 1 << 16;
+/// Number of expansions between deadline checks.
+///
+/// Reading the clock on every expansion would be noticeably slower.
+pub const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
 /// The least-significant bit.
 const LEAST_SIGNIFICANT_BIT: usize = 1usize;
 /// The bit used to track `is_closed: bool` within pointers.
@@ -49,9 +62,13 @@ impl SearchTreeIndex {
         debug_assert!(!self.is_closed());
         self.index |= IS_CLOSED_BIT;
     }
+    pub fn set_open(&mut self) {
+        debug_assert!(self.is_closed());
+        self.index &= !IS_CLOSED_BIT;
+    }
 
     #[inline(always)]
-    fn from_ptr<St: State, A: Action, C: Cost>(ptr: *const SearchTreeNode<St, A, C>) -> Self {
+    fn from_ptr<St: State, A: Action, C: OrdCost>(ptr: *const SearchTreeNode<St, A, C>) -> Self {
         let i = Self::new(ptr as usize);
         debug_assert!(!i.is_closed());
         i
@@ -64,66 +81,112 @@ pub struct SearchTreeNode<St, A, C>
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     pub(crate) parent: Option<(SearchTreeIndex, A)>,
     pub(crate) state: St,
     pub(crate) g: C,
+    /// The heuristic value, cached by searches ranking nodes with it.
+    pub(crate) h: C,
     pub(crate) heap_index: usize,
+    /// The number of actions from a start.
+    ///
+    /// NOTE: This fits in the padding left by `heap_index`.
+    pub(crate) depth: u32,
 }
 
 impl<St, A, C> SearchTreeNode<St, A, C>
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     pub fn new(heap_index: usize, s: St, parent: Option<(SearchTreeIndex, A)>, g: C) -> Self {
         Self {
             parent,
             state: s,
             g,
+            h: C::zero(),
             heap_index,
+            depth: 0,
         }
     }
 
     /// Gives this Node a better path through a new parent.
-    pub fn reach(&mut self, new_parent: (SearchTreeIndex, A), g: C) {
+    pub fn reach(&mut self, new_parent: (SearchTreeIndex, A), g: C, depth: u32) {
         debug_assert!(g < self.g);
         self.parent = Some(new_parent);
         self.g = g;
+        self.depth = depth;
     }
 
-    pub(crate) fn state(&self) -> &St {
+    pub fn state(&self) -> &St {
         &self.state
     }
+
+    #[must_use]
+    pub fn parent(&self) -> Option<(SearchTreeIndex, A)> {
+        self.parent
+    }
+
+    #[must_use]
+    pub fn g(&self) -> C {
+        self.g
+    }
+
+    #[must_use]
+    pub fn h(&self) -> C {
+        self.h
+    }
+
+    #[must_use]
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
 }
 
-pub(crate) struct SearchTree<St, A, C>
+pub struct SearchTree<St, A, C>
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
-    nodes: Arena<SearchTreeNode<St, A, C>>,
+    nodes: ChunkedArena<SearchTreeNode<St, A, C>>,
 }
 
 impl<St, A, C> SearchTree<St, A, C>
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     #[inline(always)]
     #[must_use]
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self {
-            nodes: Arena::<SearchTreeNode<St, A, C>>::new(),
+            nodes: ChunkedArena::<SearchTreeNode<St, A, C>>::new(),
         }
     }
 
+    /// Initializes a Search Tree fitting `capacity` nodes in a single chunk.
     #[inline(always)]
-    pub(crate) fn push(&mut self, node: SearchTreeNode<St, A, C>) -> SearchTreeIndex {
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: ChunkedArena::<SearchTreeNode<St, A, C>>::with_capacity(capacity),
+        }
+    }
+
+    /// Drops all the nodes, keeping their memory for new ones.
+    pub(crate) fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, mut node: SearchTreeNode<St, A, C>) -> SearchTreeIndex {
+        if let Some((parent_index, _a)) = node.parent {
+            node.depth = self[parent_index].depth + 1;
+        }
         let node = self.nodes.alloc(node);
         SearchTreeIndex::from_ptr::<St, A, C>(node as *const _)
     }
@@ -134,19 +197,133 @@ where
         self.nodes.len()
     }
 
+    #[inline(always)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Walks up the Search Tree from a node to its root (a start).
+    ///
+    /// Yields the node itself first. Parent pointers should never form cycles,
+    /// but debug builds check for them to avoid iterating forever.
+    pub fn ancestors(
+        &self,
+        node_index: SearchTreeIndex,
+    ) -> impl Iterator<Item = (SearchTreeIndex, &SearchTreeNode<St, A, C>)> {
+        let len = self.len();
+        std::iter::successors(Some(node_index), move |i| {
+            self[*i].parent.map(|(parent_index, _a)| parent_index)
+        })
+        .enumerate()
+        .map(move |(steps, i)| {
+            debug_assert!(steps < len, "Found a cycle in the Search Tree");
+            (i, &self[i])
+        })
+    }
+
     #[must_use]
     pub fn path<Sp: Space<St, A, C>>(
-        &mut self,
+        &self,
         space: &Sp,
-        mut node_index: SearchTreeIndex,
+        node_index: SearchTreeIndex,
     ) -> Path<St, A, C> {
+        let (path, _root_index) = self.build_path(space, node_index);
+
+        // The costs along the path must add up to the g-value the search
+        // accumulated, otherwise the space or the search bookkeeping is broken.
+        #[cfg(feature = "verify")]
+        {
+            let g = self[node_index].g - self[_root_index].g;
+            assert!(
+                path.cost().approx_eq(&g),
+                "Path cost doesn't match the g-value of its last node: {} != {g}",
+                path.cost()
+            );
+        }
+
+        path
+    }
+
+    /// Reconstructs the path to a node whose g-value may overestimate the
+    /// cost of the path.
+    ///
+    /// Anytime searches improve nodes without updating their sub-trees until
+    /// they get re-expanded, leaving g-values stale below them.
+    #[must_use]
+    pub(crate) fn stale_path<Sp: Space<St, A, C>>(
+        &self,
+        space: &Sp,
+        node_index: SearchTreeIndex,
+    ) -> Path<St, A, C> {
+        let (path, _root_index) = self.build_path(space, node_index);
+
+        #[cfg(feature = "verify")]
+        {
+            let g = self[node_index].g - self[_root_index].g;
+            assert!(
+                path.cost() <= g || path.cost().approx_eq(&g),
+                "Path cost exceeds the g-value of its last node: {} > {g}",
+                path.cost()
+            );
+        }
+
+        path
+    }
+
+    /// Reconstructs the path to a node as `(state, action, g)` steps, with
+    /// the state reached by the action and the cost accumulated up to it.
+    ///
+    /// The root's g-value is the starting cost, so g-values match the ones
+    /// of the nodes unless they went stale.
+    #[must_use]
+    pub fn annotated_path<Sp: Space<St, A, C>>(
+        &self,
+        space: &Sp,
+        node_index: SearchTreeIndex,
+    ) -> Vec<(St, A, C)> {
+        let mut nodes: Vec<_> = self.ancestors(node_index).map(|(_i, n)| n).collect();
+        nodes.reverse();
+
+        let mut g = nodes[0].g;
+        nodes
+            .iter()
+            .filter_map(|n| {
+                let (parent_index, a) = n.parent?;
+                g += space.cost(self[parent_index].state(), &a);
+                Some((*n.state(), a, g))
+            })
+            .collect()
+    }
+
+    /// Follows the parent pointers from a node, returning the path and the
+    /// root it starts at.
+    fn build_path<Sp: Space<St, A, C>>(
+        &self,
+        space: &Sp,
+        mut node_index: SearchTreeIndex,
+    ) -> (Path<St, A, C>, SearchTreeIndex) {
         #[cfg(feature = "coz_profile")]
         coz::scope!("PathReconstruction");
 
         let e = &self[node_index];
-        let mut path = Path::<St, A, C>::new_from_start(*e.state());
+        let mut path = Path::<St, A, C>::with_capacity(*e.state(), e.depth as usize);
+
+        // Parent pointers must not form cycles, but a bug in `reach` could
+        // point a node into its own sub-tree and make this loop forever.
+        #[cfg(debug_assertions)]
+        let mut steps = 0usize;
 
         while let Some((parent_index, a)) = self[node_index].parent {
+            #[cfg(debug_assertions)]
+            {
+                steps += 1;
+                assert!(
+                    steps <= self.len(),
+                    "Found a cycle in the Search Tree while reconstructing a path"
+                );
+            }
+
             let p = &self[parent_index];
             let s = p.state();
             let c: C = space.cost(s, &a);
@@ -158,7 +335,7 @@ where
         }
 
         path.reverse();
-        path
+        (path, node_index)
     }
 }
 
@@ -166,7 +343,7 @@ impl<St, A, C> Default for SearchTree<St, A, C>
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     #[inline(always)]
     fn default() -> Self {
@@ -178,7 +355,7 @@ impl<St, A, C> std::ops::Index<SearchTreeIndex> for SearchTree<St, A, C>
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     type Output = SearchTreeNode<St, A, C>;
 
@@ -196,7 +373,7 @@ impl<St, A, C> std::ops::IndexMut<SearchTreeIndex> for SearchTree<St, A, C>
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     #[inline(always)]
     fn index_mut(&mut self, index: SearchTreeIndex) -> &mut SearchTreeNode<St, A, C> {
@@ -212,9 +389,228 @@ impl<St, A, C> std::fmt::Debug for SearchTree<St, A, C>
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "SearchTree{{({} nodes)}}", self.len())
     }
 }
+
+/// Why a search did or didn't find a path.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SearchOutcome<St, A, C>
+where
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// A path to the next goal.
+    Found(Path<St, A, C>),
+    /// The open list ran out with goals remaining.
+    Unreachable,
+    /// The open list ran out after rejecting paths to goals for being longer
+    /// than the maximum path length.
+    TooLong,
+    /// There are no goals left, either found already or never given.
+    NoGoals,
+    /// The deadline or expansion budget stopped the search before finding a
+    /// goal. Searching again resumes it.
+    Interrupted,
+}
+
+/// A node capacity for searches on a Space, based on its size.
+#[must_use]
+pub fn node_capacity_hint<Sp, St, A, C>(space: &Sp) -> usize
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    space.size().map_or(DEFAULT_NODE_MAP_CAPACITY, |size| {
+        size.min(MAX_NODE_CAPACITY_HINT)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::problems::maze_2d::Maze2DAction;
+    use crate::problems::maze_2d::Maze2DCost;
+    use crate::problems::maze_2d::Maze2DState;
+
+    #[test]
+    fn with_capacity() {
+        type Node = SearchTreeNode<Maze2DState, Maze2DAction, Maze2DCost>;
+
+        let capacity = 1000;
+        let mut tree = SearchTree::<Maze2DState, Maze2DAction, Maze2DCost>::with_capacity(capacity);
+        let s = Maze2DState::new_from_usize(0, 0).unwrap();
+        let indices: Vec<SearchTreeIndex> = (0..capacity)
+            .map(|_| tree.push(SearchTreeNode::new(0, s, None, 0)))
+            .collect();
+        assert_eq!(tree.len(), capacity);
+
+        // All the nodes were placed next to each other in the first chunk.
+        let first = &tree[indices[0]] as *const Node as usize;
+        for (i, index) in indices.iter().enumerate() {
+            let node = &tree[*index] as *const Node as usize;
+            assert_eq!(node, first + i * std::mem::size_of::<Node>());
+        }
+    }
+
+    #[test]
+    fn node_capacity_hint() {
+        use crate::problems::maze_2d::Maze2DSpace;
+
+        let space = Maze2DSpace::new_empty_with_dimensions(20, 30);
+        assert_eq!(super::node_capacity_hint(&space), 600);
+        let space = Maze2DSpace::new_empty_with_dimensions(1000, 1000);
+        assert_eq!(super::node_capacity_hint(&space), MAX_NODE_CAPACITY_HINT);
+    }
+
+    #[test]
+    fn ancestors() {
+        let mut tree = SearchTree::<Maze2DState, Maze2DAction, Maze2DCost>::new();
+        let s0 = Maze2DState::new_from_usize(0, 0).unwrap();
+        let s1 = Maze2DState::new_from_usize(1, 0).unwrap();
+        let s2 = Maze2DState::new_from_usize(2, 0).unwrap();
+
+        let root = tree.push(SearchTreeNode::new(0, s0, None, 0));
+        let child = tree.push(SearchTreeNode::new(
+            0,
+            s1,
+            Some((root, Maze2DAction::Right)),
+            100,
+        ));
+        let grandchild = tree.push(SearchTreeNode::new(
+            0,
+            s2,
+            Some((child, Maze2DAction::Right)),
+            200,
+        ));
+
+        let lineage: Vec<_> = tree
+            .ancestors(grandchild)
+            .map(|(_i, n)| *n.state())
+            .collect();
+        assert_eq!(lineage, vec![s2, s1, s0]);
+
+        let (i, _root) = tree.ancestors(grandchild).last().unwrap();
+        assert_eq!(i, root);
+        assert_eq!(tree.ancestors(root).count(), 1);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Found a cycle in the Search Tree")]
+    fn path_cycle() {
+        use crate::problems::maze_2d::Maze2DCell;
+        use crate::problems::maze_2d::Maze2DSpace;
+
+        let space = Maze2DSpace::new_from_map(vec![vec![Maze2DCell::Empty; 2]]);
+        let mut tree = SearchTree::<Maze2DState, Maze2DAction, Maze2DCost>::new();
+        let s0 = Maze2DState::new_from_usize(0, 0).unwrap();
+        let s1 = Maze2DState::new_from_usize(1, 0).unwrap();
+
+        let a = tree.push(SearchTreeNode::new(0, s0, None, 0));
+        let b = tree.push(SearchTreeNode::new(
+            0,
+            s1,
+            Some((a, Maze2DAction::Right)),
+            100,
+        ));
+        // Corrupt the tree
+        tree[a].parent = Some((b, Maze2DAction::Left));
+
+        let _ = tree.path(&space, b);
+    }
+
+    #[test]
+    fn annotated_path() {
+        use crate::problems::maze_2d::Maze2DCell;
+        use crate::problems::maze_2d::Maze2DSpace;
+
+        let space = Maze2DSpace::new_from_map(vec![vec![Maze2DCell::Empty; 3]; 2]);
+        let mut tree = SearchTree::<Maze2DState, Maze2DAction, Maze2DCost>::new();
+        let s0 = Maze2DState::new_from_usize(0, 0).unwrap();
+        let s1 = Maze2DState::new_from_usize(1, 0).unwrap();
+        let s2 = Maze2DState::new_from_usize(2, 1).unwrap();
+
+        let root = tree.push(SearchTreeNode::new(0, s0, None, 0));
+        let child = tree.push(SearchTreeNode::new(
+            0,
+            s1,
+            Some((root, Maze2DAction::Right)),
+            space.cost(&s0, &Maze2DAction::Right),
+        ));
+        let grandchild = tree.push(SearchTreeNode::new(
+            0,
+            s2,
+            Some((child, Maze2DAction::RightDown)),
+            tree[child].g + space.cost(&s1, &Maze2DAction::RightDown),
+        ));
+
+        let steps = tree.annotated_path(&space, grandchild);
+        assert_eq!(
+            steps.iter().map(|(s, a, _g)| (*s, *a)).collect::<Vec<_>>(),
+            vec![(s1, Maze2DAction::Right), (s2, Maze2DAction::RightDown)]
+        );
+        assert_eq!(steps.last().unwrap().2, tree[grandchild].g);
+
+        let path = tree.path(&space, grandchild);
+        let mut g = 0;
+        for ((_from, _a, c), (_s, _action, step_g)) in path.steps(&space).zip(&steps) {
+            assert!(*step_g > g);
+            assert_eq!(*step_g, g + c);
+            g = *step_g;
+        }
+        assert!(tree.annotated_path(&space, root).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "verify")]
+    fn path_cost_matches_g() {
+        use indoc::indoc;
+
+        use crate::algorithms::astar::AStarSearch;
+        use crate::problems::maze_2d::Maze2DHeuristicDiagonalDistance;
+        use crate::problems::maze_2d::Maze2DProblem;
+
+        let problem = Maze2DProblem::try_from(indoc! {"
+            S...#
+            .##.#
+            ....G
+        "})
+        .unwrap();
+        let mut search =
+            AStarSearch::<Maze2DHeuristicDiagonalDistance, _, _, _, _, _>::new(problem);
+        assert!(search.find_next_goal().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "verify")]
+    #[should_panic(expected = "Path cost doesn't match the g-value")]
+    fn path_cost_mismatch() {
+        use crate::problems::maze_2d::Maze2DCell;
+        use crate::problems::maze_2d::Maze2DSpace;
+        use crate::problems::maze_2d::ORTHOGONAL_COST;
+
+        let space = Maze2DSpace::new_from_map(vec![vec![Maze2DCell::Empty; 2]]);
+        let mut tree = SearchTree::<Maze2DState, Maze2DAction, Maze2DCost>::new();
+        let s0 = Maze2DState::new_from_usize(0, 0).unwrap();
+        let s1 = Maze2DState::new_from_usize(1, 0).unwrap();
+
+        let root = tree.push(SearchTreeNode::new(0, s0, None, 0));
+        // Corrupt g, as if the search used a different cost for the step.
+        let child = tree.push(SearchTreeNode::new(
+            0,
+            s1,
+            Some((root, Maze2DAction::Right)),
+            ORTHOGONAL_COST + 1,
+        ));
+
+        let _ = tree.path(&space, child);
+    }
+}
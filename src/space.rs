@@ -4,11 +4,39 @@ use std::hash::Hash;
 
 use itertools::Itertools;
 use num_traits::SaturatingAdd;
+use num_traits::Zero;
 use num_traits::sign::Unsigned;
+use rustc_hash::FxHashSet;
+use thiserror::Error;
 
-pub trait Action: Copy + Clone + Debug + Display + PartialEq + Eq {}
+pub trait Action: Copy + Clone + Debug + Display + PartialEq + Eq {
+    /// Whether taking this action after `prev` changes direction.
+    ///
+    /// By default any different action is considered a turn.
+    #[must_use]
+    fn turns_from(&self, prev: &Self) -> bool {
+        self != prev
+    }
+
+    /// Whether this action moves diagonally.
+    #[must_use]
+    fn is_diagonal(&self) -> bool {
+        false
+    }
+}
+/// A State in a Space.
+///
+/// Deriving `PartialEq`, `Eq` and `Hash` is usually enough, and allows using
+/// `crate::data_structures::state_set` collections.
 pub trait State: Copy + Clone + Debug + Display + PartialEq + Eq + Hash {}
-pub trait Cost:
+
+/// A cost that can be compared and accumulated.
+///
+/// This is all Dijkstra and A* need to rank nodes, so it allows plugging in
+/// signed or penalty-based cost models.
+///
+/// NOTE: `Sub` is needed to recover `g` from `f` and `h` when re-ranking.
+pub trait OrdCost:
     Copy
     + Clone
     + Debug
@@ -18,44 +46,171 @@ pub trait Cost:
     + PartialOrd
     + Ord
     + SaturatingAdd
-    + Unsigned
+    + Zero
     + num_traits::bounds::UpperBounded
-    + std::ops::Add
+    + std::ops::Add<Output = Self>
     + std::ops::AddAssign
+    + std::ops::Sub<Output = Self>
 {
     fn valid(&self) -> bool {
         *self != Self::max_value()
     }
+
+    /// Checks whether two costs are equal up to rounding errors, like the
+    /// ones of adding the same costs in a different order.
+    ///
+    /// Exact unless overridden.
+    #[inline(always)]
+    #[must_use]
+    fn approx_eq(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// A non-negative cost.
+///
+/// Closing nodes after expanding them (never re-opening them) is only sound
+/// when edges can't have negative costs, so this marks the costs where that
+/// optimization keeps searches optimal.
+pub trait Cost: OrdCost + Unsigned {}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PathJoinError {
+    #[error("Can't join empty paths")]
+    EmptyPath,
+    #[error("The first path doesn't end where the second one starts")]
+    EndpointMismatch,
+}
+
+/// Why a path can't be followed in a Space.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PathError<S: Debug, C: Debug> {
+    #[error("Action #{step} can't be applied on {state:?}")]
+    InvalidAction { step: usize, state: S },
+    #[error("The path ends at {end:?}, but its actions reach {reached:?}")]
+    EndpointMismatch { reached: Option<S>, end: Option<S> },
+    #[error("The path costs {cost:?}, but its actions add up to {actual:?}")]
+    CostMismatch { cost: C, actual: C },
 }
 
+/// A sequence of actions leading from a start state to an end state.
+///
+/// Fields are private so the cost can't drift away from the actions.
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "inspect", derive(Clone))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Path<S, A, C>
 where
     S: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
-    pub start: Option<S>,
-    pub end: Option<S>,
-    pub cost: C,
-    pub actions: Vec<A>,
+    start: Option<S>,
+    end: Option<S>,
+    cost: C,
+    actions: Vec<A>,
 }
 
 impl<S, A, C> Path<S, A, C>
 where
     S: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
+    /// Starts a path reserving space for its actions.
     #[inline(always)]
     #[must_use]
-    pub(crate) fn new_from_start(start: S) -> Self {
+    pub(crate) fn with_capacity(start: S, len: usize) -> Self {
         Self {
             start: Some(start),
             end: Some(start),
             cost: C::zero(),
-            actions: vec![],
+            actions: Vec::with_capacity(len),
+        }
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn start(&self) -> Option<S> {
+        self.start
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn end(&self) -> Option<S> {
+        self.end
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn cost(&self) -> C {
+        self.cost
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn actions(&self) -> &[A] {
+        &self.actions
+    }
+
+    /// Counts the changes of direction along the path.
+    #[must_use]
+    pub fn turn_count(&self) -> usize {
+        self.actions
+            .windows(2)
+            .filter(|w| w[1].turns_from(&w[0]))
+            .count()
+    }
+
+    /// Counts the diagonal steps along the path.
+    #[must_use]
+    pub fn diagonal_step_count(&self) -> usize {
+        self.actions.iter().filter(|a| a.is_diagonal()).count()
+    }
+
+    /// Replays the path in a Space yielding `(from, action, step_cost)` for
+    /// each step.
+    ///
+    /// Stops early if an action can't be applied.
+    pub fn steps<'a, Sp>(&'a self, space: &'a Sp) -> impl Iterator<Item = (S, A, C)> + 'a
+    where
+        Sp: Space<S, A, C>,
+    {
+        self.actions.iter().scan(self.start, move |state, a| {
+            let from = (*state)?;
+            let next = space.apply(&from, a)?;
+            *state = Some(next);
+            Some((from, *a, space.cost(&from, a)))
+        })
+    }
+
+    /// Replays the path in a Space yielding the start and every state
+    /// reached after it.
+    ///
+    /// Stops early if an action can't be applied.
+    pub fn states<'a, Sp>(&'a self, space: &'a Sp) -> impl Iterator<Item = S> + 'a
+    where
+        Sp: Space<S, A, C>,
+    {
+        let reached = self.actions.iter().scan(self.start, move |state, a| {
+            let next = space.apply(&(*state)?, a)?;
+            *state = Some(next);
+            Some(next)
+        });
+        self.start.into_iter().chain(reached)
+    }
+
+    /// Collects the states along the path, or `None` if an action can't be
+    /// applied.
+    #[must_use]
+    pub fn into_state_vec<Sp>(&self, space: &Sp) -> Option<Vec<S>>
+    where
+        Sp: Space<S, A, C>,
+    {
+        let states: Vec<S> = self.states(space).collect();
+        match self.start {
+            Some(_) if states.len() != self.actions.len() + 1 => None,
+            _ => Some(states),
         }
     }
 
@@ -87,6 +242,21 @@ where
         self.cost = self.cost.saturating_add(&c);
     }
 
+    /// Appends a path starting where this one ends.
+    pub fn concat(&mut self, other: &Path<S, A, C>) -> Result<(), PathJoinError> {
+        if self.end.is_none() || other.start.is_none() {
+            return Err(PathJoinError::EmptyPath);
+        }
+        if self.end != other.start {
+            return Err(PathJoinError::EndpointMismatch);
+        }
+
+        self.actions.extend_from_slice(&other.actions);
+        self.end = other.end;
+        self.cost = self.cost.saturating_add(&other.cost);
+        Ok(())
+    }
+
     /// Reverses the Path, likely making it invalid.
     ///
     /// Useful when naturally reconstructing paths in reverse.
@@ -111,7 +281,7 @@ impl<S, A, C> Display for Path<S, A, C>
 where
     S: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         debug_assert!(self.start.is_none() == self.end.is_none());
@@ -141,7 +311,7 @@ pub trait Space<St, A, C>: Clone + std::fmt::Debug
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     #[must_use]
     fn apply(&self, s: &St, a: &A) -> Option<St>;
@@ -153,45 +323,152 @@ where
     // TODO: Check that (St, A) does not incur in a lot of padding.
     #[must_use]
     fn neighbours(&self, s: &St) -> Vec<(St, A)>;
+    /// Calls `f` on every neighbour of a State.
+    ///
+    /// Spaces can override it to avoid allocating the neighbours.
+    fn for_each_neighbour(&self, s: &St, mut f: impl FnMut(St, A)) {
+        for (t, a) in self.neighbours(s) {
+            f(t, a);
+        }
+    }
     /// Verify is a State is valid.
     #[must_use]
     fn valid(&self, s: &St) -> bool;
 
+    /// Applies a sequence of actions, failing on the first invalid one.
+    #[must_use]
+    fn apply_path(&self, start: St, actions: &[A]) -> Option<St> {
+        actions
+            .iter()
+            .try_fold(start, |state, a| self.apply(&state, a))
+    }
+
     #[must_use]
     fn valid_path(&self, p: &Path<St, A, C>) -> bool {
-        if let Some(start) = p.start {
-            // Verify path
-            let mut state: St = start;
-            for a in &p.actions {
-                match self.apply(&state, a) {
-                    Some(new_state) => state = new_state,
-                    None => return false,
-                }
-            }
-            if let Some(end) = p.end {
-                return end == state;
-            }
-            false
-        } else {
+        self.validate_path(p).is_ok()
+    }
+
+    /// Replays a path checking its actions, end and cost.
+    fn validate_path(&self, p: &Path<St, A, C>) -> Result<(), PathError<St, C>> {
+        let Some(start) = p.start else {
             // Empty paths are fine
-            *p == Path::<St, A, C>::new_empty()
+            if *p == Path::<St, A, C>::new_empty() {
+                return Ok(());
+            }
+            return Err(PathError::EndpointMismatch {
+                reached: None,
+                end: p.end,
+            });
+        };
+
+        let mut state = start;
+        let mut cost = C::zero();
+        for (step, a) in p.actions.iter().enumerate() {
+            let Some(next) = self.apply(&state, a) else {
+                return Err(PathError::InvalidAction { step, state });
+            };
+            cost = cost.saturating_add(&self.cost(&state, a));
+            state = next;
+        }
+
+        if p.end != Some(state) {
+            return Err(PathError::EndpointMismatch {
+                reached: Some(state),
+                end: p.end,
+            });
+        }
+        if !p.cost.approx_eq(&cost) {
+            return Err(PathError::CostMismatch {
+                cost: p.cost,
+                actual: cost,
+            });
         }
+        Ok(())
     }
 
     #[must_use]
     fn size(&self) -> Option<usize> {
         None
     }
+}
 
-    #[must_use]
-    fn supports_random_state() -> bool {
-        false
+/// Lets searches borrow a Space instead of owning a copy of it.
+impl<Sp, St, A, C> Space<St, A, C> for &Sp
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[inline(always)]
+    fn apply(&self, s: &St, a: &A) -> Option<St> {
+        (*self).apply(s, a)
+    }
+
+    #[inline(always)]
+    fn cost(&self, s: &St, a: &A) -> C {
+        (*self).cost(s, a)
+    }
+
+    #[inline(always)]
+    fn neighbours(&self, s: &St) -> Vec<(St, A)> {
+        (*self).neighbours(s)
+    }
+
+    #[inline(always)]
+    fn for_each_neighbour(&self, s: &St, f: impl FnMut(St, A)) {
+        (*self).for_each_neighbour(s, f)
     }
+
+    #[inline(always)]
+    fn valid(&self, s: &St) -> bool {
+        (*self).valid(s)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> Option<usize> {
+        (*self).size()
+    }
+}
+
+/// A Space where random states can be sampled.
+///
+/// This is what randomizing problems needs, so only spaces implementing it
+/// can generate random instances.
+pub trait SampleableSpace<St, A, C>: Space<St, A, C>
+where
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    /// Samples a random valid state, or `None` if none was found.
     #[must_use]
-    fn random_state<R: rand::Rng>(&self, _r: &mut R) -> Option<St> {
-        debug_assert!(!Self::supports_random_state());
-        None
+    fn random_state<R: rand::Rng>(&self, r: &mut R) -> Option<St>;
+}
+
+/// Finds all the states reachable from the starts.
+///
+/// Edge costs are ignored.
+#[must_use]
+pub fn flood_fill<Sp, St, A, C>(space: &Sp, starts: &[St]) -> FxHashSet<St>
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    let mut reached = FxHashSet::from_iter(starts.iter().cloned());
+    let mut queue = std::collections::VecDeque::from_iter(starts.iter().cloned());
+
+    while let Some(s) = queue.pop_front() {
+        for (n, _a) in space.neighbours(&s) {
+            if reached.insert(n) {
+                queue.push_back(n);
+            }
+        }
     }
+
+    reached
 }
 
 /// A space that allows computing paths in reverse.
@@ -199,7 +476,7 @@ pub trait ReversibleSpace<St, A, C>: Space<St, A, C> + Clone + std::fmt::Debug
 where
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     /// States that can reach a certain state.
     #[must_use]
@@ -217,12 +494,38 @@ where
     Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     #[must_use]
     fn h(_a: &St, _b: &St) -> C {
         C::zero()
     }
+
+    /// The estimate on a given space, for heuristics that depend on how it's
+    /// set up (like its costs).
+    ///
+    /// Searches rank nodes with this one, which defaults to `h`.
+    #[must_use]
+    fn h_in(_space: &Sp, a: &St, b: &St) -> C {
+        Self::h(a, b)
+    }
+}
+
+/// An estimate of the number of actions needed to move between two states.
+///
+/// Unlike `ObjectiveHeuristic` it ignores costs and doesn't need to be
+/// admissible. Focal searches use it to prefer nodes closer to finishing.
+pub trait DistanceHeuristic<Sp, St, A, C>: std::fmt::Debug
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+    #[must_use]
+    fn d(_a: &St, _b: &St) -> usize {
+        0
+    }
 }
 
 /// A more specific heuristic to move into a set of states satisfying some
@@ -232,10 +535,33 @@ where
     Sp: Space<St, A, C>,
     St: State,
     A: Action,
-    C: Cost,
+    C: OrdCost,
 {
     #[must_use]
     fn h(_s: &St) -> C {
         C::zero()
     }
 }
+
+/// A heuristic estimating every cost as zero, which makes searches
+/// uninformed.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoHeuristic;
+
+impl<Sp, St, A, C> ObjectiveHeuristic<Sp, St, A, C> for NoHeuristic
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+}
+
+impl<Sp, St, A, C> ConditionHeuristic<Sp, St, A, C> for NoHeuristic
+where
+    Sp: Space<St, A, C>,
+    St: State,
+    A: Action,
+    C: OrdCost,
+{
+}